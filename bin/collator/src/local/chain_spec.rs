@@ -1,8 +1,9 @@
 //! Chain specifications.
 
 use local_runtime::{
-    wasm_binary_unwrap, AccountId, AuraConfig, AuraId, BalancesConfig, EVMConfig, GenesisConfig,
-    GrandpaConfig, GrandpaId, LocalNetworkPrecompiles, Signature, SudoConfig, SystemConfig,
+    wasm_binary_unwrap, AccountId, AuraConfig, AuraId, BalancesConfig, BlockRewardConfig,
+    EVMConfig, GenesisConfig, GrandpaConfig, GrandpaId, InitialRewardAmount,
+    InitialRewardDistribution, LocalNetworkPrecompiles, Signature, SudoConfig, SystemConfig,
     VestingConfig,
 };
 use sc_service::ChainType;
@@ -89,6 +90,10 @@ fn testnet_genesis(
                 .collect(),
         },
         vesting: VestingConfig { vesting: vec![] },
+        block_reward: BlockRewardConfig {
+            reward_amount: InitialRewardAmount::get(),
+            reward_config: InitialRewardDistribution::get(),
+        },
         aura: AuraConfig {
             authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
         },
@@ -125,6 +125,10 @@ fn make_genesis(
         parachain_info: ParachainInfoConfig { parachain_id },
         balances: astar_runtime::BalancesConfig { balances },
         vesting: astar_runtime::VestingConfig { vesting: vec![] },
+        block_reward: astar_runtime::BlockRewardConfig {
+            reward_amount: astar_runtime::InitialRewardAmount::get(),
+            reward_config: astar_runtime::InitialRewardDistribution::get(),
+        },
         session: astar_runtime::SessionConfig {
             keys: authorities
                 .iter()
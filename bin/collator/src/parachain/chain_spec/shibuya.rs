@@ -3,10 +3,10 @@
 use cumulus_primitives_core::ParaId;
 use sc_service::ChainType;
 use shibuya_runtime::{
-    wasm_binary_unwrap, AccountId, AuraConfig, AuraId, Balance, BalancesConfig,
-    CollatorSelectionConfig, EVMConfig, GenesisConfig, ParachainInfoConfig, SessionConfig,
-    SessionKeys, ShibuyaNetworkPrecompiles, Signature, SudoConfig, SystemConfig, VestingConfig,
-    SDN,
+    wasm_binary_unwrap, AccountId, AuraConfig, AuraId, Balance, BalancesConfig, BlockRewardConfig,
+    CollatorSelectionConfig, EVMConfig, GenesisConfig, InitialRewardAmount,
+    InitialRewardDistribution, ParachainInfoConfig, SessionConfig, SessionKeys,
+    ShibuyaNetworkPrecompiles, Signature, SudoConfig, SystemConfig, VestingConfig, SDN,
 };
 use sp_core::{sr25519, Pair, Public};
 
@@ -81,6 +81,10 @@ fn make_genesis(
         parachain_info: ParachainInfoConfig { parachain_id },
         balances: BalancesConfig { balances },
         vesting: VestingConfig { vesting: vec![] },
+        block_reward: BlockRewardConfig {
+            reward_amount: InitialRewardAmount::get(),
+            reward_config: InitialRewardDistribution::get(),
+        },
         session: SessionConfig {
             keys: authorities
                 .iter()
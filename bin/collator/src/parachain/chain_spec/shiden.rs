@@ -79,6 +79,10 @@ fn make_genesis(
         parachain_info: ParachainInfoConfig { parachain_id },
         balances: shiden_runtime::BalancesConfig { balances },
         vesting: shiden_runtime::VestingConfig { vesting: vec![] },
+        block_reward: shiden_runtime::BlockRewardConfig {
+            reward_amount: shiden_runtime::InitialRewardAmount::get(),
+            reward_config: shiden_runtime::InitialRewardDistribution::get(),
+        },
         session: shiden_runtime::SessionConfig {
             keys: authorities
                 .iter()
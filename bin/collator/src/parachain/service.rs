@@ -255,6 +255,7 @@ where
         + sp_block_builder::BlockBuilder<Block>
         + frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
         + pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+        + pallet_custom_signatures_rpc::CustomSignaturesRuntimeApi<Block, Nonce, Balance>
         + fp_rpc::EthereumRuntimeRPCApi<Block>
         + cumulus_primitives_core::CollectCollationInfo<Block>,
     sc_client_api::StateBackendFor<TFullBackend<Block>, Block>: sp_api::StateBackend<BlakeTwo256>,
@@ -8,6 +8,7 @@ use fc_rpc::{
 use fc_rpc_core::types::FilterPool;
 use frame_rpc_system::{FullSystem, SystemApi};
 use jsonrpc_pubsub::manager::SubscriptionManager;
+use pallet_custom_signatures_rpc::{CustomSignatures, CustomSignaturesApi};
 use pallet_ethereum::EthereumStorageSchema;
 use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 use sc_client_api::{AuxStore, Backend, BlockchainEvents, StateBackend, StorageProvider};
@@ -89,6 +90,7 @@ where
         + 'static,
     C::Api: frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>
         + pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+        + pallet_custom_signatures_rpc::CustomSignaturesRuntimeApi<Block, Nonce, Balance>
         + fp_rpc::EthereumRuntimeRPCApi<Block>
         + BlockBuilder<Block>,
     P: TransactionPool<Block = Block> + Sync + Send + 'static,
@@ -121,6 +123,10 @@ where
         client.clone(),
     )));
 
+    io.extend_with(CustomSignaturesApi::to_delegate(CustomSignatures::new(
+        client.clone(),
+    )));
+
     let mut overrides_map = BTreeMap::new();
     overrides_map.insert(
         EthereumStorageSchema::V1,
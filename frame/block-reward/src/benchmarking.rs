@@ -0,0 +1,55 @@
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use crate::Pallet as BlockReward;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::traits::EnsureOrigin;
+
+benchmarks! {
+
+    set_block_reward {
+        let amount: BalanceOf<T> = 1_000_000u32.into();
+        let origin = T::UpdateOrigin::successful_origin();
+    }: {
+        BlockReward::<T>::set_block_reward(origin, amount)?;
+    }
+    verify {
+        assert_eq!(RewardAmount::<T>::get(), amount);
+    }
+
+    set_configuration {
+        let config = RewardDistributionConfig {
+            treasury_percent: Perbill::from_percent(30),
+            dapps_percent: Perbill::from_percent(60),
+            collators_percent: Perbill::from_percent(10),
+            burn_percent: Perbill::zero(),
+        };
+        let origin = T::UpdateOrigin::successful_origin();
+    }: {
+        BlockReward::<T>::set_configuration(origin, config)?;
+    }
+    verify {
+        assert_eq!(RewardDistributionConfigStorage::<T>::get(), config);
+    }
+
+    set_adaptive_dapps_share {
+        let adaptive_share = AdaptiveDappsShare {
+            min_dapps_percent: Perbill::from_percent(30),
+            max_dapps_percent: Perbill::from_percent(70),
+            ideal_staked_ratio: Perbill::from_percent(50),
+        };
+        let origin = T::UpdateOrigin::successful_origin();
+    }: {
+        BlockReward::<T>::set_adaptive_dapps_share(origin, Some(adaptive_share))?;
+    }
+    verify {
+        assert_eq!(AdaptiveDappsShareStorage::<T>::get(), Some(adaptive_share));
+    }
+}
+
+impl_benchmark_test_suite!(
+    BlockReward,
+    crate::mock::ExternalityBuilder::build(),
+    crate::mock::TestRuntime,
+);
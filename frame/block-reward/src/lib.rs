@@ -14,7 +14,8 @@
 //! ## Usage
 //!
 //! 1. Pallet should be set as a handler of `OnTimestampSet`.
-//! 2. `OnBlockReward` handler should be defined as an impl of `OnUnbalanced` trait. For example:
+//! 2. Beneficiaries of the block reward should be defined as impls of `OnUnbalanced` trait:
+//!    `TreasuryPayout`, `DappsStakingPayout` and `CollatorsPayout`. For example:
 //! ```nocompile
 //! type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
 //! struct SaveOnTreasury;
@@ -24,43 +25,519 @@
 //!   }
 //! }
 //! ```
-//! 3. Set `RewardAmount` to desiced block reward value in native currency.
+//! 3. Set the initial `RewardAmount` in the genesis config of the pallet.
+//! 4. Set `RewardCalculator` to one of the implementations:
+//!     * `FixedReward` issues `RewardAmount` for each block. Amount can be changed by `UpdateOrigin`
+//!       with `set_block_reward` call.
+//!     * `InflationReward` issues a part of the total issuance according to the yearly inflation rate,
+//!       which can decay in steps over time.
+//! 5. Set the initial `RewardDistributionConfig` in the genesis config of the pallet.
+//!    It can later be changed by `UpdateOrigin` with `set_configuration` call.
+//! 6. Set `DappsStakingTvl` to a `StakedAmountProvider` which returns the total amount staked in
+//!    dapps staking. `UpdateOrigin` can then enable the adaptive dapps share with
+//!    `set_adaptive_dapps_share` call.
+//! 7. Pallet should be added to the `EventHandler` of `pallet_authorship` to record the number
+//!    of blocks authored by each collator. Issuance and authored blocks are kept per era given by
+//!    `CurrentEra` for the last `HistoryDepth` eras. Runtimes with dapps staking should use its
+//!    current era, other runtimes can use `EraOfBlock`.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{One, SaturatedConversion, Saturating, Zero},
+    Perbill, RuntimeDebug,
+};
+use sp_std::marker::PhantomData;
+
 pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migrations;
+pub mod reward_calculator;
+pub use reward_calculator::*;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+/// Index of an era for which the issuance and authored blocks are recorded.
+pub type EraIndex = u32;
+
+/// Shares of the block reward paid out to each of the beneficiaries.
+///
+/// Shares must add up to exactly 100%. Whatever is left after rounding is burned.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct RewardDistributionConfig {
+    /// Share of the block reward paid to the treasury.
+    pub treasury_percent: Perbill,
+    /// Share of the block reward paid to dapps staking.
+    pub dapps_percent: Perbill,
+    /// Share of the block reward paid to the collators.
+    pub collators_percent: Perbill,
+    /// Share of the block reward which is burned.
+    pub burn_percent: Perbill,
+}
+
+impl Default for RewardDistributionConfig {
+    /// Split used by the runtimes before the distribution was moved on-chain.
+    fn default() -> Self {
+        RewardDistributionConfig {
+            treasury_percent: Perbill::from_percent(40),
+            dapps_percent: Perbill::from_percent(50),
+            collators_percent: Perbill::from_percent(10),
+            burn_percent: Perbill::zero(),
+        }
+    }
+}
+
+impl RewardDistributionConfig {
+    /// `true` if the shares add up to exactly 100%, `false` otherwise.
+    pub fn is_consistent(&self) -> bool {
+        let total: u64 = [
+            self.treasury_percent,
+            self.dapps_percent,
+            self.collators_percent,
+            self.burn_percent,
+        ]
+        .iter()
+        .map(|share| share.deconstruct() as u64)
+        .sum();
+
+        total == Perbill::one().deconstruct() as u64
+    }
+}
+
+impl RewardDistributionConfig {
+    /// Returns the config with the dapps share set to `dapps_percent`.
+    ///
+    /// Difference to the configured dapps share is taken from or given to the treasury,
+    /// so the dapps share can't exceed the combined dapps and treasury share.
+    pub fn with_dapps_percent(&self, dapps_percent: Perbill) -> Self {
+        let pool = self.dapps_percent.saturating_add(self.treasury_percent);
+        let dapps_percent = dapps_percent.min(pool);
+
+        RewardDistributionConfig {
+            treasury_percent: pool.saturating_sub(dapps_percent),
+            dapps_percent,
+            ..*self
+        }
+    }
+}
+
+/// Dapps share of the block reward which adapts to the amount staked in dapps staking.
+///
+/// The less is staked, the bigger the dapps share, to attract more stakers.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct AdaptiveDappsShare {
+    /// Dapps share once the staked ratio reaches `ideal_staked_ratio`.
+    pub min_dapps_percent: Perbill,
+    /// Dapps share when nothing is staked.
+    pub max_dapps_percent: Perbill,
+    /// Part of the total issuance staked in dapps staking at which the dapps share is the lowest.
+    pub ideal_staked_ratio: Perbill,
+}
+
+impl AdaptiveDappsShare {
+    /// `true` if the min share doesn't exceed the max share, `false` otherwise.
+    pub fn is_consistent(&self) -> bool {
+        self.min_dapps_percent <= self.max_dapps_percent
+    }
+
+    /// Returns the dapps share for the given staked ratio.
+    ///
+    /// Share is linearly interpolated from `max_dapps_percent` when nothing is staked
+    /// down to `min_dapps_percent` at `ideal_staked_ratio`, and stays there above it.
+    pub fn dapps_percent(&self, staked_ratio: Perbill) -> Perbill {
+        if staked_ratio >= self.ideal_staked_ratio {
+            return self.min_dapps_percent;
+        }
+
+        let progress = Perbill::from_rational(
+            staked_ratio.deconstruct(),
+            self.ideal_staked_ratio.deconstruct(),
+        );
+        let range = self
+            .max_dapps_percent
+            .saturating_sub(self.min_dapps_percent);
+
+        self.max_dapps_percent.saturating_sub(progress * range)
+    }
+}
+
+/// Provides the total amount staked, e.g. in dapps staking.
+pub trait StakedAmountProvider<Balance> {
+    /// Returns the total amount currently staked.
+    fn total_staked() -> Balance;
+}
+
+impl<Balance: Default> StakedAmountProvider<Balance> for () {
+    fn total_staked() -> Balance {
+        Default::default()
+    }
+}
+
+/// Era of `BlockPerEra` blocks, for runtimes without dapps staking.
+///
+/// Eras start at the same blocks as the dapps staking eras, i.e. once `now % BlockPerEra == 1`,
+/// so the first era starts at block 1.
+pub struct EraOfBlock<T, BlockPerEra>(PhantomData<(T, BlockPerEra)>);
+impl<T, BlockPerEra> Get<EraIndex> for EraOfBlock<T, BlockPerEra>
+where
+    T: frame_system::Config,
+    BlockPerEra: Get<T::BlockNumber>,
+{
+    fn get() -> EraIndex {
+        let block_per_era = BlockPerEra::get();
+        if block_per_era.is_zero() {
+            return Zero::zero();
+        }
+
+        let now = frame_system::Pallet::<T>::block_number();
+        let era = now.saturating_add(block_per_era.saturating_sub(One::one())) / block_per_era;
+        era.saturated_into()
+    }
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use frame_support::pallet_prelude::*;
-    use frame_support::traits::{Currency, OnTimestampSet, OnUnbalanced};
+    use frame_support::traits::{
+        Currency, Imbalance, OnTimestampSet, OnUnbalanced, StorageVersion,
+    };
+    use frame_support::{sp_io::KillStorageResult, weights::DispatchClass};
+    use frame_system::pallet_prelude::*;
+    use sp_runtime::{
+        traits::{SaturatedConversion, Saturating},
+        Perbill,
+    };
+
+    use crate::{
+        AdaptiveDappsShare, EraIndex, RewardCalculator, RewardDistributionConfig,
+        StakedAmountProvider, WeightInfo,
+    };
 
     /// The balance type of this pallet.
     pub type BalanceOf<T> =
         <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
+    /// The negative imbalance type of this pallet.
+    pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+        <T as frame_system::Config>::AccountId,
+    >>::NegativeImbalance;
+
+    /// The current storage version.
+    pub(crate) const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     #[pallet::config]
     pub trait Config: frame_system::Config {
         /// The currency trait.
         type Currency: Currency<Self::AccountId>;
 
-        /// Handle block reward as imbalance.
-        type OnBlockReward: OnUnbalanced<
-            <Self::Currency as Currency<Self::AccountId>>::NegativeImbalance,
-        >;
+        /// Handles the treasury share of the block reward.
+        type TreasuryPayout: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// Handles the dapps staking share of the block reward.
+        type DappsStakingPayout: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// Handles the collators share of the block reward.
+        type CollatorsPayout: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+        /// Provides the total amount staked in dapps staking.
+        type DappsStakingTvl: StakedAmountProvider<BalanceOf<Self>>;
+
+        /// Calculates the amount issued for each block.
+        type RewardCalculator: RewardCalculator<Self>;
+
+        /// Provides the era in progress, for which the issuance and authored blocks are recorded.
+        type CurrentEra: Get<EraIndex>;
 
-        /// The amount of issuance for each block.
+        /// Number of eras for which the issuance and authored blocks are kept.
         #[pallet::constant]
-        type RewardAmount: Get<BalanceOf<Self>>;
+        type HistoryDepth: Get<u32>;
+
+        /// Origin which can change the block reward amount and its distribution.
+        type UpdateOrigin: EnsureOrigin<Self::Origin>;
+
+        /// The overarching event type.
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        /// Weight information for extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
     }
 
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(PhantomData<T>);
 
+    /// The amount of issuance for each block.
+    #[pallet::storage]
+    #[pallet::getter(fn reward_amount)]
+    pub type RewardAmount<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    /// Block at which the reward amount was last recalculated by the inflation based calculator.
+    #[pallet::storage]
+    #[pallet::getter(fn last_reward_recalculation)]
+    pub type LastRewardRecalculation<T: Config> = StorageValue<_, T::BlockNumber>;
+
+    /// Shares of the block reward paid out to each of the beneficiaries.
+    #[pallet::storage]
+    #[pallet::getter(fn reward_config)]
+    pub type RewardDistributionConfigStorage<T: Config> =
+        StorageValue<_, RewardDistributionConfig, ValueQuery>;
+
+    /// Dapps share which adapts to the staked ratio. Fixed share from the distribution config is
+    /// used if not set.
+    #[pallet::storage]
+    #[pallet::getter(fn adaptive_dapps_share)]
+    pub type AdaptiveDappsShareStorage<T: Config> = StorageValue<_, AdaptiveDappsShare>;
+
+    /// Amount issued in each era, without the shares which were burned.
+    #[pallet::storage]
+    #[pallet::getter(fn era_issuance)]
+    pub type EraIssuance<T: Config> =
+        StorageMap<_, Twox64Concat, EraIndex, BalanceOf<T>, ValueQuery>;
+
+    /// Number of blocks authored by each collator in an era.
+    #[pallet::storage]
+    #[pallet::getter(fn authored_blocks)]
+    pub type AuthoredBlocks<T: Config> =
+        StorageDoubleMap<_, Twox64Concat, EraIndex, Twox64Concat, T::AccountId, u32, ValueQuery>;
+
+    /// Author of the current block, counted once the block reward is issued.
+    #[pallet::storage]
+    pub(crate) type BlockAuthor<T: Config> = StorageValue<_, T::AccountId>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(crate) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Block reward amount has been changed. \[amount\]
+        BlockRewardChanged(BalanceOf<T>),
+        /// Distribution of the block reward has been changed. \[config\]
+        DistributionConfigurationChanged(RewardDistributionConfig),
+        /// Adaptive dapps share has been changed or disabled. \[adaptive_share\]
+        AdaptiveDappsShareChanged(Option<AdaptiveDappsShare>),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Shares of the distribution don't add up to 100%.
+        InvalidDistributionConfiguration,
+        /// Min dapps share is greater than the max dapps share.
+        InvalidAdaptiveDappsShare,
+    }
+
+    #[pallet::genesis_config]
+    pub struct GenesisConfig<T: Config> {
+        pub reward_amount: BalanceOf<T>,
+        pub reward_config: RewardDistributionConfig,
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: Config> Default for GenesisConfig<T> {
+        fn default() -> Self {
+            Self {
+                reward_amount: Default::default(),
+                reward_config: Default::default(),
+            }
+        }
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
+        fn build(&self) {
+            assert!(
+                self.reward_config.is_consistent(),
+                "Reward distribution shares must add up to 100%"
+            );
+
+            RewardAmount::<T>::put(self.reward_amount);
+            RewardDistributionConfigStorage::<T>::put(self.reward_config);
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Sets the amount of issuance for each block.
+        ///
+        /// The dispatch origin for this call must be `UpdateOrigin`.
+        ///
+        /// # <weight>
+        /// - O(1).
+        /// - One DB write.
+        /// # </weight>
+        #[pallet::weight(T::WeightInfo::set_block_reward())]
+        pub fn set_block_reward(
+            origin: OriginFor<T>,
+            #[pallet::compact] amount: BalanceOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            RewardAmount::<T>::put(amount);
+            Self::deposit_event(Event::<T>::BlockRewardChanged(amount));
+
+            Ok(().into())
+        }
+
+        /// Sets the shares of the block reward paid out to each of the beneficiaries.
+        ///
+        /// The dispatch origin for this call must be `UpdateOrigin`.
+        ///
+        /// # <weight>
+        /// - O(1).
+        /// - One DB write.
+        /// # </weight>
+        #[pallet::weight(T::WeightInfo::set_configuration())]
+        pub fn set_configuration(
+            origin: OriginFor<T>,
+            reward_distro_params: RewardDistributionConfig,
+        ) -> DispatchResultWithPostInfo {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                reward_distro_params.is_consistent(),
+                Error::<T>::InvalidDistributionConfiguration
+            );
+
+            RewardDistributionConfigStorage::<T>::put(reward_distro_params);
+            Self::deposit_event(Event::<T>::DistributionConfigurationChanged(
+                reward_distro_params,
+            ));
+
+            Ok(().into())
+        }
+
+        /// Sets the dapps share which adapts to the staked ratio, or disables it with `None`.
+        ///
+        /// The dispatch origin for this call must be `UpdateOrigin`.
+        ///
+        /// # <weight>
+        /// - O(1).
+        /// - One DB write.
+        /// # </weight>
+        #[pallet::weight(T::WeightInfo::set_adaptive_dapps_share())]
+        pub fn set_adaptive_dapps_share(
+            origin: OriginFor<T>,
+            adaptive_share: Option<AdaptiveDappsShare>,
+        ) -> DispatchResultWithPostInfo {
+            T::UpdateOrigin::ensure_origin(origin)?;
+
+            match adaptive_share {
+                Some(share) => {
+                    ensure!(share.is_consistent(), Error::<T>::InvalidAdaptiveDappsShare);
+                    AdaptiveDappsShareStorage::<T>::put(share);
+                }
+                None => AdaptiveDappsShareStorage::<T>::kill(),
+            }
+            Self::deposit_event(Event::<T>::AdaptiveDappsShareChanged(adaptive_share));
+
+            Ok(().into())
+        }
+    }
+
     impl<Moment, T: Config> OnTimestampSet<Moment> for Pallet<T> {
         fn on_timestamp_set(_moment: Moment) {
-            let inflation = T::Currency::issue(T::RewardAmount::get());
-            T::OnBlockReward::on_unbalanced(inflation);
+            // Timestamp is set after all `on_initialize` hooks, so the era is already updated
+            // for this block.
+            let now = frame_system::Pallet::<T>::block_number();
+            let era = T::CurrentEra::get();
+            let mut weight = T::DbWeight::get().reads_writes(6, 3);
+            if !EraIssuance::<T>::contains_key(era) {
+                weight = weight.saturating_add(Self::remove_expired_records(era));
+            }
+
+            let total_issuance = T::Currency::total_issuance();
+            let inflation = T::Currency::issue(T::RewardCalculator::block_reward(now));
+            weight = weight.saturating_add(T::RewardCalculator::block_reward_weight());
+            Self::distribute_rewards(inflation);
+
+            // Shares dropped by their handlers are burned, only what was deposited is counted.
+            let issued = T::Currency::total_issuance().saturating_sub(total_issuance);
+            EraIssuance::<T>::mutate(era, |era_issuance| {
+                *era_issuance = era_issuance.saturating_add(issued)
+            });
+
+            if let Some(author) = BlockAuthor::<T>::take() {
+                AuthoredBlocks::<T>::mutate(era, author, |blocks| {
+                    *blocks = blocks.saturating_add(1)
+                });
+            }
+
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                weight,
+                DispatchClass::Mandatory,
+            );
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Splits the block reward according to the distribution config and pays out the shares.
+        ///
+        /// Burned share is simply dropped, which reduces the total issuance.
+        pub(crate) fn distribute_rewards(block_reward: NegativeImbalanceOf<T>) {
+            let config = Self::current_reward_config();
+            let total = block_reward.peek();
+
+            let (treasury, rest) = block_reward.split(config.treasury_percent.mul_floor(total));
+            let (dapps, rest) = rest.split(config.dapps_percent.mul_floor(total));
+            let (collators, _burned) = rest.split(config.collators_percent.mul_floor(total));
+
+            T::TreasuryPayout::on_unbalanced(treasury);
+            T::DappsStakingPayout::on_unbalanced(dapps);
+            T::CollatorsPayout::on_unbalanced(collators);
+        }
+
+        /// Removes the records which are out of the history depth once `era` has started.
+        fn remove_expired_records(era: EraIndex) -> Weight {
+            match era.checked_sub(T::HistoryDepth::get()) {
+                Some(expired_era) => {
+                    EraIssuance::<T>::remove(expired_era);
+                    let removed = match AuthoredBlocks::<T>::remove_prefix(expired_era, None) {
+                        KillStorageResult::AllRemoved(removed) => removed,
+                        KillStorageResult::SomeRemaining(removed) => removed,
+                    };
+
+                    T::DbWeight::get().writes((removed as Weight).saturating_add(1))
+                }
+                None => 0,
+            }
         }
+
+        /// Returns the distribution config with the dapps share adapted to the staked ratio,
+        /// if the adaptive share is enabled.
+        pub(crate) fn current_reward_config() -> RewardDistributionConfig {
+            let config = Self::reward_config();
+
+            match Self::adaptive_dapps_share() {
+                Some(adaptive_share) => {
+                    let staked: u128 = T::DappsStakingTvl::total_staked().saturated_into();
+                    let total_issuance: u128 = T::Currency::total_issuance().saturated_into();
+                    let staked_ratio = Perbill::from_rational(staked, total_issuance);
+
+                    config.with_dapps_percent(adaptive_share.dapps_percent(staked_ratio))
+                }
+                None => config,
+            }
+        }
+    }
+
+    impl<T: Config> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Pallet<T> {
+        fn note_author(author: T::AccountId) {
+            // Era may still change in this block, the author is counted in `on_timestamp_set`.
+            BlockAuthor::<T>::put(author);
+
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                T::DbWeight::get().writes(1),
+                DispatchClass::Mandatory,
+            );
+        }
+
+        fn note_uncle(_author: T::AccountId, _age: T::BlockNumber) {}
     }
 }
@@ -0,0 +1,56 @@
+//! Storage migrations for the block reward pallet.
+
+use super::*;
+use frame_support::{
+    traits::{Get, OnRuntimeUpgrade, StorageVersion},
+    weights::Weight,
+};
+use sp_std::marker::PhantomData;
+
+/// Moves the block reward amount from the runtime constant to `RewardAmount` storage.
+///
+/// `InitialRewardAmount` should be set to the value of the constant the runtime used so far.
+/// Migration is executed only once, while the storage version is `0`.
+pub struct MigrateRewardAmountToStorage<T, InitialRewardAmount>(
+    PhantomData<(T, InitialRewardAmount)>,
+);
+
+impl<T: Config, InitialRewardAmount: Get<BalanceOf<T>>> OnRuntimeUpgrade
+    for MigrateRewardAmountToStorage<T, InitialRewardAmount>
+{
+    fn on_runtime_upgrade() -> Weight {
+        if StorageVersion::get::<Pallet<T>>() == 0 {
+            RewardAmount::<T>::put(InitialRewardAmount::get());
+            StorageVersion::new(1).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(1, 2)
+        } else {
+            T::DbWeight::get().reads(1)
+        }
+    }
+}
+
+/// Moves the block reward distribution from the runtime `OnBlockReward` handler to
+/// `RewardDistributionConfigStorage`.
+///
+/// `InitialDistribution` should be set to the split the runtime handler used so far.
+/// Migration is executed only once, while the storage version is `1`, so it must be run after
+/// `MigrateRewardAmountToStorage`.
+pub struct MigrateRewardDistributionToStorage<T, InitialDistribution>(
+    PhantomData<(T, InitialDistribution)>,
+);
+
+impl<T: Config, InitialDistribution: Get<RewardDistributionConfig>> OnRuntimeUpgrade
+    for MigrateRewardDistributionToStorage<T, InitialDistribution>
+{
+    fn on_runtime_upgrade() -> Weight {
+        if StorageVersion::get::<Pallet<T>>() == 1 {
+            RewardDistributionConfigStorage::<T>::put(InitialDistribution::get());
+            StorageVersion::new(2).put::<Pallet<T>>();
+
+            T::DbWeight::get().reads_writes(1, 2)
+        } else {
+            T::DbWeight::get().reads(1)
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{self as pallet_block_reward, EraIndex, NegativeImbalanceOf, StakedAmountProvider};
+
+use frame_support::{
+    construct_runtime, parameter_types,
+    traits::{Currency, Get, OnUnbalanced},
+    weights::constants::RocksDbWeight,
+};
+use sp_core::H256;
+use sp_std::marker::PhantomData;
+use std::cell::RefCell;
+
+use sp_io::TestExternalities;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+};
+
+pub(crate) type AccountId = u64;
+pub(crate) type BlockNumber = u64;
+pub(crate) type Balance = u128;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<TestRuntime>;
+type Block = frame_system::mocking::MockBlock<TestRuntime>;
+
+pub(crate) const EXISTENTIAL_DEPOSIT: Balance = 2;
+pub(crate) const INITIAL_REWARD_AMOUNT: Balance = 1000;
+/// Account which receives the treasury share of the block reward.
+pub(crate) const TREASURY_POT: AccountId = 100;
+/// Account which receives the dapps staking share of the block reward.
+pub(crate) const DAPPS_STAKING_POT: AccountId = 101;
+/// Account which receives the collators share of the block reward.
+pub(crate) const COLLATORS_POT: AccountId = 102;
+pub(crate) const BLOCK_PER_ERA: BlockNumber = 10;
+pub(crate) const HISTORY_DEPTH: u32 = 3;
+
+construct_runtime!(
+    pub enum TestRuntime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        BlockReward: pallet_block_reward::{Pallet, Call, Storage, Config<T>, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+}
+
+impl frame_system::Config for TestRuntime {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type Origin = Origin;
+    type Index = u64;
+    type Call = Call;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type DbWeight = RocksDbWeight;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+}
+
+parameter_types! {
+    pub const MaxLocks: u32 = 4;
+    pub const ExistentialDeposit: Balance = EXISTENTIAL_DEPOSIT;
+}
+
+impl pallet_balances::Config for TestRuntime {
+    type MaxLocks = MaxLocks;
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+/// Deposits the block reward share to the `Pot` account.
+pub struct ToPot<Pot>(PhantomData<Pot>);
+impl<Pot: Get<AccountId>> OnUnbalanced<NegativeImbalanceOf<TestRuntime>> for ToPot<Pot> {
+    fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<TestRuntime>) {
+        Balances::resolve_creating(&Pot::get(), amount);
+    }
+}
+
+parameter_types! {
+    pub const TreasuryPot: AccountId = TREASURY_POT;
+    pub const DappsStakingPot: AccountId = DAPPS_STAKING_POT;
+    pub const CollatorsPot: AccountId = COLLATORS_POT;
+    pub const BlockPerEra: BlockNumber = BLOCK_PER_ERA;
+    pub const HistoryDepth: u32 = HISTORY_DEPTH;
+}
+
+thread_local! {
+    static DAPPS_STAKING_TVL: RefCell<Balance> = RefCell::new(0);
+    static CURRENT_ERA: RefCell<EraIndex> = RefCell::new(0);
+    static COLLATORS_SHARE_DROPPED: RefCell<bool> = RefCell::new(false);
+}
+
+/// Sets the total amount staked in dapps staking.
+pub(crate) fn set_dapps_staking_tvl(staked: Balance) {
+    DAPPS_STAKING_TVL.with(|tvl| *tvl.borrow_mut() = staked);
+}
+
+pub struct MockDappsStakingTvl;
+impl StakedAmountProvider<Balance> for MockDappsStakingTvl {
+    fn total_staked() -> Balance {
+        DAPPS_STAKING_TVL.with(|tvl| *tvl.borrow())
+    }
+}
+
+/// Sets the era in progress, as dapps staking would at the start of an era.
+pub(crate) fn set_current_era(era: EraIndex) {
+    CURRENT_ERA.with(|current_era| *current_era.borrow_mut() = era);
+}
+
+pub struct MockCurrentEra;
+impl Get<EraIndex> for MockCurrentEra {
+    fn get() -> EraIndex {
+        CURRENT_ERA.with(|current_era| *current_era.borrow())
+    }
+}
+
+/// Sets whether the collators share is dropped instead of being deposited to `COLLATORS_POT`.
+pub(crate) fn set_collators_share_dropped(dropped: bool) {
+    COLLATORS_SHARE_DROPPED.with(|share_dropped| *share_dropped.borrow_mut() = dropped);
+}
+
+/// Deposits the collators share to `COLLATORS_POT`, unless it's set to be dropped.
+pub struct MockCollatorsPayout;
+impl OnUnbalanced<NegativeImbalanceOf<TestRuntime>> for MockCollatorsPayout {
+    fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<TestRuntime>) {
+        if !COLLATORS_SHARE_DROPPED.with(|share_dropped| *share_dropped.borrow()) {
+            ToPot::<CollatorsPot>::on_nonzero_unbalanced(amount);
+        }
+    }
+}
+
+impl pallet_block_reward::Config for TestRuntime {
+    type Currency = Balances;
+    type TreasuryPayout = ToPot<TreasuryPot>;
+    type DappsStakingPayout = ToPot<DappsStakingPot>;
+    type CollatorsPayout = MockCollatorsPayout;
+    type DappsStakingTvl = MockDappsStakingTvl;
+    type RewardCalculator = pallet_block_reward::FixedReward;
+    type CurrentEra = MockCurrentEra;
+    type HistoryDepth = HistoryDepth;
+    type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+    type Event = Event;
+    type WeightInfo = ();
+}
+
+pub struct ExternalityBuilder;
+
+impl ExternalityBuilder {
+    pub fn build() -> TestExternalities {
+        set_dapps_staking_tvl(0);
+        set_current_era(0);
+        set_collators_share_dropped(false);
+
+        let mut storage = frame_system::GenesisConfig::default()
+            .build_storage::<TestRuntime>()
+            .unwrap();
+
+        pallet_block_reward::GenesisConfig::<TestRuntime> {
+            reward_amount: INITIAL_REWARD_AMOUNT,
+            reward_config: Default::default(),
+        }
+        .assimilate_storage(&mut storage)
+        .ok();
+
+        let mut ext = TestExternalities::from(storage);
+        ext.execute_with(|| System::set_block_number(1));
+        ext
+    }
+}
+
+/// Used to get the total amount paid out to all of the beneficiaries.
+pub(crate) fn total_paid_out() -> Balance {
+    [TREASURY_POT, DAPPS_STAKING_POT, COLLATORS_POT]
+        .iter()
+        .map(|pot| Balances::free_balance(pot))
+        .sum()
+}
+
+/// Used to get the pallet events.
+pub(crate) fn block_reward_events() -> Vec<crate::Event<TestRuntime>> {
+    System::events()
+        .into_iter()
+        .map(|r| r.event)
+        .filter_map(|e| {
+            if let Event::BlockReward(inner) = e {
+                Some(inner)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,108 @@
+//! Calculation of the amount issued for each block.
+
+use super::*;
+use codec::{Decode, Encode};
+use frame_support::{
+    traits::{Currency, Get},
+    weights::Weight,
+};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{AtLeast32BitUnsigned, SaturatedConversion, Saturating, Zero},
+    Perquintill, RuntimeDebug,
+};
+use sp_std::marker::PhantomData;
+
+/// Calculates the amount issued for each block.
+pub trait RewardCalculator<T: Config> {
+    /// Returns the amount which should be issued for block `now`.
+    fn block_reward(now: T::BlockNumber) -> BalanceOf<T>;
+
+    /// Returns the worst case weight of `block_reward`.
+    fn block_reward_weight() -> Weight;
+}
+
+/// Issues `RewardAmount` for each block, regardless of the total issuance.
+pub struct FixedReward;
+
+impl<T: Config> RewardCalculator<T> for FixedReward {
+    fn block_reward(_now: T::BlockNumber) -> BalanceOf<T> {
+        Pallet::<T>::reward_amount()
+    }
+
+    fn block_reward_weight() -> Weight {
+        // Reads `RewardAmount`.
+        T::DbWeight::get().reads(1)
+    }
+}
+
+/// Parameters of the issuance based inflation.
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct InflationSchedule<BlockNumber> {
+    /// Yearly inflation rate, relative to the total issuance, before any decay step.
+    pub initial_rate: Perquintill,
+    /// Part of the inflation rate which is kept after each decay step.
+    pub decay: Perquintill,
+    /// Number of blocks between two decay steps, counted from genesis. Zero disables the decay.
+    pub decay_period: BlockNumber,
+    /// Expected number of blocks produced in a year.
+    pub blocks_per_year: BlockNumber,
+    /// Number of blocks between two recalculations of the block reward, e.g. a year or an era.
+    pub recalculation_period: BlockNumber,
+}
+
+impl<BlockNumber: AtLeast32BitUnsigned + Copy> InflationSchedule<BlockNumber> {
+    /// Returns the yearly inflation rate at block `now`.
+    pub fn rate_at(&self, now: BlockNumber) -> Perquintill {
+        if self.decay_period.is_zero() {
+            return self.initial_rate;
+        }
+
+        let steps: u32 = (now / self.decay_period).saturated_into();
+        self.initial_rate
+            .saturating_mul(self.decay.saturating_pow(steps as usize))
+    }
+}
+
+/// Issues a part of the total issuance for each block so that the yearly issuance matches the
+/// inflation rate of the `Schedule`.
+///
+/// Block reward is recalculated once every `recalculation_period` blocks and stored as `RewardAmount`.
+/// In between, the stored amount is issued so the reward stays the same during the period.
+/// Amount set with `set_block_reward` is used only until the next recalculation.
+pub struct InflationReward<Schedule>(PhantomData<Schedule>);
+
+impl<T: Config, Schedule: Get<InflationSchedule<T::BlockNumber>>> RewardCalculator<T>
+    for InflationReward<Schedule>
+{
+    fn block_reward(now: T::BlockNumber) -> BalanceOf<T> {
+        let schedule = Schedule::get();
+
+        let recalculation_due = match Pallet::<T>::last_reward_recalculation() {
+            Some(last) => now >= last.saturating_add(schedule.recalculation_period),
+            None => true,
+        };
+        if !recalculation_due {
+            return Pallet::<T>::reward_amount();
+        }
+
+        let blocks_per_year: u128 = schedule.blocks_per_year.saturated_into();
+        let total_issuance: u128 = T::Currency::total_issuance().saturated_into();
+        let yearly_issuance = schedule.rate_at(now) * total_issuance;
+        let reward: BalanceOf<T> = yearly_issuance
+            .checked_div(blocks_per_year)
+            .unwrap_or_default()
+            .saturated_into();
+
+        RewardAmount::<T>::put(reward);
+        LastRewardRecalculation::<T>::put(now);
+
+        reward
+    }
+
+    fn block_reward_weight() -> Weight {
+        // Recalculation reads `LastRewardRecalculation` and the total issuance,
+        // then writes `RewardAmount` and `LastRewardRecalculation`.
+        T::DbWeight::get().reads_writes(2, 2)
+    }
+}
@@ -0,0 +1,561 @@
+use super::{Event, *};
+use frame_support::{
+    assert_noop, assert_ok, parameter_types,
+    traits::{Currency, Get, OnRuntimeUpgrade, OnTimestampSet, StorageVersion},
+    weights::DispatchClass,
+};
+use mock::*;
+use pallet_authorship::EventHandler;
+use sp_runtime::{traits::BadOrigin, Perbill, Perquintill};
+
+parameter_types! {
+    pub const RuntimeRewardAmount: Balance = 3 * INITIAL_REWARD_AMOUNT;
+    pub RuntimeRewardDistribution: RewardDistributionConfig = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(30),
+        dapps_percent: Perbill::from_percent(70),
+        collators_percent: Perbill::zero(),
+        burn_percent: Perbill::zero(),
+    };
+    pub TestInflationSchedule: InflationSchedule<BlockNumber> = InflationSchedule {
+        initial_rate: Perquintill::from_percent(10),
+        decay: Perquintill::from_percent(50),
+        decay_period: 100,
+        blocks_per_year: 1000,
+        recalculation_period: 10,
+    };
+}
+
+/// Adaptive share used by the tests: 30% to 70% of the block reward, ideal ratio at 50% staked.
+fn test_adaptive_share() -> AdaptiveDappsShare {
+    AdaptiveDappsShare {
+        min_dapps_percent: Perbill::from_percent(30),
+        max_dapps_percent: Perbill::from_percent(70),
+        ideal_staked_ratio: Perbill::from_percent(50),
+    }
+}
+
+type TestInflationReward = InflationReward<TestInflationSchedule>;
+
+/// Used to get the block reward calculated by the inflation based calculator.
+fn inflation_block_reward(now: BlockNumber) -> Balance {
+    <TestInflationReward as RewardCalculator<TestRuntime>>::block_reward(now)
+}
+
+#[test]
+fn genesis_sets_reward_amount() {
+    ExternalityBuilder::build().execute_with(|| {
+        assert_eq!(BlockReward::reward_amount(), INITIAL_REWARD_AMOUNT);
+        assert_eq!(BlockReward::reward_config(), Default::default());
+    })
+}
+
+#[test]
+fn reward_is_issued_on_timestamp_set() {
+    ExternalityBuilder::build().execute_with(|| {
+        let total_issuance = Balances::total_issuance();
+
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(total_paid_out(), INITIAL_REWARD_AMOUNT);
+        assert_eq!(
+            Balances::total_issuance(),
+            total_issuance + INITIAL_REWARD_AMOUNT
+        );
+    })
+}
+
+#[test]
+fn reward_calculation_weight_is_registered() {
+    ExternalityBuilder::build().execute_with(|| {
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(
+            System::block_weight().get(DispatchClass::Mandatory).clone(),
+            <FixedReward as RewardCalculator<TestRuntime>>::block_reward_weight()
+                + <TestRuntime as frame_system::Config>::DbWeight::get().reads_writes(6, 3)
+        );
+        assert!(<TestInflationReward as RewardCalculator<TestRuntime>>::block_reward_weight() > 0);
+    })
+}
+
+#[test]
+fn set_block_reward_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        let new_reward_amount = 2 * INITIAL_REWARD_AMOUNT;
+
+        assert_ok!(BlockReward::set_block_reward(
+            Origin::root(),
+            new_reward_amount
+        ));
+        assert_eq!(BlockReward::reward_amount(), new_reward_amount);
+        assert_eq!(
+            block_reward_events(),
+            vec![Event::BlockRewardChanged(new_reward_amount)]
+        );
+
+        // New amount is issued starting with the next block.
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(total_paid_out(), new_reward_amount);
+
+        // Setting the amount to zero stops the issuance.
+        assert_ok!(BlockReward::set_block_reward(Origin::root(), 0));
+        let total_issuance = Balances::total_issuance();
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(Balances::total_issuance(), total_issuance);
+    })
+}
+
+#[test]
+fn set_block_reward_is_not_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        assert_noop!(
+            BlockReward::set_block_reward(Origin::signed(1), 1),
+            BadOrigin
+        );
+        assert_noop!(BlockReward::set_block_reward(Origin::none(), 1), BadOrigin);
+        assert_eq!(BlockReward::reward_amount(), INITIAL_REWARD_AMOUNT);
+    })
+}
+
+#[test]
+fn reward_amount_migration_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        type Migration = migrations::MigrateRewardAmountToStorage<TestRuntime, RuntimeRewardAmount>;
+
+        // Simulate storage prior to the introduction of the reward amount storage.
+        RewardAmount::<TestRuntime>::kill();
+        StorageVersion::new(0).put::<BlockReward>();
+
+        Migration::on_runtime_upgrade();
+        assert_eq!(BlockReward::reward_amount(), 3 * INITIAL_REWARD_AMOUNT);
+        assert_eq!(StorageVersion::get::<BlockReward>(), StorageVersion::new(1));
+
+        // Migration is executed only once.
+        assert_ok!(BlockReward::set_block_reward(Origin::root(), 5));
+        Migration::on_runtime_upgrade();
+        assert_eq!(BlockReward::reward_amount(), 5);
+    })
+}
+
+#[test]
+fn fixed_reward_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        assert_eq!(
+            <FixedReward as RewardCalculator<TestRuntime>>::block_reward(1),
+            INITIAL_REWARD_AMOUNT
+        );
+
+        assert_ok!(BlockReward::set_block_reward(Origin::root(), 5));
+        assert_eq!(
+            <FixedReward as RewardCalculator<TestRuntime>>::block_reward(1),
+            5
+        );
+    })
+}
+
+#[test]
+fn inflation_rate_decays_in_steps() {
+    let schedule = TestInflationSchedule::get();
+
+    assert_eq!(schedule.rate_at(0), Perquintill::from_percent(10));
+    assert_eq!(schedule.rate_at(99), Perquintill::from_percent(10));
+    assert_eq!(schedule.rate_at(100), Perquintill::from_percent(5));
+    assert_eq!(
+        schedule.rate_at(250),
+        Perquintill::from_rational(25u64, 1000u64)
+    );
+
+    // Zero decay period keeps the initial rate.
+    let schedule = InflationSchedule {
+        decay_period: 0,
+        ..schedule
+    };
+    assert_eq!(schedule.rate_at(1_000_000), Perquintill::from_percent(10));
+}
+
+#[test]
+fn inflation_reward_is_recalculated_once_per_period() {
+    ExternalityBuilder::build().execute_with(|| {
+        let _ = Balances::deposit_creating(&1, 1_000_000_000);
+
+        // Reward is calculated on the first use: 10% of the total issuance spread over a year.
+        assert_eq!(inflation_block_reward(1), 100_000);
+        assert_eq!(BlockReward::reward_amount(), 100_000);
+        assert_eq!(BlockReward::last_reward_recalculation(), Some(1));
+
+        // Reward stays the same until the end of the period, even though the issuance changed.
+        let _ = Balances::deposit_creating(&1, 1_000_000_000);
+        assert_eq!(inflation_block_reward(10), 100_000);
+
+        assert_eq!(inflation_block_reward(11), 200_000);
+        assert_eq!(BlockReward::last_reward_recalculation(), Some(11));
+
+        // Rate is halved after the decay step.
+        assert_eq!(inflation_block_reward(100), 100_000);
+    })
+}
+
+#[test]
+fn default_reward_config_is_consistent() {
+    let config = RewardDistributionConfig::default();
+    assert!(config.is_consistent());
+
+    let config = RewardDistributionConfig {
+        burn_percent: Perbill::from_percent(1),
+        ..config
+    };
+    assert!(!config.is_consistent());
+
+    let config = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(39),
+        ..config
+    };
+    assert!(config.is_consistent());
+}
+
+#[test]
+fn reward_is_distributed_according_to_config() {
+    ExternalityBuilder::build().execute_with(|| {
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 400);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 500);
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 100);
+    })
+}
+
+#[test]
+fn set_configuration_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        let config = RewardDistributionConfig {
+            treasury_percent: Perbill::from_percent(15),
+            dapps_percent: Perbill::from_percent(60),
+            collators_percent: Perbill::from_percent(5),
+            burn_percent: Perbill::from_percent(20),
+        };
+
+        assert_ok!(BlockReward::set_configuration(Origin::root(), config));
+        assert_eq!(BlockReward::reward_config(), config);
+        assert_eq!(
+            block_reward_events(),
+            vec![Event::DistributionConfigurationChanged(config)]
+        );
+
+        // Burned share isn't issued at all.
+        let total_issuance = Balances::total_issuance();
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 150);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 600);
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 50);
+        assert_eq!(Balances::total_issuance(), total_issuance + 800);
+    })
+}
+
+#[test]
+fn set_configuration_is_not_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        let config = RewardDistributionConfig {
+            burn_percent: Perbill::from_percent(10),
+            ..Default::default()
+        };
+
+        assert_noop!(
+            BlockReward::set_configuration(Origin::signed(1), Default::default()),
+            BadOrigin
+        );
+        assert_noop!(
+            BlockReward::set_configuration(Origin::root(), config),
+            Error::<TestRuntime>::InvalidDistributionConfiguration
+        );
+        assert_eq!(BlockReward::reward_config(), Default::default());
+    })
+}
+
+#[test]
+fn rounding_remainder_is_burned() {
+    ExternalityBuilder::build().execute_with(|| {
+        let config = RewardDistributionConfig {
+            treasury_percent: Perbill::from_rational(1u32, 3u32),
+            dapps_percent: Perbill::from_rational(1u32, 3u32),
+            collators_percent: Perbill::from_rational(1u32, 3u32),
+            burn_percent: Perbill::from_parts(1),
+        };
+        assert_ok!(BlockReward::set_configuration(Origin::root(), config));
+
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 333);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 333);
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 333);
+    })
+}
+
+#[test]
+fn reward_distribution_migration_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        type Migration =
+            migrations::MigrateRewardDistributionToStorage<TestRuntime, RuntimeRewardDistribution>;
+
+        // Simulate storage prior to the introduction of the distribution storage.
+        RewardDistributionConfigStorage::<TestRuntime>::kill();
+        StorageVersion::new(1).put::<BlockReward>();
+
+        Migration::on_runtime_upgrade();
+        assert_eq!(
+            BlockReward::reward_config(),
+            RuntimeRewardDistribution::get()
+        );
+        assert_eq!(StorageVersion::get::<BlockReward>(), StorageVersion::new(2));
+
+        // Migration is executed only once.
+        assert_ok!(BlockReward::set_configuration(
+            Origin::root(),
+            Default::default()
+        ));
+        Migration::on_runtime_upgrade();
+        assert_eq!(BlockReward::reward_config(), Default::default());
+    })
+}
+
+#[test]
+fn adaptive_dapps_percent_is_interpolated() {
+    let adaptive_share = test_adaptive_share();
+
+    assert_eq!(
+        adaptive_share.dapps_percent(Perbill::zero()),
+        Perbill::from_percent(70)
+    );
+    assert_eq!(
+        adaptive_share.dapps_percent(Perbill::from_percent(25)),
+        Perbill::from_percent(50)
+    );
+    assert_eq!(
+        adaptive_share.dapps_percent(Perbill::from_percent(50)),
+        Perbill::from_percent(30)
+    );
+    assert_eq!(
+        adaptive_share.dapps_percent(Perbill::from_percent(90)),
+        Perbill::from_percent(30)
+    );
+
+    // Zero ideal ratio always yields the min share.
+    let adaptive_share = AdaptiveDappsShare {
+        ideal_staked_ratio: Perbill::zero(),
+        ..adaptive_share
+    };
+    assert_eq!(
+        adaptive_share.dapps_percent(Perbill::zero()),
+        Perbill::from_percent(30)
+    );
+}
+
+#[test]
+fn with_dapps_percent_takes_difference_from_treasury() {
+    let config = RewardDistributionConfig::default();
+
+    let adjusted = config.with_dapps_percent(Perbill::from_percent(70));
+    assert_eq!(adjusted.dapps_percent, Perbill::from_percent(70));
+    assert_eq!(adjusted.treasury_percent, Perbill::from_percent(20));
+    assert_eq!(adjusted.collators_percent, config.collators_percent);
+    assert!(adjusted.is_consistent());
+
+    // Dapps share is capped by the combined dapps and treasury share.
+    let adjusted = config.with_dapps_percent(Perbill::from_percent(95));
+    assert_eq!(adjusted.dapps_percent, Perbill::from_percent(90));
+    assert_eq!(adjusted.treasury_percent, Perbill::zero());
+    assert!(adjusted.is_consistent());
+}
+
+#[test]
+fn set_adaptive_dapps_share_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        let adaptive_share = test_adaptive_share();
+
+        assert_ok!(BlockReward::set_adaptive_dapps_share(
+            Origin::root(),
+            Some(adaptive_share)
+        ));
+        assert_eq!(BlockReward::adaptive_dapps_share(), Some(adaptive_share));
+
+        assert_ok!(BlockReward::set_adaptive_dapps_share(Origin::root(), None));
+        assert_eq!(BlockReward::adaptive_dapps_share(), None);
+        assert_eq!(
+            block_reward_events(),
+            vec![
+                Event::AdaptiveDappsShareChanged(Some(adaptive_share)),
+                Event::AdaptiveDappsShareChanged(None),
+            ]
+        );
+    })
+}
+
+#[test]
+fn set_adaptive_dapps_share_is_not_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        let adaptive_share = AdaptiveDappsShare {
+            min_dapps_percent: Perbill::from_percent(80),
+            ..test_adaptive_share()
+        };
+
+        assert_noop!(
+            BlockReward::set_adaptive_dapps_share(Origin::signed(1), None),
+            BadOrigin
+        );
+        assert_noop!(
+            BlockReward::set_adaptive_dapps_share(Origin::root(), Some(adaptive_share)),
+            Error::<TestRuntime>::InvalidAdaptiveDappsShare
+        );
+    })
+}
+
+#[test]
+fn reward_is_distributed_according_to_staked_ratio() {
+    ExternalityBuilder::build().execute_with(|| {
+        assert_ok!(BlockReward::set_adaptive_dapps_share(
+            Origin::root(),
+            Some(test_adaptive_share())
+        ));
+        let _ = Balances::deposit_creating(&1, 1_000_000);
+
+        // Nothing is staked, dapps get the max share.
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 700);
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 200);
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 100);
+
+        // Ideal ratio is reached, dapps get the min share.
+        set_dapps_staking_tvl(Balances::total_issuance() / 2);
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 700 + 300);
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 200 + 600);
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 100 + 100);
+
+        // Disabling the adaptive share restores the configured split.
+        assert_ok!(BlockReward::set_adaptive_dapps_share(Origin::root(), None));
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(Balances::free_balance(&DAPPS_STAKING_POT), 1000 + 500);
+        assert_eq!(Balances::free_balance(&TREASURY_POT), 800 + 400);
+    })
+}
+
+#[test]
+fn authored_blocks_are_counted_per_era() {
+    ExternalityBuilder::build().execute_with(|| {
+        for author in &[1, 1, 2] {
+            BlockReward::note_author(*author);
+            BlockReward::on_timestamp_set(0u64);
+        }
+
+        set_current_era(1);
+        BlockReward::note_author(1);
+        BlockReward::on_timestamp_set(0u64);
+
+        // Author is counted only once.
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(BlockReward::authored_blocks(0, 1), 2);
+        assert_eq!(BlockReward::authored_blocks(0, 2), 1);
+        assert_eq!(BlockReward::authored_blocks(1, 1), 1);
+        assert_eq!(BlockReward::authored_blocks(1, 2), 0);
+    })
+}
+
+#[test]
+fn block_is_recorded_in_era_started_by_it() {
+    ExternalityBuilder::build().execute_with(|| {
+        // Author is noted before dapps staking starts the new era at the boundary block.
+        System::set_block_number(BLOCK_PER_ERA + 1);
+        BlockReward::note_author(1);
+        set_current_era(1);
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(BlockReward::authored_blocks(0, 1), 0);
+        assert_eq!(BlockReward::authored_blocks(1, 1), 1);
+        assert_eq!(BlockReward::era_issuance(0), 0);
+        assert_eq!(BlockReward::era_issuance(1), INITIAL_REWARD_AMOUNT);
+    })
+}
+
+#[test]
+fn era_of_block_starts_with_dapps_staking_era() {
+    ExternalityBuilder::build().execute_with(|| {
+        type TestEra = EraOfBlock<TestRuntime, BlockPerEra>;
+
+        for (block, era) in [
+            (0, 0),
+            (1, 1),
+            (BLOCK_PER_ERA, 1),
+            (BLOCK_PER_ERA + 1, 2),
+            (2 * BLOCK_PER_ERA, 2),
+            (2 * BLOCK_PER_ERA + 1, 3),
+        ]
+        .iter()
+        {
+            System::set_block_number(*block);
+            assert_eq!(TestEra::get(), *era);
+        }
+    })
+}
+
+#[test]
+fn issuance_is_recorded_per_era() {
+    ExternalityBuilder::build().execute_with(|| {
+        BlockReward::on_timestamp_set(0u64);
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(BlockReward::era_issuance(0), 2 * INITIAL_REWARD_AMOUNT);
+
+        // Burned share isn't counted as issued.
+        assert_ok!(BlockReward::set_configuration(
+            Origin::root(),
+            RewardDistributionConfig {
+                treasury_percent: Perbill::from_percent(75),
+                burn_percent: Perbill::from_percent(25),
+                dapps_percent: Perbill::zero(),
+                collators_percent: Perbill::zero(),
+            }
+        ));
+        set_current_era(1);
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(BlockReward::era_issuance(0), 2 * INITIAL_REWARD_AMOUNT);
+        assert_eq!(BlockReward::era_issuance(1), 750);
+    })
+}
+
+#[test]
+fn dropped_share_is_not_counted_as_issued() {
+    ExternalityBuilder::build().execute_with(|| {
+        let total_issuance = Balances::total_issuance();
+        set_collators_share_dropped(true);
+
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(Balances::free_balance(&COLLATORS_POT), 0);
+        assert_eq!(BlockReward::era_issuance(0), 900);
+        assert_eq!(Balances::total_issuance(), total_issuance + 900);
+    })
+}
+
+#[test]
+fn records_out_of_history_depth_are_removed() {
+    ExternalityBuilder::build().execute_with(|| {
+        for era in 0..HISTORY_DEPTH {
+            set_current_era(era);
+            BlockReward::note_author(1);
+            BlockReward::on_timestamp_set(0u64);
+        }
+
+        // Records are removed only at the start of an era.
+        BlockReward::on_timestamp_set(0u64);
+        assert_eq!(BlockReward::authored_blocks(0, 1), 1);
+
+        // Era which is now out of the history depth is removed.
+        set_current_era(HISTORY_DEPTH);
+        BlockReward::on_timestamp_set(0u64);
+
+        assert_eq!(BlockReward::authored_blocks(0, 1), 0);
+        assert_eq!(BlockReward::era_issuance(0), 0);
+        assert_eq!(BlockReward::authored_blocks(1, 1), 1);
+        assert_eq!(BlockReward::era_issuance(1), INITIAL_REWARD_AMOUNT);
+    })
+}
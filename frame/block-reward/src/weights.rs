@@ -0,0 +1,57 @@
+//! Weights for `pallet_block_reward`
+//!
+//! Storage accesses follow the benchmarks in `benchmarking.rs`. Execution times are estimates
+//! until the weights are generated with the benchmark CLI.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_block_reward.
+pub trait WeightInfo {
+    fn set_block_reward() -> Weight;
+    fn set_configuration() -> Weight;
+    fn set_adaptive_dapps_share() -> Weight;
+}
+
+/// Weights for pallet_block_reward using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: BlockReward RewardAmount (r:0 w:1)
+	fn set_block_reward() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: BlockReward RewardDistributionConfigStorage (r:0 w:1)
+	fn set_configuration() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: BlockReward AdaptiveDappsShareStorage (r:0 w:1)
+	fn set_adaptive_dapps_share() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	// Storage: BlockReward RewardAmount (r:0 w:1)
+	fn set_block_reward() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Storage: BlockReward RewardDistributionConfigStorage (r:0 w:1)
+	fn set_configuration() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Storage: BlockReward AdaptiveDappsShareStorage (r:0 w:1)
+	fn set_adaptive_dapps_share() -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
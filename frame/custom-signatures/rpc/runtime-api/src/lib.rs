@@ -0,0 +1,24 @@
+//! Runtime API definition for custom signatures pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use pallet_custom_signatures::{PayloadError, SigningPayload};
+
+sp_api::decl_runtime_apis! {
+    /// API to build and check the payloads of calls signed by custom signatures.
+    pub trait CustomSignaturesApi<Index, Balance> where
+        Index: Codec,
+        Balance: Codec,
+    {
+        /// Returns the payload which the signer of the SCALE encoded pallet `call` signs, the
+        /// signature of the call is a placeholder of the actual one.
+        fn signing_payload(call: Vec<u8>) -> Result<SigningPayload<Index, Balance>, PayloadError>;
+
+        /// Checks the SCALE encoded pallet `call` without dispatching it, returns the check
+        /// which fails.
+        fn dry_run(call: Vec<u8>) -> Result<(), PayloadError>;
+    }
+}
@@ -0,0 +1,192 @@
+//! RPC interface for the custom signatures pallet.
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use pallet_custom_signatures_rpc_runtime_api::CustomSignaturesApi as CustomSignaturesRuntimeApi;
+use pallet_custom_signatures_rpc_runtime_api::PayloadError;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H256};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use std::sync::Arc;
+
+/// Payload which the signer signs to authorize a call.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SigningPayload<Index> {
+    /// Message which is verified by the runtime signature.
+    pub message: Bytes,
+    /// Bytes which the wallet signs for the message.
+    pub signable: Bytes,
+    /// Digest of the message which the signature is made over.
+    pub digest: H256,
+    /// Nonce which the call is signed with.
+    pub nonce: Index,
+    /// Fee of the call, charged to the sponsor for sponsored calls.
+    pub fee: NumberOrHex,
+}
+
+/// Outcome of the dry run of a signed call.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResult {
+    /// Whether all the checks pass.
+    pub valid: bool,
+    /// Name of the check which fails.
+    pub error: Option<String>,
+    /// Explanation of the failed check.
+    pub reason: Option<String>,
+}
+
+impl From<std::result::Result<(), PayloadError>> for DryRunResult {
+    fn from(result: std::result::Result<(), PayloadError>) -> Self {
+        match result {
+            Ok(()) => DryRunResult {
+                valid: true,
+                error: None,
+                reason: None,
+            },
+            Err(err) => DryRunResult {
+                valid: false,
+                error: Some(format!("{:?}", err)),
+                reason: Some(explain(err).into()),
+            },
+        }
+    }
+}
+
+/// Custom signatures RPC methods.
+#[rpc]
+pub trait CustomSignaturesApi<BlockHash, Index> {
+    /// Returns the message to sign, its digest, the nonce and the fee of the SCALE encoded
+    /// pallet `call`, which is signed by a placeholder of the actual signature.
+    ///
+    /// Single, sponsored and batch calls are supported, the placeholder selects the message
+    /// format and its length is charged by the fee.
+    #[rpc(name = "customSignatures_signingPayload")]
+    fn signing_payload(&self, call: Bytes, at: Option<BlockHash>) -> Result<SigningPayload<Index>>;
+
+    /// Checks the SCALE encoded pallet `call` without submitting it, explains which check
+    /// fails.
+    #[rpc(name = "customSignatures_dryRun")]
+    fn dry_run(&self, call: Bytes, at: Option<BlockHash>) -> Result<DryRunResult>;
+}
+
+/// An implementation of custom signatures specific RPC methods.
+pub struct CustomSignatures<C, B> {
+    client: Arc<C>,
+    _marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> CustomSignatures<C, B> {
+    /// Create new `CustomSignatures` with the given reference to the client.
+    pub fn new(client: Arc<C>) -> Self {
+        CustomSignatures {
+            client,
+            _marker: Default::default(),
+        }
+    }
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+    /// The call payload is rejected.
+    PayloadError,
+    /// The call to runtime failed.
+    RuntimeError,
+}
+
+impl From<Error> for i64 {
+    fn from(e: Error) -> i64 {
+        match e {
+            Error::PayloadError => 1,
+            Error::RuntimeError => 2,
+        }
+    }
+}
+
+/// Returns the explanation of the failed check.
+fn explain(err: PayloadError) -> &'static str {
+    match err {
+        PayloadError::CallDecodeFailure => "Call can't be decoded by the runtime.",
+        PayloadError::SignatureDecodeFailure => {
+            "Signature can't be decoded as the runtime signature type."
+        }
+        PayloadError::BadNonce => "Nonce isn't the current nonce of the signer account.",
+        PayloadError::Expired => "Current block is past the block the call is valid until.",
+        PayloadError::OutdatedPayload => {
+            "Signature is made for an outdated payload format, sign the current one."
+        }
+        PayloadError::InvalidSignature => "Signature isn't made by the signer for the payload.",
+        PayloadError::CannotPayFee => "Signer is unable to pay the call fee.",
+        PayloadError::UnsupportedFormat => {
+            "Signature is made for a message format which the runtime doesn't support."
+        }
+        PayloadError::EmptyBatch => "Batch contains no calls.",
+        PayloadError::MaxCallFeeExceeded => {
+            "Call fee exceeds the maximum call fee of the sponsorship terms."
+        }
+    }
+}
+
+fn runtime_error(message: &str, e: impl std::fmt::Debug) -> RpcError {
+    RpcError {
+        code: ErrorCode::ServerError(Error::RuntimeError.into()),
+        message: message.into(),
+        data: Some(format!("{:?}", e).into()),
+    }
+}
+
+impl<C, Block, Index, Balance> CustomSignaturesApi<<Block as BlockT>::Hash, Index>
+    for CustomSignatures<C, Block>
+where
+    Block: BlockT,
+    C: 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: CustomSignaturesRuntimeApi<Block, Index, Balance>,
+    Index: Codec,
+    Balance: Codec + Into<NumberOrHex>,
+{
+    fn signing_payload(
+        &self,
+        call: Bytes,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> Result<SigningPayload<Index>> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        let payload = api
+            .signing_payload(&at, call.to_vec())
+            .map_err(|e| runtime_error("Unable to build signing payload.", e))?
+            .map_err(|err| RpcError {
+                code: ErrorCode::ServerError(Error::PayloadError.into()),
+                message: explain(err).into(),
+                data: Some(format!("{:?}", err).into()),
+            })?;
+
+        Ok(SigningPayload {
+            message: payload.message.into(),
+            signable: payload.signable.into(),
+            digest: payload.digest.into(),
+            nonce: payload.nonce,
+            fee: payload.fee.into(),
+        })
+    }
+
+    fn dry_run(&self, call: Bytes, at: Option<<Block as BlockT>::Hash>) -> Result<DryRunResult> {
+        let api = self.client.runtime_api();
+        let at = BlockId::hash(at.unwrap_or_else(||
+            // If the block hash is not supplied assume the best block.
+            self.client.info().best_hash));
+
+        let result = api
+            .dry_run(&at, call.to_vec())
+            .map_err(|e| runtime_error("Unable to dry run signed call.", e))?;
+
+        Ok(result.into())
+    }
+}
@@ -0,0 +1,119 @@
+//! Bitcoin signed messages compatibility instances.
+
+use crate::{MessageFormat, SignatureFormat};
+use codec::{Decode, Encode};
+use sp_core::ecdsa;
+use sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::sha2_256};
+use sp_runtime::traits::{IdentifyAccount, Lazy, Verify};
+use sp_runtime::MultiSignature;
+use sp_std::prelude::*;
+
+/// Bitcoin signed message signature type, the header byte is followed by `r` and `s`.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, scale_info::TypeInfo)]
+pub struct BitcoinSignature(pub [u8; 65]);
+
+impl sp_std::fmt::Debug for BitcoinSignature {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+        write!(f, "BitcoinSignature({:?})", &self.0[..])
+    }
+}
+
+impl sp_std::convert::TryFrom<Vec<u8>> for BitcoinSignature {
+    type Error = ();
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.len() == 65 {
+            let mut inner = [0u8; 65];
+            inner.copy_from_slice(&data[..]);
+            Ok(BitcoinSignature(inner))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl SignatureFormat for BitcoinSignature {
+    fn message_format(&self) -> MessageFormat {
+        MessageFormat::Scale
+    }
+
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        signable_message(message)
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        sha2_256(&sha2_256(&signable_message(message)))
+    }
+}
+
+/// Constructs the message that Bitcoin RPC's `signmessage` would sign.
+///
+/// Note: sign hex encoded message hash, so it's readable in the wallet.
+pub fn signable_message(what: &[u8]) -> Vec<u8> {
+    let hash = hex_encode(&sha2_256(what));
+    let mut v = b"\x18Bitcoin Signed Message:\n".to_vec();
+    v.push(hash.len() as u8);
+    v.extend_from_slice(&hash[..]);
+    v
+}
+
+/// Lower case hex encoding of `data`.
+pub(crate) fn hex_encode(data: &[u8]) -> Vec<u8> {
+    const DIGITS: &[u8] = b"0123456789abcdef";
+    let mut encoded = Vec::with_capacity(data.len() * 2);
+    for byte in data {
+        encoded.push(DIGITS[(byte >> 4) as usize]);
+        encoded.push(DIGITS[(byte & 0x0f) as usize]);
+    }
+    encoded
+}
+
+/// Attempts to recover the public key from a message signature signed by using
+/// the Bitcoin RPC's `signmessage`.
+///
+/// Header bytes of both legacy (BIP-137) and SegWit addresses are accepted.
+impl Verify for BitcoinSignature {
+    type Signer = <MultiSignature as Verify>::Signer;
+
+    fn verify<L: Lazy<[u8]>>(
+        &self,
+        mut msg: L,
+        account: &<Self::Signer as IdentifyAccount>::AccountId,
+    ) -> bool {
+        let header = self.0[0];
+        if !(27..=42).contains(&header) {
+            return false;
+        }
+
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(&self.0[1..]);
+        signature[64] = (header - 27) & 3;
+
+        let msg = self.message_digest(msg.get());
+        match secp256k1_ecdsa_recover_compressed(&signature, &msg).ok() {
+            Some(public) => {
+                let signer = Self::Signer::from(ecdsa::Public::from_raw(public));
+                *account == signer.into_account()
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn verify_should_works() {
+    use hex_literal::hex;
+    use sp_core::{ecdsa, Pair};
+
+    let msg = "test btc signed message";
+    let pair = ecdsa::Pair::from_seed(&hex![
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+    ]);
+    let account = <MultiSignature as Verify>::Signer::from(pair.public()).into_account();
+    let signature = BitcoinSignature(hex!["1f993405f0fdd76dc0a625ea13b017f67a9ecc24e388743b1b538561f1f8ea2fcd3f6b1de662f599ba48ca8a12108f1b07b20fd347f2368206583cae419d7af069"]);
+    assert_eq!(signature.verify(msg.as_ref(), &account), true);
+
+    let mut signature = signature;
+    signature.0[0] = 26;
+    assert_eq!(signature.verify(msg.as_ref(), &account), false);
+}
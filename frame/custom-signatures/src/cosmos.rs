@@ -0,0 +1,232 @@
+//! Cosmos ADR-36 arbitrary message signatures compatibility instances.
+//!
+//! The message is signed as the amino JSON sign doc of `MsgSignData`, like Keplr's
+//! `signArbitrary` does. The sign doc refers to the bech32 address of the signer, which
+//! uses the `ADDRESS_PREFIX` human readable part.
+
+use crate::{MessageFormat, SignatureFormat};
+use codec::{Decode, Encode};
+use ripemd160::{Digest, Ripemd160};
+use sp_core::ecdsa;
+use sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::sha2_256};
+use sp_runtime::traits::{IdentifyAccount, Lazy, Verify};
+use sp_runtime::MultiSignature;
+use sp_std::prelude::*;
+
+/// Human readable part of the signer address.
+pub const ADDRESS_PREFIX: &[u8] = b"cosmos";
+
+/// Half of the secp256k1 curve order, the upper bound of the normalized `s` value.
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Cosmos ADR-36 signature type, along with the compressed public key of the signer.
+///
+/// Cosmos signatures lack the recovery ID, so the public key is required to verify them.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, scale_info::TypeInfo)]
+pub struct CosmosSignature {
+    /// Signature `r` and `s` values.
+    pub signature: [u8; 64],
+    /// Compressed public key of the signer.
+    pub public: [u8; 33],
+}
+
+impl sp_std::fmt::Debug for CosmosSignature {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+        write!(
+            f,
+            "CosmosSignature({:?}, {:?})",
+            &self.signature[..],
+            &self.public[..]
+        )
+    }
+}
+
+/// Signature is followed by the public key.
+impl sp_std::convert::TryFrom<Vec<u8>> for CosmosSignature {
+    type Error = ();
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.len() == 97 {
+            let mut signature = [0u8; 64];
+            signature.copy_from_slice(&data[..64]);
+            let mut public = [0u8; 33];
+            public.copy_from_slice(&data[64..]);
+            Ok(CosmosSignature { signature, public })
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl SignatureFormat for CosmosSignature {
+    fn message_format(&self) -> MessageFormat {
+        MessageFormat::Scale
+    }
+
+    /// Sign doc refers to the address of the public key carried by the signature.
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        signable_message(message, &address(&self.public))
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        sha2_256(&SignatureFormat::signable_message(self, message))
+    }
+}
+
+/// Returns the bech32 address of the compressed public key.
+pub fn address(public: &[u8; 33]) -> Vec<u8> {
+    let hash = Ripemd160::digest(&sha2_256(&public[..]));
+    bech32_encode(ADDRESS_PREFIX, &hash[..])
+}
+
+/// Constructs the ADR-36 sign doc of `what` signed by `signer` address.
+pub fn signable_message(what: &[u8], signer: &[u8]) -> Vec<u8> {
+    let mut v = br#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"sign/MsgSignData","value":{"data":""#.to_vec();
+    v.extend_from_slice(&base64_encode(what));
+    v.extend_from_slice(br#"","signer":""#);
+    v.extend_from_slice(signer);
+    v.extend_from_slice(br#""}}],"sequence":"0"}"#);
+    v
+}
+
+/// Standard base64 encoding of `data` with padding.
+fn base64_encode(data: &[u8]) -> Vec<u8> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = Vec::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let mut bytes = [0u8; 3];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let n = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Bech32 (BIP-173) encoding of `data` with human readable part `hrp`.
+fn bech32_encode(hrp: &[u8], data: &[u8]) -> Vec<u8> {
+    const CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    // Regroup bytes into 5 bit values
+    let mut values = Vec::with_capacity((data.len() * 8 + 4) / 5 + 6);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for byte in data {
+        acc = (acc << 8 | u32::from(*byte)) & 0x1fff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            values.push((acc >> bits) as u8 & 0x1f);
+        }
+    }
+    if bits > 0 {
+        values.push((acc << (5 - bits)) as u8 & 0x1f);
+    }
+
+    let mut checksum_input: Vec<u8> = hrp.iter().map(|c| c >> 5).collect();
+    checksum_input.push(0);
+    checksum_input.extend(hrp.iter().map(|c| c & 0x1f));
+    checksum_input.extend_from_slice(&values);
+    checksum_input.extend_from_slice(&[0u8; 6]);
+    let checksum = bech32_polymod(&checksum_input) ^ 1;
+    values.extend((0..6).map(|i| (checksum >> (5 * (5 - i))) as u8 & 0x1f));
+
+    let mut encoded = hrp.to_vec();
+    encoded.push(b'1');
+    encoded.extend(values.iter().map(|v| CHARSET[*v as usize]));
+    encoded
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk = 1u32;
+    for value in values {
+        let top = chk >> 25;
+        chk = (chk & 0x1ffffff) << 5 ^ u32::from(*value);
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+/// Attempts to verify a message signature signed by using Keplr's `signArbitrary`.
+impl Verify for CosmosSignature {
+    type Signer = <MultiSignature as Verify>::Signer;
+
+    fn verify<L: Lazy<[u8]>>(
+        &self,
+        mut msg: L,
+        account: &<Self::Signer as IdentifyAccount>::AccountId,
+    ) -> bool {
+        let signer = Self::Signer::from(ecdsa::Public::from_raw(self.public));
+        if *account != signer.into_account() {
+            return false;
+        }
+
+        // Cosmos SDK only accepts the low `s` form, the high one is a malleated copy
+        if self.signature[32..] > HALF_ORDER[..] {
+            return false;
+        }
+
+        let msg = self.message_digest(msg.get());
+        // Signature matches if the public key is recovered with any of the recovery IDs
+        (0..4).any(|recovery_id| {
+            let mut signature = [0u8; 65];
+            signature[..64].copy_from_slice(&self.signature[..]);
+            signature[64] = recovery_id;
+            secp256k1_ecdsa_recover_compressed(&signature, &msg)
+                .map_or(false, |public| public == self.public)
+        })
+    }
+}
+
+#[test]
+fn verify_should_works() {
+    use hex_literal::hex;
+    use sp_core::{ecdsa, Pair};
+
+    let msg = "test cosmos signed message";
+    let pair = ecdsa::Pair::from_seed(&hex![
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+    ]);
+    let account = <MultiSignature as Verify>::Signer::from(pair.public()).into_account();
+
+    let public = hex!["038318535b54105d4a7aae60c08fc45f9687181b4fdfc625bd1a753fa7397fed75"];
+    assert_eq!(
+        address(&public),
+        b"cosmos15428vq2uzwhm3taey9sr9x5vm6tk78ewe54lwe".to_vec()
+    );
+    assert_eq!(
+        signable_message(msg.as_ref(), &address(&public)),
+        br#"{"account_number":"0","chain_id":"","fee":{"amount":[],"gas":"0"},"memo":"","msgs":[{"type":"sign/MsgSignData","value":{"data":"dGVzdCBjb3Ntb3Mgc2lnbmVkIG1lc3NhZ2U=","signer":"cosmos15428vq2uzwhm3taey9sr9x5vm6tk78ewe54lwe"}}],"sequence":"0"}"#.to_vec()
+    );
+
+    let signature = CosmosSignature {
+        signature: hex!["e42a6432931bde4ce8ff9925a4e563e5d2ffb1b9e26963bc184ee43611239fc31e3ee0760e5edad56edfc668ac980512b34f0f062770ae7215efe686ac374b7d"],
+        public,
+    };
+    assert_eq!(signature.verify(msg.as_ref(), &account), true);
+
+    // Same signature with the high `s` value
+    let malleated = CosmosSignature {
+        signature: hex!["e42a6432931bde4ce8ff9925a4e563e5d2ffb1b9e26963bc184ee43611239fc3e1c11f89f1a1252a912039975367faec075fcde087d7f1c9a9e2780623fef5c4"],
+        public,
+    };
+    assert_eq!(malleated.verify(msg.as_ref(), &account), false);
+
+    // Public key of another account
+    let other =
+        <MultiSignature as Verify>::Signer::from(ecdsa::Pair::from_seed(&[1u8; 32]).public())
+            .into_account();
+    assert_eq!(signature.verify(msg.as_ref(), &other), false);
+}
@@ -0,0 +1,386 @@
+//! EIP-712 typed data signatures (eth_signTypedData_v4 API call).
+//!
+//! Signed call is presented to the wallet as a typed `Call` struct:
+//! ```nocompile
+//! EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)
+//! Call(string pallet,string call,uint256 nonce,uint256 validUntil,bytes32 callHash)
+//! SponsoredCall(string pallet,string call,uint256 nonce,uint256 validUntil,bytes32 callHash,bytes32 sponsor,uint256 maxCallFee)
+//! Batch(uint256 nonce,uint256 validUntil,bool atomic,BatchItem[] calls)
+//! BatchItem(string pallet,string call,bytes32 callHash)
+//! ```
+//! Domain `name` is the chain name, `version` is the runtime spec version, `chainId` is the
+//! Ethereum chain ID and `salt` is the genesis hash. `validUntil` is the last block the call is
+//! valid in, zero if it doesn't expire. `callHash` is the keccak of the SCALE encoded call.
+//! Calls paid by a sponsor are signed as `SponsoredCall`, where `sponsor` is the allowed sponsor
+//! account, zero for any, and `maxCallFee` is the maximum call fee, zero if unlimited. Batches of calls
+//! are signed as `Batch`.
+
+use crate::{Config, MessageFormat, Pallet, SignatureFormat, SignedMessage, SponsorshipOf};
+use codec::{Decode, Encode};
+use frame_support::traits::{Get, GetCallMetadata};
+use sp_core::ecdsa;
+use sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::keccak_256};
+use sp_runtime::traits::{IdentifyAccount, Lazy, SaturatedConversion, Verify};
+use sp_runtime::MultiSignature;
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Type of the EIP-712 domain.
+pub const DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,bytes32 salt)";
+
+/// Type of the signed call struct.
+pub const CALL_TYPE: &[u8] =
+    b"Call(string pallet,string call,uint256 nonce,uint256 validUntil,bytes32 callHash)";
+
+/// Type of the signed struct of a call paid by a sponsor.
+pub const SPONSORED_CALL_TYPE: &[u8] = b"SponsoredCall(string pallet,string call,uint256 nonce,uint256 validUntil,bytes32 callHash,bytes32 sponsor,uint256 maxCallFee)";
+
+/// Type of the signed batch struct, followed by the referenced `BATCH_ITEM_TYPE`.
+pub const BATCH_TYPE: &[u8] =
+    b"Batch(uint256 nonce,uint256 validUntil,bool atomic,BatchItem[] calls)BatchItem(string pallet,string call,bytes32 callHash)";
+
+/// Type of a call in the signed batch struct.
+pub const BATCH_ITEM_TYPE: &[u8] = b"BatchItem(string pallet,string call,bytes32 callHash)";
+
+/// EIP-712 typed data signature type.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, scale_info::TypeInfo)]
+pub struct Eip712Signature(pub [u8; 65]);
+
+impl sp_std::fmt::Debug for Eip712Signature {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+        write!(f, "Eip712Signature({:?})", &self.0[..])
+    }
+}
+
+impl From<ecdsa::Signature> for Eip712Signature {
+    fn from(signature: ecdsa::Signature) -> Self {
+        Self(signature.into())
+    }
+}
+
+impl sp_std::convert::TryFrom<Vec<u8>> for Eip712Signature {
+    type Error = ();
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.len() == 65 {
+            let mut inner = [0u8; 65];
+            inner.copy_from_slice(&data[..]);
+            Ok(Eip712Signature(inner))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl SignatureFormat for Eip712Signature {
+    fn message_format(&self) -> MessageFormat {
+        MessageFormat::Eip712
+    }
+
+    /// Typed data message is signed as is.
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        message.to_vec()
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        keccak_256(message)
+    }
+}
+
+/// Attempts to recover the Ethereum public key from a signature of the typed data message
+/// constructed by `typed_data_message`.
+impl Verify for Eip712Signature {
+    type Signer = <MultiSignature as Verify>::Signer;
+
+    fn verify<L: Lazy<[u8]>>(
+        &self,
+        mut msg: L,
+        account: &<Self::Signer as IdentifyAccount>::AccountId,
+    ) -> bool {
+        let msg = self.message_digest(msg.get());
+        match secp256k1_ecdsa_recover_compressed(&self.0, &msg).ok() {
+            Some(public) => {
+                let signer = Self::Signer::from(ecdsa::Public::from_raw(public));
+                *account == signer.into_account()
+            }
+            None => false,
+        }
+    }
+}
+
+/// Encodes the value as a big-endian `uint256`.
+fn encode_uint256(value: u128) -> [u8; 32] {
+    let mut encoded = [0u8; 32];
+    encoded[16..].copy_from_slice(&value.to_be_bytes());
+    encoded
+}
+
+/// Renders the value as a decimal string.
+pub fn decimal_string(mut value: u128) -> Vec<u8> {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(b'0' + (value % 10) as u8);
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    digits
+}
+
+/// Returns the hash of the EIP-712 domain.
+pub fn domain_separator(
+    name: &[u8],
+    spec_version: u32,
+    chain_id: u64,
+    genesis_hash: &[u8; 32],
+) -> [u8; 32] {
+    let mut encoded = keccak_256(DOMAIN_TYPE).to_vec();
+    encoded.extend_from_slice(&keccak_256(name));
+    encoded.extend_from_slice(&keccak_256(&decimal_string(spec_version.into())));
+    encoded.extend_from_slice(&encode_uint256(chain_id.into()));
+    encoded.extend_from_slice(&genesis_hash[..]);
+    keccak_256(&encoded)
+}
+
+/// Encodes the fields shared by the call structs.
+fn encode_call_fields(
+    type_hash: [u8; 32],
+    pallet: &[u8],
+    call: &[u8],
+    nonce: u128,
+    valid_until: u128,
+    call_hash: &[u8; 32],
+) -> Vec<u8> {
+    let mut encoded = type_hash.to_vec();
+    encoded.extend_from_slice(&keccak_256(pallet));
+    encoded.extend_from_slice(&keccak_256(call));
+    encoded.extend_from_slice(&encode_uint256(nonce));
+    encoded.extend_from_slice(&encode_uint256(valid_until));
+    encoded.extend_from_slice(&call_hash[..]);
+    encoded
+}
+
+/// Returns the hash of the signed call struct.
+pub fn call_struct_hash(
+    pallet: &[u8],
+    call: &[u8],
+    nonce: u128,
+    valid_until: u128,
+    call_hash: &[u8; 32],
+) -> [u8; 32] {
+    let encoded = encode_call_fields(
+        keccak_256(CALL_TYPE),
+        pallet,
+        call,
+        nonce,
+        valid_until,
+        call_hash,
+    );
+    keccak_256(&encoded)
+}
+
+/// Returns the hash of the signed struct of a call paid by a sponsor.
+pub fn sponsored_call_struct_hash(
+    pallet: &[u8],
+    call: &[u8],
+    nonce: u128,
+    valid_until: u128,
+    call_hash: &[u8; 32],
+    sponsor: &[u8; 32],
+    max_call_fee: u128,
+) -> [u8; 32] {
+    let mut encoded = encode_call_fields(
+        keccak_256(SPONSORED_CALL_TYPE),
+        pallet,
+        call,
+        nonce,
+        valid_until,
+        call_hash,
+    );
+    encoded.extend_from_slice(&sponsor[..]);
+    encoded.extend_from_slice(&encode_uint256(max_call_fee));
+    keccak_256(&encoded)
+}
+
+/// Returns the hash of a call in the signed batch struct.
+pub fn batch_item_struct_hash(pallet: &[u8], call: &[u8], call_hash: &[u8; 32]) -> [u8; 32] {
+    let mut encoded = keccak_256(BATCH_ITEM_TYPE).to_vec();
+    encoded.extend_from_slice(&keccak_256(pallet));
+    encoded.extend_from_slice(&keccak_256(call));
+    encoded.extend_from_slice(&call_hash[..]);
+    keccak_256(&encoded)
+}
+
+/// Returns the hash of the signed batch struct, `items` are the hashes of its calls.
+pub fn batch_struct_hash(
+    nonce: u128,
+    valid_until: u128,
+    atomic: bool,
+    items: &[[u8; 32]],
+) -> [u8; 32] {
+    let mut encoded = keccak_256(BATCH_TYPE).to_vec();
+    encoded.extend_from_slice(&encode_uint256(nonce));
+    encoded.extend_from_slice(&encode_uint256(valid_until));
+    encoded.extend_from_slice(&encode_uint256(atomic.into()));
+    encoded.extend_from_slice(&keccak_256(&items.concat()));
+    keccak_256(&encoded)
+}
+
+/// Copies the encoding of `value` into `bytes32`.
+fn encode_bytes32<V: Encode>(value: &V) -> [u8; 32] {
+    let mut encoded = [0u8; 32];
+    value.using_encoded(|bytes| {
+        let len = bytes.len().min(32);
+        encoded[..len].copy_from_slice(&bytes[..len]);
+    });
+    encoded
+}
+
+/// Constructs the message that Ethereum RPC's `eth_signTypedData_v4` would sign.
+pub fn typed_data_message(domain_separator: &[u8; 32], struct_hash: &[u8; 32]) -> Vec<u8> {
+    let mut v = b"\x19\x01".to_vec();
+    v.extend_from_slice(&domain_separator[..]);
+    v.extend_from_slice(&struct_hash[..]);
+    v
+}
+
+/// EIP-712 typed data message of a call, should be used together with `Eip712Signature`.
+///
+/// `ChainName` and `ChainId` are used in the domain, along with the runtime spec version
+/// and the genesis hash.
+pub struct Eip712Message<ChainName, ChainId>(PhantomData<(ChainName, ChainId)>);
+
+impl<ChainName: Get<&'static str>, ChainId: Get<u64>> Eip712Message<ChainName, ChainId> {
+    /// Returns the domain separator of the chain.
+    fn chain_domain_separator<T: Config>() -> [u8; 32] {
+        domain_separator(
+            ChainName::get().as_bytes(),
+            <T as frame_system::Config>::Version::get().spec_version,
+            ChainId::get(),
+            &encode_bytes32(&Pallet::<T>::genesis_hash()),
+        )
+    }
+}
+
+impl<T, ChainName, ChainId> SignedMessage<T> for Eip712Message<ChainName, ChainId>
+where
+    T: Config,
+    <T as Config>::Call: GetCallMetadata,
+    ChainName: Get<&'static str>,
+    ChainId: Get<u64>,
+{
+    fn signed_message(
+        format: MessageFormat,
+        call: &<T as Config>::Call,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+        sponsorship: Option<&SponsorshipOf<T>>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Eip712 {
+            return None;
+        }
+
+        let metadata = call.get_call_metadata();
+        let pallet = metadata.pallet_name.as_bytes();
+        let function = metadata.function_name.as_bytes();
+        let nonce = (*nonce).saturated_into();
+        let valid_until = valid_until.map(|n| n.saturated_into()).unwrap_or_default();
+        let call_hash = keccak_256(&call.encode());
+        let struct_hash = match sponsorship {
+            Some(sponsorship) => sponsored_call_struct_hash(
+                pallet,
+                function,
+                nonce,
+                valid_until,
+                &call_hash,
+                &sponsorship
+                    .sponsor
+                    .as_ref()
+                    .map(encode_bytes32)
+                    .unwrap_or_default(),
+                sponsorship
+                    .max_call_fee
+                    .map(|fee| fee.saturated_into())
+                    .unwrap_or_default(),
+            ),
+            None => call_struct_hash(pallet, function, nonce, valid_until, &call_hash),
+        };
+
+        Some(typed_data_message(
+            &Self::chain_domain_separator::<T>(),
+            &struct_hash,
+        ))
+    }
+
+    fn signed_batch_message(
+        format: MessageFormat,
+        calls: &[<T as Config>::Call],
+        atomic: bool,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Eip712 {
+            return None;
+        }
+
+        let items = calls
+            .iter()
+            .map(|call| {
+                let metadata = call.get_call_metadata();
+                batch_item_struct_hash(
+                    metadata.pallet_name.as_bytes(),
+                    metadata.function_name.as_bytes(),
+                    &keccak_256(&call.encode()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let struct_hash = batch_struct_hash(
+            (*nonce).saturated_into(),
+            valid_until.map(|n| n.saturated_into()).unwrap_or_default(),
+            atomic,
+            &items,
+        );
+
+        Some(typed_data_message(
+            &Self::chain_domain_separator::<T>(),
+            &struct_hash,
+        ))
+    }
+}
+
+#[test]
+fn verify_should_works() {
+    use hex_literal::hex;
+    use sp_core::{ecdsa, Pair};
+
+    let pair = ecdsa::Pair::from_seed(&hex![
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+    ]);
+    let account = <MultiSignature as Verify>::Signer::from(pair.public()).into_account();
+
+    let domain_separator = domain_separator(b"Astar Test", 0, 592, &[69u8; 32]);
+    assert_eq!(
+        domain_separator,
+        hex!["c70d91687765340765f5c4c80063e28f6ddb27cf3108e95af64ad926e2fc69dd"]
+    );
+
+    let call = hex!["0000c4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f"];
+    let struct_hash = call_struct_hash(b"Balances", b"transfer", 0, 0, &keccak_256(&call));
+    assert_eq!(
+        struct_hash,
+        hex!["e6a97a9668fd24ee0204674eb601f77b572689e12bb9bf51d7cbc04c6044cf43"]
+    );
+
+    let item = batch_item_struct_hash(b"Balances", b"transfer", &keccak_256(&call));
+    assert_eq!(
+        batch_struct_hash(0, 0, true, &[item, item]),
+        hex!["7495c12027f3fb8972b7b405b0110441c68b85fa4b4da8a1667f24bb41725797"]
+    );
+
+    let msg = typed_data_message(&domain_separator, &struct_hash);
+    let signature = Eip712Signature(hex!["fef00d7948e766762da5a8fd6a818fee49b76a99cd04d330ea146cd94c5624f94fd8282cc051d7bc1e2cc1f015ffd76d97be90789454cae8a83882522519b3f81b"]);
+    assert_eq!(signature.verify(&msg[..], &account), true);
+    assert_eq!(signature.verify(&struct_hash[..], &account), false);
+}
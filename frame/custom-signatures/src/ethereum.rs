@@ -1,5 +1,6 @@
 //! Ethereum prefixed signatures compatibility instances.
 
+use crate::{MessageFormat, SignatureFormat};
 use codec::{Decode, Encode};
 use sp_core::ecdsa;
 use sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::keccak_256};
@@ -37,6 +38,20 @@ impl sp_std::convert::TryFrom<Vec<u8>> for EthereumSignature {
     }
 }
 
+impl SignatureFormat for EthereumSignature {
+    fn message_format(&self) -> MessageFormat {
+        MessageFormat::Scale
+    }
+
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        signable_message(message)
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        keccak_256(&signable_message(message))
+    }
+}
+
 /// Constructs the message that Ethereum RPC's `personal_sign` and `eth_sign` would sign.
 ///
 /// Note: sign message hash to escape of message length estimation.
@@ -57,7 +72,7 @@ impl Verify for EthereumSignature {
         mut msg: L,
         account: &<Self::Signer as IdentifyAccount>::AccountId,
     ) -> bool {
-        let msg = keccak_256(&signable_message(msg.get()));
+        let msg = self.message_digest(msg.get());
         match secp256k1_ecdsa_recover_compressed(&self.0, &msg).ok() {
             Some(public) => {
                 let signer = Self::Signer::from(ecdsa::Public::from_raw(public));
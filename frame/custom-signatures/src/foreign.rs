@@ -0,0 +1,127 @@
+//! Signatures made by the wallets of other chains.
+
+use crate::{bitcoin::BitcoinSignature, cosmos::CosmosSignature, ethereum::EthereumSignature};
+use crate::{eip712::Eip712Signature, text::TextSignature, MessageFormat, SignatureFormat};
+use codec::{Decode, DecodeAll, Encode};
+use sp_runtime::traits::{IdentifyAccount, Lazy, Verify};
+use sp_runtime::{MultiSignature, RuntimeDebug};
+use sp_std::{convert::TryFrom, prelude::*};
+
+/// Signature of any supported foreign wallet.
+///
+/// EIP-712 signatures are verified against the `Eip712` message format, text ones against the
+/// `Text` one and other ones against the `Scale` one, so the runtime message should support
+/// them all, e.g. `(ScaleMessage, Eip712Message<ChainName, ChainId>, TextMessage<ChainName>)`.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, RuntimeDebug, scale_info::TypeInfo)]
+pub enum ForeignSignature {
+    /// Ethereum `personal_sign` signature.
+    Ethereum(EthereumSignature),
+    /// Bitcoin `signmessage` signature.
+    Bitcoin(BitcoinSignature),
+    /// Cosmos ADR-36 signature.
+    Cosmos(CosmosSignature),
+    /// Ethereum `eth_signTypedData_v4` signature.
+    Eip712(Eip712Signature),
+    /// Ethereum `personal_sign` signature of the human readable text.
+    Text(TextSignature),
+}
+
+/// Plain 65 bytes are decoded as Ethereum signature for compatibility, other signatures
+/// are SCALE encoded `ForeignSignature`.
+impl sp_std::convert::TryFrom<Vec<u8>> for ForeignSignature {
+    type Error = ();
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.len() == 65 {
+            EthereumSignature::try_from(data).map(ForeignSignature::Ethereum)
+        } else {
+            ForeignSignature::decode_all(&data[..]).map_err(|_| ())
+        }
+    }
+}
+
+impl Verify for ForeignSignature {
+    type Signer = <MultiSignature as Verify>::Signer;
+
+    fn verify<L: Lazy<[u8]>>(
+        &self,
+        msg: L,
+        account: &<Self::Signer as IdentifyAccount>::AccountId,
+    ) -> bool {
+        match self {
+            ForeignSignature::Ethereum(signature) => signature.verify(msg, account),
+            ForeignSignature::Bitcoin(signature) => signature.verify(msg, account),
+            ForeignSignature::Cosmos(signature) => signature.verify(msg, account),
+            ForeignSignature::Eip712(signature) => signature.verify(msg, account),
+            ForeignSignature::Text(signature) => signature.verify(msg, account),
+        }
+    }
+}
+
+impl SignatureFormat for ForeignSignature {
+    fn message_format(&self) -> MessageFormat {
+        match self {
+            ForeignSignature::Ethereum(signature) => signature.message_format(),
+            ForeignSignature::Bitcoin(signature) => signature.message_format(),
+            ForeignSignature::Cosmos(signature) => signature.message_format(),
+            ForeignSignature::Eip712(signature) => signature.message_format(),
+            ForeignSignature::Text(signature) => signature.message_format(),
+        }
+    }
+
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            ForeignSignature::Ethereum(signature) => signature.signable_message(message),
+            ForeignSignature::Bitcoin(signature) => signature.signable_message(message),
+            ForeignSignature::Cosmos(signature) => signature.signable_message(message),
+            ForeignSignature::Eip712(signature) => signature.signable_message(message),
+            ForeignSignature::Text(signature) => signature.signable_message(message),
+        }
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        match self {
+            ForeignSignature::Ethereum(signature) => signature.message_digest(message),
+            ForeignSignature::Bitcoin(signature) => signature.message_digest(message),
+            ForeignSignature::Cosmos(signature) => signature.message_digest(message),
+            ForeignSignature::Eip712(signature) => signature.message_digest(message),
+            ForeignSignature::Text(signature) => signature.message_digest(message),
+        }
+    }
+}
+
+#[test]
+fn try_from_should_works() {
+    let ethereum = ForeignSignature::Ethereum(EthereumSignature([1u8; 65]));
+    assert_eq!(
+        ForeignSignature::try_from(vec![1u8; 65]),
+        Ok(ethereum.clone())
+    );
+    assert_eq!(
+        ForeignSignature::try_from(ethereum.encode()),
+        Ok(ethereum.clone())
+    );
+
+    let bitcoin = ForeignSignature::Bitcoin(BitcoinSignature([1u8; 65]));
+    assert_eq!(ForeignSignature::try_from(bitcoin.encode()), Ok(bitcoin));
+
+    let cosmos = ForeignSignature::Cosmos(CosmosSignature {
+        signature: [1u8; 64],
+        public: [2u8; 33],
+    });
+    assert_eq!(ForeignSignature::try_from(cosmos.encode()), Ok(cosmos));
+
+    let eip712 = ForeignSignature::Eip712(Eip712Signature([1u8; 65]));
+    assert_eq!(
+        ForeignSignature::try_from(eip712.encode()),
+        Ok(eip712.clone())
+    );
+    assert_eq!(eip712.message_format(), MessageFormat::Eip712);
+
+    let text = ForeignSignature::Text(TextSignature([1u8; 65]));
+    assert_eq!(ForeignSignature::try_from(text.encode()), Ok(text.clone()));
+    assert_eq!(text.message_format(), MessageFormat::Text);
+    assert_eq!(ethereum.message_format(), MessageFormat::Scale);
+
+    assert_eq!(ForeignSignature::try_from(vec![1u8; 64]), Err(()));
+}
@@ -1,27 +1,311 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use codec::{Decode, Encode};
+use frame_support::traits::Get;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
 pub use pallet::*;
 
 /// Ethereum-compatible signatures (eth_sign API call).
 pub mod ethereum;
 
+/// Ethereum typed data signatures (eth_signTypedData_v4 API call).
+pub mod eip712;
+
+/// Bitcoin signed messages (signmessage RPC call).
+pub mod bitcoin;
+
+/// Cosmos ADR-36 arbitrary message signatures (Keplr signArbitrary API call).
+pub mod cosmos;
+
+/// Signatures of any supported foreign wallet.
+pub mod foreign;
+
+/// Human readable text messages (personal_sign API call).
+pub mod text;
+
 #[cfg(test)]
 mod tests;
 
+/// Version of the SCALE encoded payload signed by `ScaleMessage`.
+pub const PAYLOAD_VERSION: u8 = 1;
+
+/// Version of the SCALE encoded batch payload signed by `ScaleMessage`.
+///
+/// The high bit tells it apart from the single call payload.
+pub const BATCH_PAYLOAD_VERSION: u8 = 0x80 | PAYLOAD_VERSION;
+
+/// Terms under which a sponsor may submit the call and pay its fee.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo)]
+pub struct Sponsorship<AccountId, Balance> {
+    /// The only account allowed to sponsor the call, any account if `None`.
+    pub sponsor: Option<AccountId>,
+    /// Maximum fee of the signed call, unlimited if `None`.
+    ///
+    /// Only the call fee computed by the pallet is capped, the fee of the sponsor's own
+    /// transaction wrapping the call is charged by the transaction payment as usual.
+    pub max_call_fee: Option<Balance>,
+}
+
+/// Payload which the signer signs to authorize a call.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo)]
+pub struct SigningPayload<Index, Balance> {
+    /// Message built by `SignedMessage`, which is verified by the user defined signature.
+    pub message: Vec<u8>,
+    /// Bytes which the wallet signs for the message, e.g. prefixed by the wallet.
+    pub signable: Vec<u8>,
+    /// Digest of the message which the signature is made over.
+    pub digest: [u8; 32],
+    /// Nonce of the signer account, which the call is signed with.
+    pub nonce: Index,
+    /// Fee of the call, charged to the sponsor instead of the signer for sponsored calls.
+    pub fee: Balance,
+}
+
+/// Check of a signed call that fails.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo)]
+pub enum PayloadError {
+    /// Call decode fails.
+    CallDecodeFailure,
+    /// Signature decode fails.
+    SignatureDecodeFailure,
+    /// Nonce mismatches the signer account nonce.
+    BadNonce,
+    /// Call is submitted after the block it's valid until.
+    Expired,
+    /// Signature is valid for an outdated payload format.
+    OutdatedPayload,
+    /// Signature and account mismatched.
+    InvalidSignature,
+    /// Signer is unable to pay the call fee.
+    CannotPayFee,
+    /// Signature is made for a message format which isn't supported.
+    UnsupportedFormat,
+    /// Batch contains no calls.
+    EmptyBatch,
+    /// Call fee exceeds the maximum call fee allowed by the signer.
+    MaxCallFeeExceeded,
+}
+
+/// Format of the message which the signer signs to authorize a call.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, scale_info::TypeInfo)]
+pub enum MessageFormat {
+    /// SCALE encoded payload built by `ScaleMessage`.
+    Scale,
+    /// EIP-712 typed data built by `eip712::Eip712Message`.
+    Eip712,
+    /// Human readable text built by `text::TextMessage`.
+    Text,
+}
+
+/// Signature which selects the format of the message it's verified against.
+pub trait SignatureFormat {
+    /// Returns the format of the message signed by the signature.
+    fn message_format(&self) -> MessageFormat;
+
+    /// Returns the bytes which the wallet signs for the `message`, e.g. prefixed by the wallet.
+    fn signable_message(&self, message: &[u8]) -> Vec<u8>;
+
+    /// Returns the digest of the `message` which the signature is made over.
+    fn message_digest(&self, message: &[u8]) -> [u8; 32];
+}
+
+/// Builds the message which the signer signs to authorize a call.
+///
+/// Messages are built only for the supported formats, `None` is returned for other ones.
+/// Tuples of builders support the formats of all of them.
+pub trait SignedMessage<T: Config> {
+    /// Returns the message signed for `call` with `nonce`, valid until block `valid_until`.
+    ///
+    /// Calls paid by a sponsor are signed together with the `sponsorship` terms.
+    fn signed_message(
+        format: MessageFormat,
+        call: &<T as Config>::Call,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+        sponsorship: Option<&SponsorshipOf<T>>,
+    ) -> Option<Vec<u8>>;
+
+    /// Returns the message signed for a batch of `calls` with `nonce`, valid until block
+    /// `valid_until`.
+    fn signed_batch_message(
+        format: MessageFormat,
+        calls: &[<T as Config>::Call],
+        atomic: bool,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+    ) -> Option<Vec<u8>>;
+
+    /// Returns the message which clients using an outdated format sign for `call` with `nonce`.
+    ///
+    /// It's never accepted, only used to tell such clients apart from invalid signatures.
+    fn outdated_message(
+        _format: MessageFormat,
+        _call: &<T as Config>::Call,
+        _nonce: &T::Index,
+    ) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+macro_rules! impl_signed_message_for_tuple {
+    ($($message:ident),+) => {
+        /// Message is built by the first builder which supports the format.
+        impl<T: Config, $($message: SignedMessage<T>),+> SignedMessage<T> for ($($message,)+) {
+            fn signed_message(
+                format: MessageFormat,
+                call: &<T as Config>::Call,
+                nonce: &T::Index,
+                valid_until: &Option<T::BlockNumber>,
+                sponsorship: Option<&SponsorshipOf<T>>,
+            ) -> Option<Vec<u8>> {
+                None $(.or_else(|| {
+                    <$message as SignedMessage<T>>::signed_message(
+                        format,
+                        call,
+                        nonce,
+                        valid_until,
+                        sponsorship,
+                    )
+                }))+
+            }
+
+            fn signed_batch_message(
+                format: MessageFormat,
+                calls: &[<T as Config>::Call],
+                atomic: bool,
+                nonce: &T::Index,
+                valid_until: &Option<T::BlockNumber>,
+            ) -> Option<Vec<u8>> {
+                None $(.or_else(|| {
+                    <$message as SignedMessage<T>>::signed_batch_message(
+                        format,
+                        calls,
+                        atomic,
+                        nonce,
+                        valid_until,
+                    )
+                }))+
+            }
+
+            fn outdated_message(
+                format: MessageFormat,
+                call: &<T as Config>::Call,
+                nonce: &T::Index,
+            ) -> Option<Vec<u8>> {
+                None $(.or_else(|| {
+                    <$message as SignedMessage<T>>::outdated_message(format, call, nonce)
+                }))+
+            }
+        }
+    };
+}
+
+impl_signed_message_for_tuple!(A, B);
+impl_signed_message_for_tuple!(A, B, C);
+
+/// SCALE encoded
+/// `(CallMagicNumber, PAYLOAD_VERSION, genesis_hash, transaction_version, nonce, valid_until, call)`,
+/// should be used together with `ethereum::EthereumSignature`.
+///
+/// Genesis hash and transaction version bind the signature to a single chain and call encoding.
+/// Sponsored calls are followed by the SCALE encoded `Sponsorship`.
+///
+/// Batches are signed as SCALE encoded
+/// `(CallMagicNumber, BATCH_PAYLOAD_VERSION, genesis_hash, transaction_version, nonce, valid_until, atomic, calls)`.
+pub struct ScaleMessage;
+
+impl<T: Config> SignedMessage<T> for ScaleMessage {
+    fn signed_message(
+        format: MessageFormat,
+        call: &<T as Config>::Call,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+        sponsorship: Option<&SponsorshipOf<T>>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Scale {
+            return None;
+        }
+
+        let mut message = (
+            T::CallMagicNumber::get(),
+            PAYLOAD_VERSION,
+            Pallet::<T>::genesis_hash(),
+            <T as frame_system::Config>::Version::get().transaction_version,
+            *nonce,
+            valid_until,
+            call,
+        )
+            .encode();
+        if let Some(sponsorship) = sponsorship {
+            sponsorship.encode_to(&mut message);
+        }
+        Some(message)
+    }
+
+    fn signed_batch_message(
+        format: MessageFormat,
+        calls: &[<T as Config>::Call],
+        atomic: bool,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Scale {
+            return None;
+        }
+
+        let message = (
+            T::CallMagicNumber::get(),
+            BATCH_PAYLOAD_VERSION,
+            Pallet::<T>::genesis_hash(),
+            <T as frame_system::Config>::Version::get().transaction_version,
+            *nonce,
+            valid_until,
+            atomic,
+            calls,
+        )
+            .encode();
+        Some(message)
+    }
+
+    /// Unversioned `(CallMagicNumber, nonce, call)` payload.
+    fn outdated_message(
+        format: MessageFormat,
+        call: &<T as Config>::Call,
+        nonce: &T::Index,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Scale {
+            return None;
+        }
+
+        Some((T::CallMagicNumber::get(), *nonce, call).encode())
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
+    use codec::DecodeAll;
     use frame_support::{
         pallet_prelude::*,
+        storage::{with_transaction, TransactionOutcome},
         traits::{
-            Currency, ExistenceRequirement, Get, OnUnbalanced, UnfilteredDispatchable,
+            Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, UnfilteredDispatchable,
             WithdrawReasons,
         },
-        weights::GetDispatchInfo,
+        weights::{DispatchInfo, GetDispatchInfo, PostDispatchInfo, WeightToFeePolynomial},
+    };
+    use frame_system::{ensure_none, ensure_signed, pallet_prelude::*};
+    use sp_runtime::{
+        traits::{CheckedSub, IdentifyAccount, One, SaturatedConversion, Saturating, Verify, Zero},
+        FixedPointNumber, FixedU128,
     };
-    use frame_system::{ensure_none, pallet_prelude::*};
-    use sp_runtime::traits::{IdentifyAccount, Verify};
     use sp_std::{convert::TryFrom, prelude::*};
 
+    use crate::{
+        MessageFormat, PayloadError, SignatureFormat, SignedMessage, SigningPayload, Sponsorship,
+    };
+
     #[pallet::pallet]
     pub struct Pallet<T>(_);
 
@@ -29,6 +313,9 @@ pub mod pallet {
     pub type BalanceOf<T> =
         <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
+    /// The sponsorship terms type of this pallet.
+    pub type SponsorshipOf<T> = Sponsorship<<T as frame_system::Config>::AccountId, BalanceOf<T>>;
+
     #[pallet::config]
     pub trait Config: frame_system::Config {
         /// The overarching event type.
@@ -37,12 +324,18 @@ pub mod pallet {
         /// A signable call.
         type Call: Parameter + UnfilteredDispatchable<Origin = Self::Origin> + GetDispatchInfo;
 
-        /// User defined signature type.
-        type Signature: Parameter + Verify<Signer = Self::Signer> + TryFrom<Vec<u8>>;
+        /// User defined signature type, which selects the format of the signed message.
+        type Signature: Parameter
+            + Verify<Signer = Self::Signer>
+            + TryFrom<Vec<u8>>
+            + SignatureFormat;
 
         /// User defined signer type.
         type Signer: IdentifyAccount<AccountId = Self::AccountId>;
 
+        /// Message which is signed to authorize a call, in the formats of the `Signature` type.
+        type SignedMessage: SignedMessage<Self>;
+
         /// The currency trait.
         type Currency: Currency<Self::AccountId>;
 
@@ -51,21 +344,31 @@ pub mod pallet {
             <Self::Currency as Currency<Self::AccountId>>::NegativeImbalance,
         >;
 
-        /// The call processing fee amount.
+        /// Converts the call weight to the fee, should be the same as the transaction payment one.
+        type WeightToFee: WeightToFeePolynomial<Balance = BalanceOf<Self>>;
+
+        /// The fee for each byte of the encoded call.
         #[pallet::constant]
-        type CallFee: Get<BalanceOf<Self>>;
+        type TransactionByteFee: Get<BalanceOf<Self>>;
+
+        /// The weight fee multiplier, usually the transaction payment next fee multiplier.
+        type FeeMultiplier: Get<FixedU128>;
 
         /// The call magic number.
         #[pallet::constant]
         type CallMagicNumber: Get<u16>;
 
-        /// A configuration for base priority of unsigned transactions.
-        ///
-        /// This is exposed so that it can be tuned for particular runtime, when
-        /// multiple pallets send unsigned transactions.
-        type UnsignedPriority: Get<TransactionPriority>;
+        /// The maximum number of calls in a signed batch.
+        #[pallet::constant]
+        type MaxBatchCalls: Get<u32>;
     }
 
+    /// Weight of the call processing, on top of the derivative call weight.
+    pub(crate) const CALL_WEIGHT: Weight = 10_000;
+
+    /// Number of parts in which the share of the block taken by a call is measured.
+    pub(crate) const BLOCK_PARTS: u128 = 1_000_000_000;
+
     #[pallet::error]
     pub enum Error<T> {
         /// Signature decode fails.
@@ -74,17 +377,40 @@ pub mod pallet {
         InvalidSignature,
         /// Bad nonce parameter.
         BadNonce,
+        /// Signature is valid for an outdated payload format.
+        OutdatedPayload,
+        /// Call is submitted after the block it's valid until.
+        Expired,
+        /// Call is submitted by a sponsor other than the one allowed by the signer.
+        InvalidSponsor,
+        /// Call fee exceeds the maximum call fee allowed by the signer.
+        MaxCallFeeExceeded,
+        /// Batch contains no calls.
+        EmptyBatch,
+        /// Signature is made for a message format which isn't supported.
+        UnsupportedFormat,
     }
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
-        /// A call just executed. \[result\]
+        /// A signed call just executed. \[signer, result\]
         Executed(T::AccountId, DispatchResult),
+        /// A signed batch of calls just executed. \[signer, results\]
+        ///
+        /// Atomic batches report results up to the first failed call, which reverts them all.
+        BatchExecuted(T::AccountId, Vec<DispatchResult>),
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
+        /// Dispatches the `call` signed by `signer` with the user defined signature.
+        ///
+        /// If `valid_until` is set, the call is rejected once the block number exceeds it.
+        ///
+        /// The fee is computed from the weight and the length of the call, the same way as
+        /// the transaction payment does, and the unused weight is refunded after the dispatch.
+        ///
         /// # <weight>
         /// - O(1).
         /// - Limited storage reads.
@@ -93,7 +419,7 @@ pub mod pallet {
         /// # </weight>
         #[pallet::weight({
             let dispatch_info = call.get_dispatch_info();
-            (dispatch_info.weight + 10_000, dispatch_info.class)
+            (dispatch_info.weight + CALL_WEIGHT, dispatch_info.class)
         })]
         pub fn call(
             origin: OriginFor<T>,
@@ -101,100 +427,762 @@ pub mod pallet {
             signer: T::AccountId,
             signature: Vec<u8>,
             #[pallet::compact] nonce: T::Index,
+            valid_until: Option<T::BlockNumber>,
         ) -> DispatchResultWithPostInfo {
             ensure_none(origin)?;
 
-            // Ensure that transaction isn't stale
-            ensure!(
-                nonce == frame_system::Pallet::<T>::account_nonce(signer.clone()),
-                Error::<T>::BadNonce,
-            );
+            // Length of the encoded call, charged by the length fee
+            let len = Call::<T>::call {
+                call: call.clone(),
+                signer: signer.clone(),
+                signature: signature.clone(),
+                nonce,
+                valid_until,
+            }
+            .encoded_size() as u32;
 
-            let signature = <T as Config>::Signature::try_from(signature)
-                .map_err(|_| Error::<T>::DecodeFailure)?;
+            Self::ensure_valid_payload(
+                &signer,
+                signature,
+                &nonce,
+                &valid_until,
+                |format| {
+                    T::SignedMessage::signed_message(format, &call, &nonce, &valid_until, None)
+                },
+                |format| T::SignedMessage::outdated_message(format, &call, &nonce),
+            )?;
 
-            // Ensure that transaction signature is valid
-            ensure!(
-                Self::valid_signature(&call, &signer, &signature, &nonce),
-                Error::<T>::InvalidSignature
-            );
+            // Processing fee, charged for the declared weight
+            let dispatch_info = call.get_dispatch_info();
+            let tx_fee = T::Currency::withdraw(
+                &signer,
+                Self::compute_fee(len, &dispatch_info),
+                WithdrawReasons::FEE,
+                ExistenceRequirement::AllowDeath,
+            )?;
 
             // Increment account nonce
             frame_system::Pallet::<T>::inc_account_nonce(signer.clone());
 
-            // Processing fee
+            let (post_info, result) = Self::dispatch_as_signer(*call, signer.clone());
+            Self::deposit_event(Event::Executed(signer.clone(), result));
+
+            // Refund the fee of unused weight
+            let actual_fee = Self::compute_actual_fee(len, &dispatch_info, &post_info);
+            let (tx_fee, refund) = tx_fee.split(actual_fee);
+            T::Currency::resolve_creating(&signer, refund);
+            T::OnChargeTransaction::on_unbalanced(tx_fee);
+
+            // Fee already charged
+            Ok(PostDispatchInfo {
+                actual_weight: Some(
+                    post_info
+                        .calc_actual_weight(&dispatch_info)
+                        .saturating_add(CALL_WEIGHT),
+                ),
+                pays_fee: Pays::No,
+            })
+        }
+
+        /// Dispatches the `call` signed by `signer`, the fee is paid by the sponsor submitting it.
+        ///
+        /// The signer authorizes the sponsorship by signing the `sponsorship` terms together
+        /// with the call. The sponsor pays the regular transaction fee. The maximum call fee of
+        /// the terms caps the call fee computed by this pallet for the signed call, it doesn't
+        /// cover the length of the sponsor's own signature and extensions nor the tip.
+        ///
+        /// # <weight>
+        /// - O(1).
+        /// - Limited storage reads.
+        /// - One DB write (event).
+        /// - Weight of derivative `call` execution + 10,000.
+        /// # </weight>
+        #[pallet::weight({
+            let dispatch_info = call.get_dispatch_info();
+            (dispatch_info.weight + CALL_WEIGHT, dispatch_info.class)
+        })]
+        pub fn sponsored_call(
+            origin: OriginFor<T>,
+            call: Box<<T as Config>::Call>,
+            signer: T::AccountId,
+            signature: Vec<u8>,
+            #[pallet::compact] nonce: T::Index,
+            valid_until: Option<T::BlockNumber>,
+            sponsorship: SponsorshipOf<T>,
+        ) -> DispatchResultWithPostInfo {
+            let sponsor = ensure_signed(origin)?;
+
+            // Length of the encoded call, charged by the length fee
+            let len = Call::<T>::sponsored_call {
+                call: call.clone(),
+                signer: signer.clone(),
+                signature: signature.clone(),
+                nonce,
+                valid_until,
+                sponsorship: sponsorship.clone(),
+            }
+            .encoded_size() as u32;
+
+            Self::ensure_valid_payload(
+                &signer,
+                signature,
+                &nonce,
+                &valid_until,
+                |format| {
+                    T::SignedMessage::signed_message(
+                        format,
+                        &call,
+                        &nonce,
+                        &valid_until,
+                        Some(&sponsorship),
+                    )
+                },
+                |format| T::SignedMessage::outdated_message(format, &call, &nonce),
+            )?;
+
+            // Ensure that sponsorship terms are met
+            if let Some(allowed_sponsor) = sponsorship.sponsor {
+                ensure!(allowed_sponsor == sponsor, Error::<T>::InvalidSponsor);
+            }
+            let dispatch_info = call.get_dispatch_info();
+            if let Some(max_call_fee) = sponsorship.max_call_fee {
+                ensure!(
+                    Self::compute_fee(len, &dispatch_info) <= max_call_fee,
+                    Error::<T>::MaxCallFeeExceeded
+                );
+            }
+
+            // Increment account nonce
+            frame_system::Pallet::<T>::inc_account_nonce(signer.clone());
+
+            let (post_info, result) = Self::dispatch_as_signer(*call, signer.clone());
+            Self::deposit_event(Event::Executed(signer, result));
+
+            // Sponsor pays the transaction fee of the actual weight
+            Ok(PostDispatchInfo {
+                actual_weight: Some(
+                    post_info
+                        .calc_actual_weight(&dispatch_info)
+                        .saturating_add(CALL_WEIGHT),
+                ),
+                pays_fee: Pays::Yes,
+            })
+        }
+
+        /// Dispatches the batch of `calls` signed by `signer` with a single signature and nonce.
+        ///
+        /// If `atomic` is set, the calls are interrupted at the first failure and all of them
+        /// are reverted, like `batch_all` of the utility pallet. Otherwise every call is
+        /// dispatched regardless of the results of others.
+        ///
+        /// The batch contains at least one and at most `MaxBatchCalls` calls, longer batches
+        /// fail to decode. The fee is charged the same way as for a single call.
+        ///
+        /// # <weight>
+        /// - O(C) where C is the number of calls.
+        /// - Limited storage reads.
+        /// - One DB write (event).
+        /// - Weight of derivative `calls` execution + 10,000.
+        /// # </weight>
+        #[pallet::weight({
+            let dispatch_info = Pallet::<T>::batch_dispatch_info(calls);
+            (dispatch_info.weight + CALL_WEIGHT, dispatch_info.class)
+        })]
+        pub fn batch_call(
+            origin: OriginFor<T>,
+            calls: BoundedVec<<T as Config>::Call, T::MaxBatchCalls>,
+            atomic: bool,
+            signer: T::AccountId,
+            signature: Vec<u8>,
+            #[pallet::compact] nonce: T::Index,
+            valid_until: Option<T::BlockNumber>,
+        ) -> DispatchResultWithPostInfo {
+            ensure_none(origin)?;
+            ensure!(!calls.is_empty(), Error::<T>::EmptyBatch);
+
+            // Length of the encoded call, charged by the length fee
+            let len = Call::<T>::batch_call {
+                calls: calls.clone(),
+                atomic,
+                signer: signer.clone(),
+                signature: signature.clone(),
+                nonce,
+                valid_until,
+            }
+            .encoded_size() as u32;
+
+            Self::ensure_valid_payload(
+                &signer,
+                signature,
+                &nonce,
+                &valid_until,
+                |format| {
+                    T::SignedMessage::signed_batch_message(
+                        format,
+                        &calls,
+                        atomic,
+                        &nonce,
+                        &valid_until,
+                    )
+                },
+                |_| None,
+            )?;
+
+            // Processing fee, charged for the declared weight
+            let dispatch_info = Self::batch_dispatch_info(&calls);
             let tx_fee = T::Currency::withdraw(
                 &signer,
-                T::CallFee::get(),
+                Self::compute_fee(len, &dispatch_info),
                 WithdrawReasons::FEE,
                 ExistenceRequirement::AllowDeath,
             )?;
-            T::OnChargeTransaction::on_unbalanced(tx_fee);
 
-            // Dispatch call
-            let new_origin = frame_system::RawOrigin::Signed(signer.clone()).into();
-            let res = call.dispatch_bypass_filter(new_origin).map(|_| ());
-            Self::deposit_event(Event::Executed(signer, res.map_err(|e| e.error)));
+            // Increment account nonce
+            frame_system::Pallet::<T>::inc_account_nonce(signer.clone());
+
+            let (weight, results) = if atomic {
+                with_transaction(|| {
+                    let (weight, results) = Self::dispatch_batch(calls.into_inner(), &signer, true);
+                    if results.iter().all(Result::is_ok) {
+                        TransactionOutcome::Commit((weight, results))
+                    } else {
+                        TransactionOutcome::Rollback((weight, results))
+                    }
+                })
+            } else {
+                Self::dispatch_batch(calls.into_inner(), &signer, false)
+            };
+            Self::deposit_event(Event::BatchExecuted(signer.clone(), results));
+
+            // Refund the fee of unused weight
+            let post_info = PostDispatchInfo {
+                actual_weight: Some(weight),
+                pays_fee: Pays::Yes,
+            };
+            let actual_fee = Self::compute_actual_fee(len, &dispatch_info, &post_info);
+            let (tx_fee, refund) = tx_fee.split(actual_fee);
+            T::Currency::resolve_creating(&signer, refund);
+            T::OnChargeTransaction::on_unbalanced(tx_fee);
 
             // Fee already charged
-            Ok(Pays::No.into())
+            Ok(PostDispatchInfo {
+                actual_weight: Some(
+                    post_info
+                        .calc_actual_weight(&dispatch_info)
+                        .saturating_add(CALL_WEIGHT),
+                ),
+                pays_fee: Pays::No,
+            })
         }
     }
 
     impl<T: Config> Pallet<T> {
+        /// Ensures that the payload isn't stale and its `message` is signed by `signer`.
+        fn ensure_valid_payload(
+            signer: &T::AccountId,
+            signature: Vec<u8>,
+            nonce: &T::Index,
+            valid_until: &Option<T::BlockNumber>,
+            message: impl FnOnce(MessageFormat) -> Option<Vec<u8>>,
+            outdated_message: impl FnOnce(MessageFormat) -> Option<Vec<u8>>,
+        ) -> DispatchResult {
+            // Ensure that transaction isn't stale
+            ensure!(
+                *nonce == frame_system::Pallet::<T>::account_nonce(signer.clone()),
+                Error::<T>::BadNonce,
+            );
+            ensure!(!Self::is_expired(valid_until), Error::<T>::Expired);
+
+            let signature = <T as Config>::Signature::try_from(signature)
+                .map_err(|_| Error::<T>::DecodeFailure)?;
+            let format = signature.message_format();
+            let message = message(format).ok_or(Error::<T>::UnsupportedFormat)?;
+
+            // Ensure that transaction signature is valid
+            if !signature.verify(&message[..], signer) {
+                let outdated = outdated_message(format)
+                    .map_or(false, |message| signature.verify(&message[..], signer));
+                ensure!(!outdated, Error::<T>::OutdatedPayload);
+                return Err(Error::<T>::InvalidSignature.into());
+            }
+
+            Ok(())
+        }
+
+        /// Dispatches the `call` with `signer` origin, returns its post dispatch info and result.
+        fn dispatch_as_signer(
+            call: <T as Config>::Call,
+            signer: T::AccountId,
+        ) -> (PostDispatchInfo, DispatchResult) {
+            let new_origin = frame_system::RawOrigin::Signed(signer).into();
+            match call.dispatch_bypass_filter(new_origin) {
+                Ok(post_info) => (post_info, Ok(())),
+                Err(err) => (err.post_info, Err(err.error)),
+            }
+        }
+
+        /// Dispatches the `calls` with `signer` origin, returns their actual weight and results.
+        ///
+        /// If `interrupt` is set, the calls following the first failed one aren't dispatched.
+        fn dispatch_batch(
+            calls: Vec<<T as Config>::Call>,
+            signer: &T::AccountId,
+            interrupt: bool,
+        ) -> (Weight, Vec<DispatchResult>) {
+            let mut weight: Weight = 0;
+            let mut results = Vec::with_capacity(calls.len());
+            for call in calls {
+                let info = call.get_dispatch_info();
+                let (post_info, result) = Self::dispatch_as_signer(call, signer.clone());
+                weight = weight.saturating_add(post_info.calc_actual_weight(&info));
+                let failed = result.is_err();
+                results.push(result);
+                if interrupt && failed {
+                    break;
+                }
+            }
+            (weight, results)
+        }
+
+        /// Returns the dispatch info of a batch of `calls`, which is operational only if all
+        /// the calls are.
+        pub fn batch_dispatch_info(calls: &[<T as Config>::Call]) -> DispatchInfo {
+            let infos = calls
+                .iter()
+                .map(|call| call.get_dispatch_info())
+                .collect::<Vec<_>>();
+            let operational = !infos.is_empty()
+                && infos
+                    .iter()
+                    .all(|info| info.class == DispatchClass::Operational);
+            DispatchInfo {
+                weight: infos
+                    .iter()
+                    .fold(0, |total: Weight, info| total.saturating_add(info.weight)),
+                class: if operational {
+                    DispatchClass::Operational
+                } else {
+                    DispatchClass::Normal
+                },
+                pays_fee: Pays::Yes,
+            }
+        }
+
         /// Verify custom signature and returns `true` if correct.
         pub fn valid_signature(
             call: &Box<<T as Config>::Call>,
             signer: &T::AccountId,
             signature: &T::Signature,
             nonce: &T::Index,
+            valid_until: &Option<T::BlockNumber>,
+            sponsorship: Option<&SponsorshipOf<T>>,
         ) -> bool {
-            let payload = (T::CallMagicNumber::get(), *nonce, call.clone());
-            signature.verify(&payload.encode()[..], signer)
+            match T::SignedMessage::signed_message(
+                signature.message_format(),
+                call,
+                nonce,
+                valid_until,
+                sponsorship,
+            ) {
+                Some(message) => signature.verify(&message[..], signer),
+                None => false,
+            }
+        }
+
+        /// Returns `true` if the signature is valid for the outdated payload format.
+        pub fn outdated_signature(
+            call: &Box<<T as Config>::Call>,
+            signer: &T::AccountId,
+            signature: &T::Signature,
+            nonce: &T::Index,
+        ) -> bool {
+            match T::SignedMessage::outdated_message(signature.message_format(), call, nonce) {
+                Some(message) => signature.verify(&message[..], signer),
+                None => false,
+            }
+        }
+
+        /// Returns `true` if the current block is past `valid_until`.
+        pub fn is_expired(valid_until: &Option<T::BlockNumber>) -> bool {
+            match valid_until {
+                Some(valid_until) => frame_system::Pallet::<T>::block_number() > *valid_until,
+                None => false,
+            }
+        }
+
+        /// Returns `true` if `signer` is able to pay the fee of a `call` with encoded length `len`.
+        pub fn can_pay_fee(signer: &T::AccountId, len: u32, info: &DispatchInfo) -> bool {
+            let fee = Self::compute_fee(len, info);
+            match T::Currency::free_balance(signer).checked_sub(&fee) {
+                Some(new_balance) => {
+                    T::Currency::ensure_can_withdraw(signer, fee, WithdrawReasons::FEE, new_balance)
+                        .is_ok()
+                }
+                None => false,
+            }
+        }
+
+        /// Returns the fee of a `call` with encoded length `len`, for its declared weight.
+        ///
+        /// `info` is the dispatch info of the derivative call.
+        pub fn compute_fee(len: u32, info: &DispatchInfo) -> BalanceOf<T> {
+            Self::compute_fee_raw(len, info.weight, info.class, info.pays_fee)
+        }
+
+        /// Returns the fee of a `call` with encoded length `len`, for its actual weight.
+        pub fn compute_actual_fee(
+            len: u32,
+            info: &DispatchInfo,
+            post_info: &PostDispatchInfo,
+        ) -> BalanceOf<T> {
+            Self::compute_fee_raw(
+                len,
+                post_info.calc_actual_weight(info),
+                info.class,
+                post_info.pays_fee(info),
+            )
+        }
+
+        /// Returns the pool priority of a `call` with encoded length `len`, derived from its fee.
+        ///
+        /// The priority is the fee paid per part of the block taken by the call, by its weight or
+        /// length, whichever takes more of the block.
+        pub fn compute_priority(len: u32, info: &DispatchInfo) -> TransactionPriority {
+            let fee: u128 = Self::compute_fee(len, info).saturated_into();
+            let max_block_weight = T::BlockWeights::get().max_block;
+            let max_block_length = *T::BlockLength::get().max.get(DispatchClass::Normal) as u64;
+
+            let weight_parts =
+                Self::block_parts(info.weight.saturating_add(CALL_WEIGHT), max_block_weight);
+            let len_parts = Self::block_parts(len as u64, max_block_length);
+
+            (fee / weight_parts.max(len_parts).max(1)).saturated_into::<TransactionPriority>()
+        }
+
+        /// Returns the number of parts of the block taken by `used` out of `max`.
+        fn block_parts(used: u64, max: u64) -> u128 {
+            (used as u128).saturating_mul(BLOCK_PARTS) / (max as u128).max(1)
+        }
+
+        fn compute_fee_raw(
+            len: u32,
+            weight: Weight,
+            class: DispatchClass,
+            pays_fee: Pays,
+        ) -> BalanceOf<T> {
+            if pays_fee == Pays::No {
+                return Zero::zero();
+            }
+
+            let block_weights = T::BlockWeights::get();
+            let weight = weight
+                .saturating_add(CALL_WEIGHT)
+                .min(block_weights.max_block);
+
+            let base_fee = T::WeightToFee::calc(&block_weights.get(class).base_extrinsic);
+            let len_fee = T::TransactionByteFee::get().saturating_mul(len.into());
+            let weight_fee = T::FeeMultiplier::get()
+                .saturating_mul_int(T::WeightToFee::calc(&weight).saturated_into::<u128>())
+                .saturated_into();
+
+            base_fee.saturating_add(len_fee).saturating_add(weight_fee)
+        }
+
+        /// Returns the hash of the genesis block.
+        pub fn genesis_hash() -> T::Hash {
+            frame_system::Pallet::<T>::block_hash(T::BlockNumber::zero())
+        }
+
+        /// Returns the payload which the signer of the SCALE encoded pallet `call` signs to
+        /// authorize it with the current nonce of the signer, which replaces the call one.
+        ///
+        /// The signature of the call is a placeholder of the actual one, it selects the message
+        /// format and is charged by the length fee, so it should be of the same type and length.
+        /// Cosmos placeholders carry the signer public key, which the sign doc refers to.
+        pub fn signing_payload(
+            call: Vec<u8>,
+        ) -> Result<SigningPayload<T::Index, BalanceOf<T>>, PayloadError> {
+            let mut call =
+                Call::<T>::decode_all(&call[..]).map_err(|_| PayloadError::CallDecodeFailure)?;
+            let nonce = match &mut call {
+                Call::call { signer, nonce, .. }
+                | Call::sponsored_call { signer, nonce, .. }
+                | Call::batch_call { signer, nonce, .. } => {
+                    *nonce = frame_system::Pallet::<T>::account_nonce(signer.clone());
+                    *nonce
+                }
+                _ => return Err(PayloadError::CallDecodeFailure),
+            };
+            let (signature, dispatch_info) = match &call {
+                Call::call {
+                    call, signature, ..
+                }
+                | Call::sponsored_call {
+                    call, signature, ..
+                } => (signature, call.get_dispatch_info()),
+                Call::batch_call {
+                    calls, signature, ..
+                } => (signature, Self::batch_dispatch_info(calls)),
+                _ => return Err(PayloadError::CallDecodeFailure),
+            };
+
+            let signature = <T as Config>::Signature::try_from(signature.clone())
+                .map_err(|_| PayloadError::SignatureDecodeFailure)?;
+            let message = Self::call_message(&call, signature.message_format())
+                .ok_or(PayloadError::UnsupportedFormat)?;
+            let len = call.encoded_size() as u32;
+
+            Ok(SigningPayload {
+                signable: signature.signable_message(&message),
+                digest: signature.message_digest(&message),
+                message,
+                nonce,
+                fee: Self::compute_fee(len, &dispatch_info),
+            })
+        }
+
+        /// Checks the SCALE encoded pallet `call` without dispatching it, returns the check
+        /// which fails.
+        ///
+        /// Checks are the same as the transaction pool ones for unsigned calls and batches.
+        /// The signer of sponsored calls isn't charged, so only the sponsorship terms which
+        /// don't depend on the sponsor are checked instead of the signer balance.
+        pub fn dry_run(call: Vec<u8>) -> Result<(), PayloadError> {
+            let len = call.len() as u32;
+            let call =
+                Call::<T>::decode_all(&call[..]).map_err(|_| PayloadError::CallDecodeFailure)?;
+            Self::check_call(&call, len)
         }
     }
 
     pub(crate) const SIGNATURE_DECODE_FAILURE: u8 = 1;
+    pub(crate) const OUTDATED_PAYLOAD: u8 = 2;
+    pub(crate) const EMPTY_BATCH: u8 = 3;
+    pub(crate) const UNSUPPORTED_FORMAT: u8 = 4;
+
+    impl<T: Config> Pallet<T> {
+        /// Returns the message signed for the pallet `call` in the `format`.
+        fn call_message(call: &Call<T>, format: MessageFormat) -> Option<Vec<u8>> {
+            match call {
+                Call::call {
+                    call,
+                    nonce,
+                    valid_until,
+                    ..
+                } => T::SignedMessage::signed_message(format, call, nonce, valid_until, None),
+                Call::sponsored_call {
+                    call,
+                    nonce,
+                    valid_until,
+                    sponsorship,
+                    ..
+                } => T::SignedMessage::signed_message(
+                    format,
+                    call,
+                    nonce,
+                    valid_until,
+                    Some(sponsorship),
+                ),
+                Call::batch_call {
+                    calls,
+                    atomic,
+                    nonce,
+                    valid_until,
+                    ..
+                } => T::SignedMessage::signed_batch_message(
+                    format,
+                    calls,
+                    *atomic,
+                    nonce,
+                    valid_until,
+                ),
+                _ => None,
+            }
+        }
+
+        /// Returns the message which clients using an outdated format sign for the pallet `call`.
+        fn outdated_call_message(call: &Call<T>, format: MessageFormat) -> Option<Vec<u8>> {
+            match call {
+                Call::call { call, nonce, .. } | Call::sponsored_call { call, nonce, .. } => {
+                    T::SignedMessage::outdated_message(format, call, nonce)
+                }
+                _ => None,
+            }
+        }
+
+        /// Checks the pallet `call` with encoded length `len` the way the transaction pool does.
+        fn check_call(call: &Call<T>, len: u32) -> Result<(), PayloadError> {
+            let (signer, signature, nonce, valid_until) = match call {
+                Call::call {
+                    signer,
+                    signature,
+                    nonce,
+                    valid_until,
+                    ..
+                }
+                | Call::sponsored_call {
+                    signer,
+                    signature,
+                    nonce,
+                    valid_until,
+                    ..
+                }
+                | Call::batch_call {
+                    signer,
+                    signature,
+                    nonce,
+                    valid_until,
+                    ..
+                } => (signer, signature, nonce, valid_until),
+                _ => return Err(PayloadError::CallDecodeFailure),
+            };
+            if let Call::batch_call { calls, .. } = call {
+                if calls.is_empty() {
+                    return Err(PayloadError::EmptyBatch);
+                }
+            }
+
+            Self::check_payload(
+                signer,
+                signature,
+                nonce,
+                valid_until,
+                |format| Self::call_message(call, format),
+                |format| Self::outdated_call_message(call, format),
+            )?;
+
+            match call {
+                // Check that sponsorship terms are met, the sponsor pays the call fee
+                Call::sponsored_call {
+                    call, sponsorship, ..
+                } => {
+                    let fee = Self::compute_fee(len, &call.get_dispatch_info());
+                    if sponsorship.max_call_fee.map_or(false, |max| fee > max) {
+                        return Err(PayloadError::MaxCallFeeExceeded);
+                    }
+                }
+                // Check that signer is able to pay the call fee
+                Call::call { call, .. } => {
+                    if !Self::can_pay_fee(signer, len, &call.get_dispatch_info()) {
+                        return Err(PayloadError::CannotPayFee);
+                    }
+                }
+                Call::batch_call { calls, .. } => {
+                    if !Self::can_pay_fee(signer, len, &Self::batch_dispatch_info(calls)) {
+                        return Err(PayloadError::CannotPayFee);
+                    }
+                }
+                _ => (),
+            }
+
+            Ok(())
+        }
+
+        /// Checks the `message` signed by `signer` the way the transaction pool does.
+        fn check_payload(
+            signer: &T::AccountId,
+            signature: &[u8],
+            nonce: &T::Index,
+            valid_until: &Option<T::BlockNumber>,
+            message: impl FnOnce(MessageFormat) -> Option<Vec<u8>>,
+            outdated_message: impl FnOnce(MessageFormat) -> Option<Vec<u8>>,
+        ) -> Result<(), PayloadError> {
+            // Check that tx isn't stale
+            if *nonce != frame_system::Pallet::<T>::account_nonce(signer.clone()) {
+                return Err(PayloadError::BadNonce);
+            }
+            if Self::is_expired(valid_until) {
+                return Err(PayloadError::Expired);
+            }
+
+            // Check signature encoding
+            let signature = <T as Config>::Signature::try_from(signature.to_vec())
+                .map_err(|_| PayloadError::SignatureDecodeFailure)?;
+
+            // Check that signed message format is supported
+            let format = signature.message_format();
+            let message = message(format).ok_or(PayloadError::UnsupportedFormat)?;
+
+            // Verify signature
+            if !signature.verify(&message[..], signer) {
+                if outdated_message(format)
+                    .map_or(false, |message| signature.verify(&message[..], signer))
+                {
+                    // Signature made by a client using an outdated payload format
+                    return Err(PayloadError::OutdatedPayload);
+                }
+                // Signature mismatched to given signer
+                return Err(PayloadError::InvalidSignature);
+            }
+
+            Ok(())
+        }
+    }
 
     #[pallet::validate_unsigned]
     impl<T: Config> frame_support::unsigned::ValidateUnsigned for Pallet<T> {
         type Call = Call<T>;
 
         fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
-            // Call decomposition (we have only one possible value here)
-            let (call, signer, signature, nonce) = match call {
+            let len = call.encoded_size() as u32;
+
+            // Sponsored calls are signed by the sponsor, so they aren't unsigned transactions
+            let (info, signer, nonce, valid_until) = match call {
                 Call::call {
                     call,
                     signer,
-                    signature,
                     nonce,
-                } => (call, signer, signature, nonce),
+                    valid_until,
+                    ..
+                } => (call.get_dispatch_info(), signer, nonce, valid_until),
+                Call::batch_call {
+                    calls,
+                    signer,
+                    nonce,
+                    valid_until,
+                    ..
+                } => (Self::batch_dispatch_info(calls), signer, nonce, valid_until),
                 _ => return InvalidTransaction::Call.into(),
             };
 
-            // Check that tx isn't stale
-            if *nonce != frame_system::Pallet::<T>::account_nonce(signer.clone()) {
-                return InvalidTransaction::Stale.into();
+            if let Err(err) = Self::check_call(call, len) {
+                let invalid = match err {
+                    PayloadError::CallDecodeFailure => InvalidTransaction::Call,
+                    PayloadError::BadNonce | PayloadError::Expired => InvalidTransaction::Stale,
+                    PayloadError::SignatureDecodeFailure => {
+                        InvalidTransaction::Custom(SIGNATURE_DECODE_FAILURE)
+                    }
+                    PayloadError::OutdatedPayload => InvalidTransaction::Custom(OUTDATED_PAYLOAD),
+                    PayloadError::UnsupportedFormat => {
+                        InvalidTransaction::Custom(UNSUPPORTED_FORMAT)
+                    }
+                    PayloadError::EmptyBatch => InvalidTransaction::Custom(EMPTY_BATCH),
+                    PayloadError::InvalidSignature => InvalidTransaction::BadProof,
+                    PayloadError::CannotPayFee | PayloadError::MaxCallFeeExceeded => {
+                        InvalidTransaction::Payment
+                    }
+                };
+                return invalid.into();
             }
 
-            // Check signature encoding
-            if let Ok(signature) = <T as Config>::Signature::try_from(signature.clone()) {
-                // Verify signature
-                if Self::valid_signature(call, signer, &signature, nonce) {
-                    ValidTransaction::with_tag_prefix("CustomSignatures")
-                        .priority(T::UnsignedPriority::get())
-                        .and_provides((call, signer, nonce))
-                        .longevity(64_u64)
-                        .propagate(true)
-                        .build()
-                } else {
-                    // Signature mismatched to given signer
-                    InvalidTransaction::BadProof.into()
+            // Transaction is kept in the pool until it expires, but no longer than 64 blocks
+            let longevity = match valid_until {
+                Some(valid_until) => {
+                    let remaining = valid_until
+                        .saturating_sub(frame_system::Pallet::<T>::block_number())
+                        .saturating_add(One::one());
+                    remaining.saturated_into::<u64>().min(64)
                 }
-            } else {
-                // Signature encoding broken
-                InvalidTransaction::Custom(SIGNATURE_DECODE_FAILURE).into()
-            }
+                None => 64,
+            };
+
+            // Calls signed with the same nonce replace each other regardless of their kind
+            ValidTransaction::with_tag_prefix("CustomSignatures")
+                .priority(Self::compute_priority(len, &info))
+                .and_provides((signer, nonce))
+                .longevity(longevity)
+                .propagate(true)
+                .build()
         }
     }
 }
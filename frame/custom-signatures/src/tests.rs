@@ -1,7 +1,12 @@
 use crate as custom_signatures;
-use codec::Encode;
+use codec::{Compact, Decode, Encode};
 use custom_signatures::*;
-use frame_support::{assert_err, assert_ok, parameter_types};
+use frame_support::{
+    assert_err, assert_ok, parameter_types,
+    traits::Currency,
+    weights::{DispatchClass, DispatchInfo, GetDispatchInfo, IdentityFee, Pays, PostDispatchInfo},
+    BoundedVec,
+};
 use hex_literal::hex;
 use sp_core::{ecdsa, Pair};
 use sp_io::hashing::keccak_256;
@@ -9,9 +14,9 @@ use sp_keyring::AccountKeyring as Keyring;
 use sp_runtime::{
     testing::{Header, H256},
     traits::{BlakeTwo256, IdentifyAccount, IdentityLookup, Verify},
-    transaction_validity::TransactionPriority,
-    MultiSignature, MultiSigner,
+    FixedPointNumber, FixedU128, MultiSignature, MultiSigner,
 };
+use sp_std::convert::TryInto;
 
 pub const ECDSA_SEED: [u8; 32] =
     hex_literal::hex!["7e9c7ad85df5cdc88659f53e06fb2eb9bab3ebc59083a3190eaf2c730332529c"];
@@ -37,6 +42,16 @@ frame_support::construct_runtime!(
 
 parameter_types! {
     pub const BlockHashCount: u64 = 250;
+    pub BlockWeights: frame_system::limits::BlockWeights =
+        frame_system::limits::BlockWeights::builder()
+            .base_block(0)
+            .for_class(DispatchClass::all(), |weights| {
+                weights.base_extrinsic = 100;
+            })
+            .for_class(DispatchClass::non_mandatory(), |weights| {
+                weights.max_total = Some(1_000_000_000_000);
+            })
+            .build_or_panic();
 }
 
 impl frame_system::Config for Runtime {
@@ -59,7 +74,7 @@ impl frame_system::Config for Runtime {
     type OnKilledAccount = ();
     type DbWeight = ();
     type SystemWeightInfo = ();
-    type BlockWeights = ();
+    type BlockWeights = BlockWeights;
     type BlockLength = ();
     type SS58Prefix = ();
     type OnSetCode = ();
@@ -82,21 +97,31 @@ impl pallet_balances::Config for Runtime {
 }
 
 parameter_types! {
-    pub const Priority: TransactionPriority = TransactionPriority::max_value();
-    pub const CallFee: Balance = 42;
+    pub const TransactionByteFee: Balance = 1;
+    pub storage FeeMultiplier: FixedU128 = FixedU128::saturating_from_integer(2);
     pub const CallMagicNumber: u16 = 0xff50;
+    pub const MaxBatchCalls: u32 = 3;
+    pub const ChainName: &'static str = "Astar Test";
+    pub const ChainId: u64 = 592;
 }
 
 impl Config for Runtime {
     type Event = Event;
     type Call = Call;
-    type Signature = ethereum::EthereumSignature;
+    type Signature = foreign::ForeignSignature;
     type Signer = <Signature as Verify>::Signer;
+    type SignedMessage = (
+        ScaleMessage,
+        eip712::Eip712Message<ChainName, ChainId>,
+        text::TextMessage<ChainName, TransferArgs>,
+    );
     type CallMagicNumber = CallMagicNumber;
+    type MaxBatchCalls = MaxBatchCalls;
     type Currency = Balances;
-    type CallFee = CallFee;
+    type WeightToFee = IdentityFee<Balance>;
+    type TransactionByteFee = TransactionByteFee;
+    type FeeMultiplier = FeeMultiplier;
     type OnChargeTransaction = ();
-    type UnsignedPriority = Priority;
 }
 
 fn new_test_ext() -> sp_io::TestExternalities {
@@ -113,6 +138,61 @@ fn new_test_ext() -> sp_io::TestExternalities {
     storage.into()
 }
 
+/// Genesis hash set by the `frame_system` genesis config.
+const GENESIS_HASH: H256 = H256([69u8; 32]);
+
+/// Payload signed by the clients, transaction version of the test runtime is zero.
+fn scale_payload(nonce: u32, valid_until: Option<BlockNumber>, call: &Call) -> Vec<u8> {
+    (
+        0xff50u16,
+        PAYLOAD_VERSION,
+        GENESIS_HASH,
+        0u32,
+        nonce,
+        valid_until,
+        call,
+    )
+        .encode()
+}
+
+/// Payload signed by the clients for a batch of calls.
+fn batch_payload(nonce: u32, atomic: bool, calls: &[Call]) -> Vec<u8> {
+    (
+        0xff50u16,
+        BATCH_PAYLOAD_VERSION,
+        GENESIS_HASH,
+        0u32,
+        nonce,
+        None::<BlockNumber>,
+        atomic,
+        calls,
+    )
+        .encode()
+}
+
+/// Payload of a call paid by the sponsor.
+fn sponsored_payload(
+    nonce: u32,
+    call: &Call,
+    sponsorship: &Sponsorship<AccountId, Balance>,
+) -> Vec<u8> {
+    let mut payload = scale_payload(nonce, None, call);
+    sponsorship.encode_to(&mut payload);
+    payload
+}
+
+/// Length of the encoded `call` extrinsic which is charged by the pallet.
+fn encoded_len(call: &Call, signer: &AccountId, signature: &[u8], nonce: u32) -> u32 {
+    custom_signatures::Call::<Runtime>::call {
+        call: Box::new(call.clone()),
+        signer: signer.clone(),
+        signature: signature.to_vec(),
+        nonce,
+        valid_until: None,
+    }
+    .encoded_size() as u32
+}
+
 /// Simple `eth_sign` implementation, should be equal to exported by RPC
 fn eth_sign(seed: &[u8; 32], data: &[u8]) -> Vec<u8> {
     let call_msg = ethereum::signable_message(data);
@@ -126,6 +206,28 @@ fn eth_sign(seed: &[u8; 32], data: &[u8]) -> Vec<u8> {
     out
 }
 
+/// Simple `eth_signTypedData_v4` implementation for the typed data `message`.
+fn eip712_sign(seed: &[u8; 32], message: &[u8]) -> Vec<u8> {
+    let ecdsa_msg = libsecp256k1::Message::parse(&keccak_256(message));
+    let secret = libsecp256k1::SecretKey::parse(&seed).expect("valid seed");
+    let (signature, recovery_id) = libsecp256k1::sign(&ecdsa_msg, &secret);
+    let mut out = [0u8; 65];
+    out[..64].copy_from_slice(&signature.serialize()[..]);
+    out[64] = recovery_id.serialize() + 27;
+    foreign::ForeignSignature::Eip712(eip712::Eip712Signature(out)).encode()
+}
+
+/// Simple `personal_sign` implementation for the human readable `text`.
+fn text_sign(seed: &[u8; 32], text: &[u8]) -> Vec<u8> {
+    let ecdsa_msg = libsecp256k1::Message::parse(&keccak_256(&text::signable_message(text)));
+    let secret = libsecp256k1::SecretKey::parse(&seed).expect("valid seed");
+    let (signature, recovery_id) = libsecp256k1::sign(&ecdsa_msg, &secret);
+    let mut out = [0u8; 65];
+    out[..64].copy_from_slice(&signature.serialize()[..]);
+    out[64] = recovery_id.serialize() + 27;
+    foreign::ForeignSignature::Text(text::TextSignature(out)).encode()
+}
+
 #[test]
 fn eth_sign_works() {
     let seed = hex!["ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"];
@@ -146,7 +248,7 @@ fn invalid_signature() {
     let signature = Vec::from(&hex!["dd0992d40e5cdf99db76bed162808508ac65acd7ae2fdc8573594f03ed9c939773e813181788fc02c3c68f3fdc592759b35f6354484343e18cb5317d34dab6c61b"][..]);
     new_test_ext().execute_with(|| {
         assert_err!(
-            CustomSignatures::call(Origin::none(), Box::new(call), bob, signature, 0),
+            CustomSignatures::call(Origin::none(), Box::new(call), bob, signature, 0, None),
             Error::<Runtime>::InvalidSignature,
         );
     });
@@ -166,8 +268,11 @@ fn balance_transfer() {
             value: 1_000,
         }
         .into();
-        let payload = (0xff50u16, 0u32, call.clone());
-        let signature = eth_sign(&ECDSA_SEED, payload.encode().as_ref()).into();
+        let payload = scale_payload(0, None, &call);
+        let signature: Vec<u8> = eth_sign(&ECDSA_SEED, payload.as_ref()).into();
+        let len = encoded_len(&call, &account, &signature, 0);
+        let fee = CustomSignatures::compute_fee(len, &call.get_dispatch_info());
+        assert!(fee > 0);
 
         assert_eq!(System::account(account.clone()).nonce, 0);
         assert_ok!(CustomSignatures::call(
@@ -176,12 +281,18 @@ fn balance_transfer() {
             account.clone(),
             signature,
             0,
+            None,
         ));
         assert_eq!(System::account(alice.clone()).data.free, 1_000);
         assert_eq!(System::account(account.clone()).nonce, 1);
-        assert_eq!(System::account(account.clone()).data.free, 999_998_958);
+        assert_eq!(
+            System::account(account.clone()).data.free,
+            1_000_000_000 - 1_000 - fee
+        );
+        // Fee is burned by the test runtime
+        assert_eq!(Balances::total_issuance(), 1_000_000_000 - fee);
 
-        let signature = eth_sign(&ECDSA_SEED, payload.encode().as_ref()).into();
+        let signature = eth_sign(&ECDSA_SEED, payload.as_ref()).into();
         assert_err!(
             CustomSignatures::call(
                 Origin::none(),
@@ -189,12 +300,15 @@ fn balance_transfer() {
                 account.clone(),
                 signature,
                 0,
+                None,
             ),
             Error::<Runtime>::BadNonce,
         );
 
-        let payload = (0xff50u16, 1u32, call.clone());
-        let signature = eth_sign(&ECDSA_SEED, payload.encode().as_ref()).into();
+        let payload = scale_payload(1, None, &call);
+        let signature: Vec<u8> = eth_sign(&ECDSA_SEED, payload.as_ref()).into();
+        let len = encoded_len(&call, &account, &signature, 1);
+        let second_fee = CustomSignatures::compute_fee(len, &call.get_dispatch_info());
         assert_eq!(System::account(account.clone()).nonce, 1);
         assert_ok!(CustomSignatures::call(
             Origin::none(),
@@ -202,42 +316,1338 @@ fn balance_transfer() {
             account.clone(),
             signature,
             1,
+            None,
         ));
         assert_eq!(System::account(alice).data.free, 2_000);
         assert_eq!(System::account(account.clone()).nonce, 2);
-        assert_eq!(System::account(account.clone()).data.free, 999_997_916);
+        assert_eq!(
+            System::account(account.clone()).data.free,
+            1_000_000_000 - 2_000 - fee - second_fee
+        );
     })
 }
 
 #[test]
-fn call_fixtures() {
-    use sp_core::crypto::Ss58Codec;
+fn fee_follows_weight_and_length() {
+    new_test_ext().execute_with(|| {
+        let info = DispatchInfo {
+            weight: 1_000,
+            class: DispatchClass::Normal,
+            pays_fee: Pays::Yes,
+        };
 
-    let seed = hex!["ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"];
-    let pair = ecdsa::Pair::from_seed(&seed);
-    assert_eq!(
-        MultiSigner::from(pair.public())
-            .into_account()
-            .to_ss58check(),
-        "5EGynCAEvv8NLeHx8vDMvb8hTcEcMYUMWCDQEEncNEfNWB2W",
-    );
+        // Base fee 100, length fee 10 and doubled weight fee for 1_000 + 10_000
+        assert_eq!(CustomSignatures::compute_fee(10, &info), 22_110);
 
-    let dest =
-        AccountId::from_ss58check("5GVwcV6EzxxYbXBm7H6dtxc9TCgL4oepMXtgqWYEc3VXJoaf").unwrap();
-    let call: Call = pallet_balances::Call::<Runtime>::transfer { dest, value: 1000 }.into();
-    assert_eq!(
-        call.encode(),
-        hex!["0000c4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f"],
-    );
+        // Unused weight isn't charged
+        let post_info = PostDispatchInfo {
+            actual_weight: Some(400),
+            pays_fee: Pays::Yes,
+        };
+        assert_eq!(
+            CustomSignatures::compute_actual_fee(10, &info, &post_info),
+            20_910
+        );
 
-    let payload = (0xff50u16, 0u32, call.clone());
-    assert_eq!(
-        payload.encode(),
-        hex![
-            "50ff000000000000c4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f"
-        ],
-    );
+        // Actual weight above the declared one is ignored
+        let post_info = PostDispatchInfo {
+            actual_weight: Some(2_000),
+            pays_fee: Pays::Yes,
+        };
+        assert_eq!(
+            CustomSignatures::compute_actual_fee(10, &info, &post_info),
+            22_110
+        );
+    })
+}
+
+#[test]
+fn priority_follows_fee() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::TransactionSource;
+
+    new_test_ext().execute_with(|| {
+        let info = DispatchInfo {
+            weight: 1_000,
+            class: DispatchClass::Normal,
+            pays_fee: Pays::Yes,
+        };
+
+        // Fee 22_110 per billionth of the block length taken by 10 bytes
+        assert_eq!(
+            CustomSignatures::compute_priority(10, &info),
+            22_110 / 2_543
+        );
+
+        // Heavy calls pay per billionth of the block weight they take
+        let heavy_info = DispatchInfo {
+            weight: 1_000_000_000,
+            ..info
+        };
+        assert_eq!(
+            CustomSignatures::compute_priority(10, &heavy_info),
+            2_000_020_110 / 1_000_010
+        );
+
+        // Free calls have no priority
+        let free_info = DispatchInfo {
+            pays_fee: Pays::No,
+            ..info
+        };
+        assert_eq!(CustomSignatures::compute_priority(10, &free_info), 0);
+
+        // Pool priority of signed calls is derived from their fee
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: <Runtime as frame_system::Config>::AccountId = Keyring::Alice.into();
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice,
+            value: 1_000,
+        }
+        .into();
+        let signature = eth_sign(&ECDSA_SEED, scale_payload(0, None, &call).as_ref());
+        let len = encoded_len(&call, &account, &signature, 0);
+        let unsigned_call = custom_signatures::Call::call {
+            call: Box::new(call.clone()),
+            signer: account,
+            signature,
+            nonce: 0,
+            valid_until: None,
+        };
+        let validity =
+            CustomSignatures::validate_unsigned(TransactionSource::External, &unsigned_call)
+                .unwrap();
+        assert_eq!(
+            validity.priority,
+            CustomSignatures::compute_priority(len, &call.get_dispatch_info())
+        );
+    })
+}
+
+#[test]
+fn priority_of_expensive_calls_follows_fee() {
+    new_test_ext().execute_with(|| {
+        // Fees which used to saturate the priority once scaled by the number of calls in a block
+        FeeMultiplier::set(&FixedU128::saturating_from_integer(10_000_000_000u64));
+        let info = DispatchInfo {
+            weight: 1_000,
+            class: DispatchClass::Normal,
+            pays_fee: Pays::Yes,
+        };
+        let pricier_info = DispatchInfo {
+            weight: 2_000,
+            ..info
+        };
+        assert!(
+            CustomSignatures::compute_fee(10, &info).saturating_mul(393_216) > u64::MAX as Balance
+        );
+
+        let priority = CustomSignatures::compute_priority(10, &info);
+        let pricier_priority = CustomSignatures::compute_priority(10, &pricier_info);
+        assert!(priority < pricier_priority);
+        assert!(pricier_priority < u64::MAX);
+    })
+}
+
+#[test]
+fn unfunded_signer_is_rejected() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+    new_test_ext().execute_with(|| {
+        // Signer without any funds
+        let seed = [1u8; 32];
+        let pair = ecdsa::Pair::from_seed(&seed);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: <Runtime as frame_system::Config>::AccountId = Keyring::Alice.into();
+
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into();
+        let signature = eth_sign(&seed, scale_payload(0, None, &call).as_ref());
+        let unsigned_call = custom_signatures::Call::call {
+            call: Box::new(call.clone()),
+            signer: account.clone(),
+            signature: signature.clone(),
+            nonce: 0,
+            valid_until: None,
+        };
+        let fee = CustomSignatures::compute_fee(
+            unsigned_call.encoded_size() as u32,
+            &call.get_dispatch_info(),
+        );
+
+        // Validly signed calls of empty accounts don't enter the pool
+        assert_eq!(
+            CustomSignatures::validate_unsigned(TransactionSource::External, &unsigned_call),
+            InvalidTransaction::Payment.into(),
+        );
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                None,
+            ),
+            pallet_balances::Error::<Runtime>::InsufficientBalance,
+        );
+        assert_eq!(System::account(account.clone()).nonce, 0);
+
+        // Balance is below the fee
+        let _ = Balances::deposit_creating(&account, fee - 1);
+        assert_eq!(
+            CustomSignatures::validate_unsigned(TransactionSource::External, &unsigned_call),
+            InvalidTransaction::Payment.into(),
+        );
+
+        // Fee can be paid
+        let _ = Balances::deposit_creating(&account, 1_002);
+        assert_ok!(CustomSignatures::validate_unsigned(
+            TransactionSource::External,
+            &unsigned_call
+        ));
+        assert_ok!(CustomSignatures::call(
+            Origin::none(),
+            Box::new(call),
+            account.clone(),
+            signature,
+            0,
+            None,
+        ));
+        assert_eq!(System::account(alice).data.free, 1_000);
+        assert_eq!(System::account(account).data.free, 1);
+    })
+}
+
+#[test]
+fn sponsored_call_works() {
+    use sp_runtime::traits::Hash;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        // Signer without any funds
+        let seed = [1u8; 32];
+        let pair = ecdsa::Pair::from_seed(&seed);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let relayer: AccountId = Keyring::Bob.into();
+
+        let call: Call = frame_system::Call::<Runtime>::remark_with_event {
+            remark: b"Hello Astar".to_vec(),
+        }
+        .into();
+        let sponsorship = Sponsorship {
+            sponsor: Some(relayer.clone()),
+            max_call_fee: None,
+        };
+        let signature = eth_sign(&seed, &sponsored_payload(0, &call, &sponsorship));
+
+        // Only the allowed sponsor can submit the call
+        assert_err!(
+            CustomSignatures::sponsored_call(
+                Origin::signed(Keyring::Alice.into()),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                None,
+                sponsorship.clone(),
+            ),
+            Error::<Runtime>::InvalidSponsor,
+        );
+
+        // Sponsorship terms are signed
+        assert_err!(
+            CustomSignatures::sponsored_call(
+                Origin::signed(Keyring::Alice.into()),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                None,
+                Sponsorship {
+                    sponsor: None,
+                    max_call_fee: None,
+                },
+            ),
+            Error::<Runtime>::InvalidSignature,
+        );
+
+        // Sponsored signature can't be used to charge the signer
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                None,
+            ),
+            Error::<Runtime>::InvalidSignature,
+        );
+
+        let post_info = CustomSignatures::sponsored_call(
+            Origin::signed(relayer),
+            Box::new(call),
+            account.clone(),
+            signature,
+            0,
+            None,
+            sponsorship,
+        )
+        .unwrap();
+        assert_eq!(post_info.pays_fee, Pays::Yes);
+
+        // Call is dispatched by the signer
+        System::assert_has_event(Event::System(frame_system::Event::Remarked(
+            account.clone(),
+            BlakeTwo256::hash(b"Hello Astar"),
+        )));
+        System::assert_has_event(Event::CustomSignatures(custom_signatures::Event::Executed(
+            account.clone(),
+            Ok(()),
+        )));
+        assert_eq!(System::account(account.clone()).nonce, 1);
+        assert_eq!(System::account(account).data.free, 0);
+    })
+}
+
+#[test]
+fn sponsored_call_respects_max_call_fee() {
+    use frame_support::traits::UnfilteredDispatchable;
+
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let relayer: AccountId = Keyring::Bob.into();
+
+        let call: Call = frame_system::Call::<Runtime>::remark_with_event {
+            remark: b"Hello Astar".to_vec(),
+        }
+        .into();
+        let sponsored_call = |max_call_fee| {
+            let sponsorship = Sponsorship {
+                sponsor: None,
+                max_call_fee: Some(max_call_fee),
+            };
+            let signature = eth_sign(&ECDSA_SEED, &sponsored_payload(0, &call, &sponsorship));
+            custom_signatures::Call::<Runtime>::sponsored_call {
+                call: Box::new(call.clone()),
+                signer: account.clone(),
+                signature,
+                nonce: 0,
+                valid_until: None,
+                sponsorship,
+            }
+        };
+
+        // Call fee depends on the length of the call, which doesn't change with the maximum
+        let fee = CustomSignatures::compute_fee(
+            sponsored_call(0).encoded_size() as u32,
+            &call.get_dispatch_info(),
+        );
+
+        assert_err!(
+            sponsored_call(fee - 1).dispatch_bypass_filter(Origin::signed(relayer.clone())),
+            Error::<Runtime>::MaxCallFeeExceeded,
+        );
+        assert_ok!(sponsored_call(fee).dispatch_bypass_filter(Origin::signed(relayer)));
+        assert_eq!(System::account(account).nonce, 1);
+    })
+}
+
+/// Transfers to Alice, the second one fails because of insufficient balance.
+fn batch_calls() -> Vec<Call> {
+    let alice: AccountId = Keyring::Alice.into();
+    vec![
+        pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into(),
+        pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000_000_000_000,
+        }
+        .into(),
+        pallet_balances::Call::<Runtime>::transfer {
+            dest: alice,
+            value: 500,
+        }
+        .into(),
+    ]
+}
+
+/// Signed batch of `calls`, which fits the batch size limit.
+fn bounded(calls: Vec<Call>) -> BoundedVec<Call, MaxBatchCalls> {
+    calls.try_into().expect("batch fits the limit")
+}
+
+#[test]
+fn batch_call_works() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: AccountId = Keyring::Alice.into();
+
+        let calls = batch_calls();
+        let signature = eth_sign(&ECDSA_SEED, &batch_payload(0, false, &calls));
+
+        // Atomicity is signed
+        assert_err!(
+            CustomSignatures::batch_call(
+                Origin::none(),
+                bounded(calls.clone()),
+                true,
+                account.clone(),
+                signature.clone(),
+                0,
+                None,
+            ),
+            Error::<Runtime>::InvalidSignature,
+        );
+
+        // Every call is dispatched regardless of failures
+        assert_ok!(CustomSignatures::batch_call(
+            Origin::none(),
+            bounded(calls),
+            false,
+            account.clone(),
+            signature,
+            0,
+            None,
+        ));
+        System::assert_has_event(Event::CustomSignatures(
+            custom_signatures::Event::BatchExecuted(
+                account.clone(),
+                vec![
+                    Ok(()),
+                    Err(pallet_balances::Error::<Runtime>::InsufficientBalance.into()),
+                    Ok(()),
+                ],
+            ),
+        ));
+        assert_eq!(System::account(alice).data.free, 1_500);
+        assert_eq!(System::account(account).nonce, 1);
+    })
+}
+
+#[test]
+fn atomic_batch_call_is_reverted() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: AccountId = Keyring::Alice.into();
+
+        let calls = batch_calls();
+        let signature = eth_sign(&ECDSA_SEED, &batch_payload(0, true, &calls));
+        assert_ok!(CustomSignatures::batch_call(
+            Origin::none(),
+            bounded(calls),
+            true,
+            account.clone(),
+            signature,
+            0,
+            None,
+        ));
+
+        // Calls are interrupted at the first failure and reverted
+        System::assert_has_event(Event::CustomSignatures(
+            custom_signatures::Event::BatchExecuted(
+                account.clone(),
+                vec![
+                    Ok(()),
+                    Err(pallet_balances::Error::<Runtime>::InsufficientBalance.into()),
+                ],
+            ),
+        ));
+        assert_eq!(System::account(alice).data.free, 0);
+
+        // Nonce and fee aren't reverted
+        assert_eq!(System::account(account.clone()).nonce, 1);
+        assert!(System::account(account).data.free < 1_000_000_000);
+    })
+}
+
+#[test]
+fn batch_call_is_bounded() {
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+
+        let mut calls = batch_calls();
+        calls.push(calls[0].clone());
+
+        // Encoded `batch_call` with the given calls
+        let encode = |calls: &[Call]| {
+            (
+                2u8,
+                calls,
+                false,
+                &account,
+                Vec::<u8>::new(),
+                Compact(0u32),
+                None::<BlockNumber>,
+            )
+                .encode()
+        };
+
+        // Batches above the limit fail to decode
+        assert!(custom_signatures::Call::<Runtime>::decode(&mut &encode(&calls[..3])[..]).is_ok());
+        assert!(custom_signatures::Call::<Runtime>::decode(&mut &encode(&calls[..])[..]).is_err());
+    })
+}
+
+#[test]
+fn empty_batch_call_is_rejected() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+
+        let signature = eth_sign(&ECDSA_SEED, &batch_payload(0, false, &[]));
+
+        assert_eq!(
+            CustomSignatures::validate_unsigned(
+                TransactionSource::External,
+                &custom_signatures::Call::batch_call {
+                    calls: bounded(vec![]),
+                    atomic: false,
+                    signer: account.clone(),
+                    signature: signature.clone(),
+                    nonce: 0,
+                    valid_until: None,
+                }
+            ),
+            InvalidTransaction::Custom(custom_signatures::pallet::EMPTY_BATCH).into(),
+        );
+        assert_err!(
+            CustomSignatures::batch_call(
+                Origin::none(),
+                bounded(vec![]),
+                false,
+                account.clone(),
+                signature,
+                0,
+                None,
+            ),
+            Error::<Runtime>::EmptyBatch,
+        );
 
-    let signature = hex!["6ecb474240df46ee5cde8f51cf5ccf4c75d15ac3c1772aea6c8189604263c98b16350883438c4eaa447ebcb6889d516f70351fd704bb3521072cd2fccc7c99dc1c"];
-    assert_eq!(eth_sign(&seed, payload.encode().as_ref()), signature)
+        // Neither nonce nor fee is charged
+        assert_eq!(System::account(account.clone()).nonce, 0);
+        assert_eq!(System::account(account).data.free, 1_000_000_000);
+    })
+}
+
+#[test]
+fn calls_with_same_nonce_replace_each_other() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::{TransactionSource, ValidTransaction};
+
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+
+        let calls = batch_calls();
+        let call = custom_signatures::Call::<Runtime>::call {
+            call: Box::new(calls[0].clone()),
+            signer: account.clone(),
+            signature: eth_sign(&ECDSA_SEED, &scale_payload(0, None, &calls[0])),
+            nonce: 0,
+            valid_until: None,
+        };
+        let batch_call = custom_signatures::Call::<Runtime>::batch_call {
+            calls: bounded(calls.clone()),
+            atomic: false,
+            signer: account.clone(),
+            signature: eth_sign(&ECDSA_SEED, &batch_payload(0, false, &calls)),
+            nonce: 0,
+            valid_until: None,
+        };
+
+        // Both calls provide the tag of the signer nonce
+        let provides = ValidTransaction::with_tag_prefix("CustomSignatures")
+            .and_provides((account, 0u32))
+            .build()
+            .unwrap()
+            .provides;
+        let validity =
+            CustomSignatures::validate_unsigned(TransactionSource::External, &call).unwrap();
+        assert_eq!(validity.provides, provides);
+        let validity =
+            CustomSignatures::validate_unsigned(TransactionSource::External, &batch_call).unwrap();
+        assert_eq!(validity.provides, provides);
+    })
+}
+
+#[test]
+fn outdated_payload_is_rejected() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: <Runtime as frame_system::Config>::AccountId = Keyring::Alice.into();
+
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice,
+            value: 1_000,
+        }
+        .into();
+        let payload = (0xff50u16, 0u32, call.clone());
+        let signature: Vec<u8> = eth_sign(&ECDSA_SEED, payload.encode().as_ref());
+
+        assert_eq!(
+            CustomSignatures::validate_unsigned(
+                TransactionSource::External,
+                &custom_signatures::Call::call {
+                    call: Box::new(call.clone()),
+                    signer: account.clone(),
+                    signature: signature.clone(),
+                    nonce: 0,
+                    valid_until: None,
+                }
+            ),
+            InvalidTransaction::Custom(custom_signatures::pallet::OUTDATED_PAYLOAD).into(),
+        );
+        assert_err!(
+            CustomSignatures::call(Origin::none(), Box::new(call), account, signature, 0, None),
+            Error::<Runtime>::OutdatedPayload,
+        );
+    })
+}
+
+#[test]
+fn expired_call_is_rejected() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::{InvalidTransaction, TransactionSource};
+
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: <Runtime as frame_system::Config>::AccountId = Keyring::Alice.into();
+
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into();
+        let signature = eth_sign(&ECDSA_SEED, scale_payload(0, Some(10), &call).as_ref());
+        let unsigned_call = custom_signatures::Call::call {
+            call: Box::new(call.clone()),
+            signer: account.clone(),
+            signature: signature.clone(),
+            nonce: 0,
+            valid_until: Some(10),
+        };
+
+        // Call is kept in the pool only until it expires.
+        System::set_block_number(8);
+        let validity =
+            CustomSignatures::validate_unsigned(TransactionSource::External, &unsigned_call)
+                .unwrap();
+        assert_eq!(validity.longevity, 3);
+
+        System::set_block_number(11);
+        assert_eq!(
+            CustomSignatures::validate_unsigned(TransactionSource::External, &unsigned_call),
+            InvalidTransaction::Stale.into(),
+        );
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                Some(10),
+            ),
+            Error::<Runtime>::Expired,
+        );
+
+        // Expiry is signed, so it can't be extended by the submitter.
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature.clone(),
+                0,
+                Some(20),
+            ),
+            Error::<Runtime>::InvalidSignature,
+        );
+
+        // Call is valid up to and including the last block.
+        System::set_block_number(10);
+        assert_ok!(CustomSignatures::call(
+            Origin::none(),
+            Box::new(call),
+            account,
+            signature,
+            0,
+            Some(10),
+        ));
+        assert_eq!(System::account(alice).data.free, 1_000);
+    })
+}
+
+#[test]
+fn signing_payload_works() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: AccountId = Keyring::Alice.into();
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into();
+        let unsigned_call = |signature: Vec<u8>, nonce| custom_signatures::Call::<Runtime>::call {
+            call: Box::new(call.clone()),
+            signer: account.clone(),
+            signature,
+            nonce,
+            valid_until: None,
+        };
+
+        // Nonce of the call is replaced by the signer one
+        let payload =
+            CustomSignatures::signing_payload(unsigned_call(vec![0u8; 65], 7).encode()).unwrap();
+        assert_eq!(payload.message, scale_payload(0, None, &call));
+        assert_eq!(
+            payload.signable,
+            ethereum::signable_message(&payload.message)
+        );
+        assert_eq!(payload.digest, keccak_256(&payload.signable));
+        assert_eq!(payload.nonce, 0);
+        // Fee of the call with the placeholder signature
+        let len = encoded_len(&call, &account, &[0u8; 65], 0);
+        assert_eq!(
+            payload.fee,
+            CustomSignatures::compute_fee(len, &call.get_dispatch_info())
+        );
+
+        // Signed payload passes the dry run, which doesn't dispatch the call
+        let signature = eth_sign(&ECDSA_SEED, &payload.message);
+        assert_eq!(
+            CustomSignatures::dry_run(unsigned_call(signature, 0).encode()),
+            Ok(())
+        );
+        assert_eq!(System::account_nonce(account.clone()), 0);
+        assert_eq!(System::account(alice).data.free, 0);
+
+        // Message and fee follow the placeholder signature type
+        let placeholder =
+            foreign::ForeignSignature::Eip712(eip712::Eip712Signature([0u8; 65])).encode();
+        let payload =
+            CustomSignatures::signing_payload(unsigned_call(placeholder.clone(), 0).encode())
+                .unwrap();
+        assert_eq!(
+            Some(payload.message.clone()),
+            <Runtime as Config>::SignedMessage::signed_message(
+                MessageFormat::Eip712,
+                &call,
+                &0,
+                &None,
+                None
+            )
+        );
+        assert_eq!(payload.signable, payload.message);
+        assert_eq!(payload.digest, keccak_256(&payload.message));
+        let len = encoded_len(&call, &account, &placeholder, 0);
+        assert_eq!(
+            payload.fee,
+            CustomSignatures::compute_fee(len, &call.get_dispatch_info())
+        );
+        assert_eq!(
+            CustomSignatures::dry_run(
+                unsigned_call(eip712_sign(&ECDSA_SEED, &payload.message), 0).encode()
+            ),
+            Ok(())
+        );
+
+        assert_eq!(
+            CustomSignatures::signing_payload(unsigned_call(vec![0u8; 64], 0).encode()),
+            Err(PayloadError::SignatureDecodeFailure)
+        );
+
+        // Nonce follows the signer account
+        System::inc_account_nonce(account.clone());
+        let payload = CustomSignatures::signing_payload(
+            custom_signatures::Call::<Runtime>::call {
+                call: Box::new(call.clone()),
+                signer: account,
+                signature: vec![0u8; 65],
+                nonce: 0,
+                valid_until: Some(10),
+            }
+            .encode(),
+        )
+        .unwrap();
+        assert_eq!(payload.message, scale_payload(1, Some(10), &call));
+        assert_eq!(payload.nonce, 1);
+    })
+}
+
+#[test]
+fn sponsored_call_signing_payload_works() {
+    new_test_ext().execute_with(|| {
+        // Signer without any funds, the call is paid by the sponsor
+        let seed = [1u8; 32];
+        let account = MultiSigner::from(ecdsa::Pair::from_seed(&seed).public()).into_account();
+        let call: Call = frame_system::Call::<Runtime>::remark_with_event {
+            remark: b"Hello Astar".to_vec(),
+        }
+        .into();
+        let sponsored_call =
+            |signature: Vec<u8>, max_call_fee| custom_signatures::Call::<Runtime>::sponsored_call {
+                call: Box::new(call.clone()),
+                signer: account.clone(),
+                signature,
+                nonce: 0,
+                valid_until: None,
+                sponsorship: Sponsorship {
+                    sponsor: None,
+                    max_call_fee,
+                },
+            };
+
+        let placeholder = sponsored_call(vec![0u8; 65], None);
+        let payload = CustomSignatures::signing_payload(placeholder.encode()).unwrap();
+        let sponsorship = Sponsorship {
+            sponsor: None,
+            max_call_fee: None,
+        };
+        assert_eq!(payload.message, sponsored_payload(0, &call, &sponsorship));
+        assert_eq!(
+            payload.fee,
+            CustomSignatures::compute_fee(
+                placeholder.encoded_size() as u32,
+                &call.get_dispatch_info()
+            )
+        );
+        let signature = eth_sign(&seed, &payload.message);
+        assert_eq!(
+            CustomSignatures::dry_run(sponsored_call(signature, None).encode()),
+            Ok(())
+        );
+
+        // Maximum call fee of the terms is checked
+        let max_call_fee = Some(payload.fee - 1);
+        let payload =
+            CustomSignatures::signing_payload(sponsored_call(vec![0u8; 65], max_call_fee).encode())
+                .unwrap();
+        let signature = eth_sign(&seed, &payload.message);
+        assert_eq!(
+            CustomSignatures::dry_run(sponsored_call(signature, max_call_fee).encode()),
+            Err(PayloadError::MaxCallFeeExceeded)
+        );
+    })
+}
+
+#[test]
+fn batch_call_signing_payload_works() {
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let calls = batch_calls();
+        let batch_call =
+            |calls: Vec<Call>, signature: Vec<u8>| custom_signatures::Call::<Runtime>::batch_call {
+                calls: bounded(calls),
+                atomic: true,
+                signer: account.clone(),
+                signature,
+                nonce: 0,
+                valid_until: None,
+            };
+
+        let placeholder = batch_call(calls.clone(), vec![0u8; 65]);
+        let payload = CustomSignatures::signing_payload(placeholder.encode()).unwrap();
+        assert_eq!(payload.message, batch_payload(0, true, &calls));
+        assert_eq!(
+            payload.fee,
+            CustomSignatures::compute_fee(
+                placeholder.encoded_size() as u32,
+                &CustomSignatures::batch_dispatch_info(&calls)
+            )
+        );
+        let signature = eth_sign(&ECDSA_SEED, &payload.message);
+        assert_eq!(
+            CustomSignatures::dry_run(batch_call(calls, signature).encode()),
+            Ok(())
+        );
+
+        let signature = eth_sign(&ECDSA_SEED, &batch_payload(0, true, &[]));
+        assert_eq!(
+            CustomSignatures::dry_run(batch_call(vec![], signature).encode()),
+            Err(PayloadError::EmptyBatch)
+        );
+    })
+}
+
+#[test]
+fn dry_run_explains_failed_check() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(10);
+
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: Keyring::Alice.into(),
+            value: 1_000,
+        }
+        .into();
+        let signature = eth_sign(&ECDSA_SEED, &scale_payload(0, None, &call));
+        let dry_run = |account: &AccountId, signature: Vec<u8>, nonce, valid_until| {
+            CustomSignatures::dry_run(
+                custom_signatures::Call::<Runtime>::call {
+                    call: Box::new(call.clone()),
+                    signer: account.clone(),
+                    signature,
+                    nonce,
+                    valid_until,
+                }
+                .encode(),
+            )
+        };
+
+        assert_eq!(
+            CustomSignatures::dry_run(vec![0xff]),
+            Err(PayloadError::CallDecodeFailure)
+        );
+        assert_eq!(
+            dry_run(&account, vec![1, 2, 3], 0, None),
+            Err(PayloadError::SignatureDecodeFailure)
+        );
+        assert_eq!(
+            dry_run(&account, signature.clone(), 1, None),
+            Err(PayloadError::BadNonce)
+        );
+        assert_eq!(
+            dry_run(&account, signature.clone(), 0, Some(9)),
+            Err(PayloadError::Expired)
+        );
+
+        let outdated = eth_sign(&ECDSA_SEED, &(0xff50u16, 0u32, &call).encode());
+        assert_eq!(
+            dry_run(&account, outdated, 0, None),
+            Err(PayloadError::OutdatedPayload)
+        );
+        let other = eth_sign(&[1u8; 32], &scale_payload(0, None, &call));
+        assert_eq!(
+            dry_run(&account, other.clone(), 0, None),
+            Err(PayloadError::InvalidSignature)
+        );
+
+        // Signer without any funds
+        let unfunded =
+            MultiSigner::from(ecdsa::Pair::from_seed(&[1u8; 32]).public()).into_account();
+        assert_eq!(
+            dry_run(&unfunded, other, 0, None),
+            Err(PayloadError::CannotPayFee)
+        );
+
+        assert_eq!(dry_run(&account, signature, 0, None), Ok(()));
+    })
+}
+
+#[test]
+fn call_fixtures() {
+    use sp_core::crypto::Ss58Codec;
+
+    let seed = hex!["ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"];
+    let pair = ecdsa::Pair::from_seed(&seed);
+    assert_eq!(
+        MultiSigner::from(pair.public())
+            .into_account()
+            .to_ss58check(),
+        "5EGynCAEvv8NLeHx8vDMvb8hTcEcMYUMWCDQEEncNEfNWB2W",
+    );
+
+    let dest =
+        AccountId::from_ss58check("5GVwcV6EzxxYbXBm7H6dtxc9TCgL4oepMXtgqWYEc3VXJoaf").unwrap();
+    let call: Call = pallet_balances::Call::<Runtime>::transfer { dest, value: 1000 }.into();
+    assert_eq!(
+        call.encode(),
+        hex!["0000c4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f"],
+    );
+
+    let payload = scale_payload(0, None, &call);
+    assert_eq!(
+        payload,
+        hex!["50ff0145454545454545454545454545454545454545454545454545454545454545450000000000000000000000c4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f"],
+    );
+    new_test_ext().execute_with(|| {
+        assert_eq!(
+            <ScaleMessage as SignedMessage<Runtime>>::signed_message(
+                MessageFormat::Scale,
+                &call,
+                &0,
+                &None,
+                None
+            ),
+            Some(payload)
+        );
+    });
+
+    let signature = hex!["90de9927a302075b199b7696c5e59ad23bc9604b041c21634aa6967c8b929fe51652c161723d1387289ff5b9ee66a83f949af997b0197d6b6152b4e9b5e966731c"];
+    assert_eq!(eth_sign(&seed, payload.as_ref()), signature)
+}
+
+#[test]
+fn eip712_call_fixtures() {
+    use sp_core::crypto::Ss58Codec;
+
+    let seed = hex!["ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"];
+    let pair = ecdsa::Pair::from_seed(&seed);
+    let account = MultiSigner::from(pair.public()).into_account();
+
+    let dest =
+        AccountId::from_ss58check("5GVwcV6EzxxYbXBm7H6dtxc9TCgL4oepMXtgqWYEc3VXJoaf").unwrap();
+    let call: Call = pallet_balances::Call::<Runtime>::transfer { dest, value: 1000 }.into();
+
+    new_test_ext().execute_with(|| {
+        let message =
+            <eip712::Eip712Message<ChainName, ChainId> as SignedMessage<Runtime>>::signed_message(
+                MessageFormat::Eip712,
+                &call,
+                &0,
+                &None,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            message,
+            hex!["1901c70d91687765340765f5c4c80063e28f6ddb27cf3108e95af64ad926e2fc69dde6a97a9668fd24ee0204674eb601f77b572689e12bb9bf51d7cbc04c6044cf43"],
+        );
+
+        let signature = eip712::Eip712Signature(hex!["fef00d7948e766762da5a8fd6a818fee49b76a99cd04d330ea146cd94c5624f94fd8282cc051d7bc1e2cc1f015ffd76d97be90789454cae8a83882522519b3f81b"]);
+        assert!(signature.verify(&message[..], &account));
+
+        // Typed data signature isn't valid for the `eth_sign` message.
+        let message = <ScaleMessage as SignedMessage<Runtime>>::signed_message(
+            MessageFormat::Scale,
+            &call,
+            &0,
+            &None,
+            None,
+        )
+        .unwrap();
+        assert!(!signature.verify(&message[..], &account));
+    })
+}
+
+#[test]
+fn eip712_call_works() {
+    use frame_support::unsigned::ValidateUnsigned;
+    use sp_runtime::transaction_validity::TransactionSource;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: AccountId = Keyring::Alice.into();
+
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into();
+        let message = <Runtime as Config>::SignedMessage::signed_message(
+            MessageFormat::Eip712,
+            &call,
+            &0,
+            &None,
+            None,
+        )
+        .unwrap();
+        let signature = eip712_sign(&ECDSA_SEED, &message);
+
+        // Typed data signature doesn't sign the SCALE payload
+        let scale_signature = eth_sign(&ECDSA_SEED, &scale_payload(0, None, &call));
+        assert_ne!(signature, scale_signature);
+
+        // Format is selected by the signature, both are accepted by the same runtime
+        let unsigned_call = custom_signatures::Call::call {
+            call: Box::new(call.clone()),
+            signer: account.clone(),
+            signature: signature.clone(),
+            nonce: 0,
+            valid_until: None,
+        };
+        assert_ok!(CustomSignatures::validate_unsigned(
+            TransactionSource::External,
+            &unsigned_call
+        ));
+        assert_ok!(CustomSignatures::call(
+            Origin::none(),
+            Box::new(call.clone()),
+            account.clone(),
+            scale_signature,
+            0,
+            None,
+        ));
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature,
+                0,
+                None,
+            ),
+            Error::<Runtime>::BadNonce,
+        );
+
+        let message = <Runtime as Config>::SignedMessage::signed_message(
+            MessageFormat::Eip712,
+            &call,
+            &1,
+            &None,
+            None,
+        )
+        .unwrap();
+        let signature = eip712_sign(&ECDSA_SEED, &message);
+        assert_ok!(CustomSignatures::call(
+            Origin::none(),
+            Box::new(call),
+            account.clone(),
+            signature,
+            1,
+            None,
+        ));
+        assert_eq!(System::account(alice).data.free, 2_000);
+        assert_eq!(System::account(account).nonce, 2);
+    })
+}
+
+#[test]
+fn text_call_works() {
+    new_test_ext().execute_with(|| {
+        let pair = ecdsa::Pair::from_seed(&ECDSA_SEED);
+        let account = MultiSigner::from(pair.public()).into_account();
+        let alice: AccountId = Keyring::Alice.into();
+
+        let call: Call = pallet_balances::Call::<Runtime>::transfer {
+            dest: alice.clone(),
+            value: 1_000,
+        }
+        .into();
+        let message = <Runtime as Config>::SignedMessage::signed_message(
+            MessageFormat::Text,
+            &call,
+            &0,
+            &None,
+            None,
+        )
+        .unwrap();
+
+        // Arguments of the transfer are rendered by name
+        let text = String::from_utf8(message.clone()).unwrap();
+        assert!(text.contains("Call: Balances.transfer\nArguments: dest 0x"));
+        assert!(text.contains(", value 1000\n"));
+
+        // Text signature isn't valid for another format
+        let signature = text_sign(&ECDSA_SEED, &scale_payload(0, None, &call));
+        assert_err!(
+            CustomSignatures::call(
+                Origin::none(),
+                Box::new(call.clone()),
+                account.clone(),
+                signature,
+                0,
+                None,
+            ),
+            Error::<Runtime>::InvalidSignature,
+        );
+
+        assert_ok!(CustomSignatures::call(
+            Origin::none(),
+            Box::new(call),
+            account.clone(),
+            text_sign(&ECDSA_SEED, &message),
+            0,
+            None,
+        ));
+        assert_eq!(System::account(alice).data.free, 1_000);
+        assert_eq!(System::account(account).nonce, 1);
+    })
+}
+
+#[test]
+fn unsupported_format_is_not_built() {
+    type SignedMessage = (ScaleMessage, eip712::Eip712Message<ChainName, ChainId>);
+
+    let call: Call = frame_system::Call::<Runtime>::remark {
+        remark: b"Hello".to_vec(),
+    }
+    .into();
+
+    new_test_ext().execute_with(|| {
+        // Human readable text isn't signed without the text message
+        assert_eq!(
+            <SignedMessage as custom_signatures::SignedMessage<Runtime>>::signed_message(
+                MessageFormat::Text,
+                &call,
+                &0,
+                &None,
+                None
+            ),
+            None
+        );
+        assert_eq!(
+            <SignedMessage as custom_signatures::SignedMessage<Runtime>>::signed_batch_message(
+                MessageFormat::Text,
+                &[call.clone()],
+                true,
+                &0,
+                &None
+            ),
+            None
+        );
+        assert!(
+            <SignedMessage as custom_signatures::SignedMessage<Runtime>>::signed_batch_message(
+                MessageFormat::Eip712,
+                &[call],
+                true,
+                &0,
+                &None
+            )
+            .is_some()
+        );
+    })
+}
+
+/// Renders the balance transfer arguments by name, other calls as hex.
+pub struct TransferArgs;
+
+impl text::RenderArgs<Call> for TransferArgs {
+    fn render_args(call: &Call) -> Vec<u8> {
+        match call {
+            Call::Balances(pallet_balances::Call::transfer { dest, value }) => {
+                text::render_fields(&[
+                    (&b"dest"[..], text::hex_string(dest.as_ref())),
+                    (&b"value"[..], eip712::decimal_string(*value)),
+                ])
+            }
+            _ => <text::HexArgs as text::RenderArgs<Call>>::render_args(call),
+        }
+    }
+}
+
+#[test]
+fn text_call_fixtures() {
+    use sp_core::crypto::Ss58Codec;
+
+    let seed = hex!["ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"];
+    let pair = ecdsa::Pair::from_seed(&seed);
+    let account = MultiSigner::from(pair.public()).into_account();
+
+    let dest =
+        AccountId::from_ss58check("5GVwcV6EzxxYbXBm7H6dtxc9TCgL4oepMXtgqWYEc3VXJoaf").unwrap();
+    let call: Call = pallet_balances::Call::<Runtime>::transfer { dest, value: 1000 }.into();
+
+    new_test_ext().execute_with(|| {
+        let message = <text::TextMessage<ChainName> as SignedMessage<Runtime>>::signed_message(
+            MessageFormat::Text,
+            &call,
+            &0,
+            &None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(message.clone()).unwrap(),
+            "Authorize call on Astar Test\n\
+             Call: Balances.transfer\n\
+             Arguments: 0xc4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563da10f\n\
+             Nonce: 0\n\
+             Valid until: no expiry\n\
+             Genesis hash: 0x4545454545454545454545454545454545454545454545454545454545454545\n\
+             Transaction version: 0",
+        );
+
+        let signature = text::TextSignature(hex!["37986649c1ed230ae33ec0fbbe33f6df847fdf8245ad5ffff6e4ae5df07984417ef887c73bca29657ae7616f60212a761c44530f3e1de226f6cba0583f693d6a1b"]);
+        assert!(signature.verify(&message[..], &account));
+
+        // Text of another nonce isn't signed
+        let message = <text::TextMessage<ChainName> as SignedMessage<Runtime>>::signed_message(
+            MessageFormat::Text,
+            &call,
+            &1,
+            &None,
+            None,
+        )
+        .unwrap();
+        assert!(!signature.verify(&message[..], &account));
+
+        let sponsorship = Sponsorship {
+            sponsor: None,
+            max_call_fee: Some(500),
+        };
+        let message =
+            <text::TextMessage<ChainName, TransferArgs> as SignedMessage<Runtime>>::signed_message(
+                MessageFormat::Text,
+                &call,
+                &3,
+                &Some(20),
+                Some(&sponsorship),
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            "Authorize call on Astar Test\n\
+             Call: Balances.transfer\n\
+             Arguments: dest 0xc4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563d, value 1000\n\
+             Nonce: 3\n\
+             Valid until: block 20\n\
+             Sponsor: any\n\
+             Max call fee: 500\n\
+             Genesis hash: 0x4545454545454545454545454545454545454545454545454545454545454545\n\
+             Transaction version: 0",
+        );
+
+        let remark: Call = frame_system::Call::<Runtime>::remark {
+            remark: b"Hello".to_vec(),
+        }
+        .into();
+        let message =
+            <text::TextMessage<ChainName, TransferArgs> as SignedMessage<Runtime>>::signed_batch_message(
+                MessageFormat::Text,
+                &[call.clone(), remark],
+                true,
+                &0,
+                &None,
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(message).unwrap(),
+            "Authorize batch on Astar Test\n\
+             Atomic: yes\n\
+             Call 1: Balances.transfer\n\
+             Arguments 1: dest 0xc4305fb88b6ccb43d6552dc11d18e7b0ee3185247adcc6e885eb284adf6c563d, value 1000\n\
+             Call 2: System.remark\n\
+             Arguments 2: 0x1448656c6c6f\n\
+             Nonce: 0\n\
+             Valid until: no expiry\n\
+             Genesis hash: 0x4545454545454545454545454545454545454545454545454545454545454545\n\
+             Transaction version: 0",
+        );
+    })
 }
@@ -0,0 +1,310 @@
+//! Human readable text messages (personal_sign API call).
+//!
+//! Signed call is rendered as lines of text, so the wallet shows what is authorized:
+//! ```nocompile
+//! Authorize call on Astar
+//! Call: Balances.transfer
+//! Arguments: 0xc4305f...a10f
+//! Nonce: 0
+//! Valid until: no expiry
+//! Genesis hash: 0x9eb76c5184c4ab8679d2d5d819fdf90b9c001403e9e17da2e14b6d8aec4029c6
+//! Transaction version: 2
+//! ```
+//! Calls paid by a sponsor are followed by `Sponsor` and `Max call fee` lines. Batches start with
+//! `Authorize batch on <chain>` and an `Atomic` line, followed by a `Call` and `Arguments`
+//! line numbered from one for each call of the batch.
+//!
+//! Arguments are rendered by the `RenderArgs` implementation of the runtime, so the rendering
+//! must be deterministic and independent of the `std` feature.
+
+use crate::{bitcoin::hex_encode, eip712::decimal_string, Config, Pallet, SignedMessage};
+use crate::{BalanceOf, MessageFormat, SignatureFormat, SponsorshipOf};
+use codec::{Decode, Encode};
+use frame_support::traits::{Get, GetCallMetadata};
+use sp_core::ecdsa;
+use sp_io::{crypto::secp256k1_ecdsa_recover_compressed, hashing::keccak_256};
+use sp_runtime::traits::{IdentifyAccount, Lazy, SaturatedConversion, Verify};
+use sp_runtime::MultiSignature;
+use sp_std::{marker::PhantomData, prelude::*};
+
+/// Renders the arguments of a call for the human readable message.
+pub trait RenderArgs<Call> {
+    /// Returns the text rendering of the `call` arguments.
+    fn render_args(call: &Call) -> Vec<u8>;
+}
+
+/// Renders the arguments as hex of their SCALE encoding.
+///
+/// Runtime calls are encoded as the pallet index and the call index followed by the arguments.
+pub struct HexArgs;
+
+impl<Call: Encode> RenderArgs<Call> for HexArgs {
+    fn render_args(call: &Call) -> Vec<u8> {
+        let encoded = call.encode();
+        hex_string(encoded.get(2..).unwrap_or_default())
+    }
+}
+
+/// Ethereum-compatible signature of a human readable message.
+#[derive(Encode, Decode, PartialEq, Eq, Clone, scale_info::TypeInfo)]
+pub struct TextSignature(pub [u8; 65]);
+
+impl sp_std::fmt::Debug for TextSignature {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+        write!(f, "TextSignature({:?})", &self.0[..])
+    }
+}
+
+impl From<ecdsa::Signature> for TextSignature {
+    fn from(signature: ecdsa::Signature) -> Self {
+        Self(signature.into())
+    }
+}
+
+impl sp_std::convert::TryFrom<Vec<u8>> for TextSignature {
+    type Error = ();
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.len() == 65 {
+            let mut inner = [0u8; 65];
+            inner.copy_from_slice(&data[..]);
+            Ok(TextSignature(inner))
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl SignatureFormat for TextSignature {
+    fn message_format(&self) -> MessageFormat {
+        MessageFormat::Text
+    }
+
+    fn signable_message(&self, message: &[u8]) -> Vec<u8> {
+        signable_message(message)
+    }
+
+    fn message_digest(&self, message: &[u8]) -> [u8; 32] {
+        keccak_256(&signable_message(message))
+    }
+}
+
+/// Constructs the message that Ethereum RPC's `personal_sign` would sign for the text `what`.
+///
+/// Note: unlike `ethereum::signable_message`, the text itself is signed.
+pub fn signable_message(what: &[u8]) -> Vec<u8> {
+    let mut v = b"\x19Ethereum Signed Message:\n".to_vec();
+    v.extend_from_slice(&decimal_string(what.len() as u128));
+    v.extend_from_slice(what);
+    v
+}
+
+/// Attempts to recover the Ethereum public key from a human readable message signature
+/// signed by using the Ethereum RPC's `personal_sign`.
+impl Verify for TextSignature {
+    type Signer = <MultiSignature as Verify>::Signer;
+
+    fn verify<L: Lazy<[u8]>>(
+        &self,
+        mut msg: L,
+        account: &<Self::Signer as IdentifyAccount>::AccountId,
+    ) -> bool {
+        let msg = self.message_digest(msg.get());
+        match secp256k1_ecdsa_recover_compressed(&self.0, &msg).ok() {
+            Some(public) => {
+                let signer = Self::Signer::from(ecdsa::Public::from_raw(public));
+                *account == signer.into_account()
+            }
+            None => false,
+        }
+    }
+}
+
+/// Renders the data as `0x` prefixed lower case hex.
+pub fn hex_string(data: &[u8]) -> Vec<u8> {
+    let mut v = b"0x".to_vec();
+    v.extend_from_slice(&hex_encode(data));
+    v
+}
+
+/// Renders the named arguments as comma separated `name value` pairs.
+pub fn render_fields(fields: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+    let mut text = Vec::new();
+    for (i, (name, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            text.extend_from_slice(b", ");
+        }
+        text.extend_from_slice(name);
+        text.push(b' ');
+        text.extend_from_slice(value);
+    }
+    text
+}
+
+/// Appends the `name: value` line to the text.
+fn push_line(text: &mut Vec<u8>, name: &[u8], value: &[u8]) {
+    text.push(b'\n');
+    text.extend_from_slice(name);
+    text.extend_from_slice(b": ");
+    text.extend_from_slice(value);
+}
+
+/// Human readable text message of a call, should be used together with `TextSignature`.
+///
+/// `ChainName` is shown in the heading, `Args` renders the arguments of the calls.
+pub struct TextMessage<ChainName, Args = HexArgs>(PhantomData<(ChainName, Args)>);
+
+impl<ChainName: Get<&'static str>, Args> TextMessage<ChainName, Args> {
+    /// Returns the heading line of the message.
+    fn heading(what: &[u8]) -> Vec<u8> {
+        let mut text = b"Authorize ".to_vec();
+        text.extend_from_slice(what);
+        text.extend_from_slice(b" on ");
+        text.extend_from_slice(ChainName::get().as_bytes());
+        text
+    }
+
+    /// Appends the `Call` and `Arguments` lines of `call`, numbered if `index` is given.
+    fn push_call<T>(text: &mut Vec<u8>, call: &<T as Config>::Call, index: Option<usize>)
+    where
+        T: Config,
+        <T as Config>::Call: GetCallMetadata,
+        Args: RenderArgs<<T as Config>::Call>,
+    {
+        let suffix = index
+            .map(|i| {
+                let mut suffix = b" ".to_vec();
+                suffix.extend_from_slice(&decimal_string(i as u128 + 1));
+                suffix
+            })
+            .unwrap_or_default();
+        let metadata = call.get_call_metadata();
+        let mut function = metadata.pallet_name.as_bytes().to_vec();
+        function.push(b'.');
+        function.extend_from_slice(metadata.function_name.as_bytes());
+
+        push_line(text, &[&b"Call"[..], &suffix].concat(), &function);
+        push_line(
+            text,
+            &[&b"Arguments"[..], &suffix].concat(),
+            &Args::render_args(call),
+        );
+    }
+
+    /// Appends the lines shared by calls and batches.
+    fn push_terms<T: Config>(
+        text: &mut Vec<u8>,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+    ) {
+        push_line(text, b"Nonce", &decimal_string((*nonce).saturated_into()));
+        let valid_until = valid_until
+            .map(|n| {
+                let mut block = b"block ".to_vec();
+                block.extend_from_slice(&decimal_string(n.saturated_into()));
+                block
+            })
+            .unwrap_or_else(|| b"no expiry".to_vec());
+        push_line(text, b"Valid until", &valid_until);
+    }
+
+    /// Appends the lines binding the message to a single chain and call encoding.
+    fn push_chain<T: Config>(text: &mut Vec<u8>) {
+        push_line(
+            text,
+            b"Genesis hash",
+            &hex_string(&Pallet::<T>::genesis_hash().encode()),
+        );
+        push_line(
+            text,
+            b"Transaction version",
+            &decimal_string(
+                <T as frame_system::Config>::Version::get()
+                    .transaction_version
+                    .into(),
+            ),
+        );
+    }
+}
+
+impl<T, ChainName, Args> SignedMessage<T> for TextMessage<ChainName, Args>
+where
+    T: Config,
+    <T as Config>::Call: GetCallMetadata,
+    ChainName: Get<&'static str>,
+    Args: RenderArgs<<T as Config>::Call>,
+{
+    fn signed_message(
+        format: MessageFormat,
+        call: &<T as Config>::Call,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+        sponsorship: Option<&SponsorshipOf<T>>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Text {
+            return None;
+        }
+
+        let mut text = Self::heading(b"call");
+        Self::push_call::<T>(&mut text, call, None);
+        Self::push_terms::<T>(&mut text, nonce, valid_until);
+        if let Some(sponsorship) = sponsorship {
+            let sponsor = sponsorship
+                .sponsor
+                .as_ref()
+                .map(|sponsor| hex_string(&sponsor.encode()))
+                .unwrap_or_else(|| b"any".to_vec());
+            push_line(&mut text, b"Sponsor", &sponsor);
+            let max_call_fee = sponsorship
+                .max_call_fee
+                .map(|fee: BalanceOf<T>| decimal_string(fee.saturated_into()))
+                .unwrap_or_else(|| b"unlimited".to_vec());
+            push_line(&mut text, b"Max call fee", &max_call_fee);
+        }
+        Self::push_chain::<T>(&mut text);
+        Some(text)
+    }
+
+    fn signed_batch_message(
+        format: MessageFormat,
+        calls: &[<T as Config>::Call],
+        atomic: bool,
+        nonce: &T::Index,
+        valid_until: &Option<T::BlockNumber>,
+    ) -> Option<Vec<u8>> {
+        if format != MessageFormat::Text {
+            return None;
+        }
+
+        let mut text = Self::heading(b"batch");
+        push_line(&mut text, b"Atomic", if atomic { b"yes" } else { b"no" });
+        for (index, call) in calls.iter().enumerate() {
+            Self::push_call::<T>(&mut text, call, Some(index));
+        }
+        Self::push_terms::<T>(&mut text, nonce, valid_until);
+        Self::push_chain::<T>(&mut text);
+        Some(text)
+    }
+}
+
+#[test]
+fn verify_should_works() {
+    use hex_literal::hex;
+    use sp_core::{ecdsa, Pair};
+
+    let msg = "test eth signed text";
+    let pair = ecdsa::Pair::from_seed(&hex![
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+    ]);
+    let account = <MultiSignature as Verify>::Signer::from(pair.public()).into_account();
+    assert_eq!(
+        signable_message(msg.as_ref()),
+        b"\x19Ethereum Signed Message:\n20test eth signed text".to_vec()
+    );
+    let signature = TextSignature(hex!["bde18f90076fd6a71144e371d1b49226bfd6a3019a0ee0bf1d5b2e66ef8159d53458c50bc3130e0f8988429fbc43b63d622b72c0c724a6f042c12630a898819e1c"]);
+    assert_eq!(signature.verify(msg.as_ref(), &account), true);
+
+    // Hashed `eth_sign` message isn't signed by the text signature
+    let signature = crate::ethereum::EthereumSignature(signature.0);
+    assert_eq!(signature.verify(msg.as_ref(), &account), false);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use crate::Pallet as DappsSignalling;
+
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::{
+    traits::{EnsureOrigin, Get},
+    BoundedVec,
+};
+use frame_system::{Pallet as System, RawOrigin};
+use sp_runtime::traits::One;
+use sp_std::convert::TryInto;
+
+/// Used to create a distinct smart contract id from the given seed.
+///
+/// Assumes that the first variant of the smart contract type wraps a 20 byte address (e.g. `H160`).
+fn contract_from_seed<T: Config>(seed: u32) -> T::SmartContract {
+    let mut encoded = [0u8; 21];
+    encoded[1..5].copy_from_slice(&seed.to_le_bytes());
+    T::SmartContract::decode(&mut &encoded[..]).expect("Contract can be decoded from 21 bytes.")
+}
+
+/// Used to create poll options with the specified number of distinct contracts.
+fn poll_options<T: Config>(
+    number_of_options: u32,
+) -> Result<BoundedVec<T::SmartContract, T::MaxPollOptions>, &'static str> {
+    let options: Vec<_> = (0..number_of_options)
+        .map(|id| contract_from_seed::<T>(id + 1))
+        .collect();
+    options.try_into().map_err(|_| "Too many poll options.")
+}
+
+/// Opens a poll with the specified number of options and returns its index.
+///
+/// Poll can be closed right away.
+fn open_ended_poll<T: Config>(number_of_options: u32) -> Result<PollIndex, &'static str> {
+    let poll_index = DappsSignalling::<T>::poll_count();
+    DappsSignalling::<T>::open_poll(
+        T::PollOrigin::successful_origin(),
+        T::Hash::default(),
+        poll_options::<T>(number_of_options)?,
+        One::one(),
+    )?;
+
+    // Poll must have ended and there must be a finished era which can be counted.
+    let mut poll = Polls::<T>::get(poll_index).ok_or("Poll was opened.")?;
+    poll.start_era = 0;
+    System::<T>::set_block_number(poll.end);
+    Polls::<T>::insert(poll_index, poll);
+
+    Ok(poll_index)
+}
+
+benchmarks! {
+
+    open_poll {
+        let n in 1 .. T::MaxPollOptions::get();
+        let options = poll_options::<T>(n)?;
+        let poll_index = DappsSignalling::<T>::poll_count();
+        let origin = T::PollOrigin::successful_origin();
+    }: {
+        DappsSignalling::<T>::open_poll(origin, T::Hash::default(), options, One::one())?;
+    }
+    verify {
+        assert!(Polls::<T>::contains_key(poll_index));
+    }
+
+    close_poll {
+        let n in 1 .. T::MaxPollOptions::get();
+        let poll_index = open_ended_poll::<T>(n)?;
+        let caller: T::AccountId = whitelisted_caller();
+    }: _(RawOrigin::Signed(caller), poll_index)
+    verify {
+        assert!(PollResults::<T>::contains_key(poll_index));
+    }
+
+    cancel_poll {
+        let poll_index = open_ended_poll::<T>(T::MaxPollOptions::get())?;
+        let origin = T::PollOrigin::successful_origin();
+    }: {
+        DappsSignalling::<T>::cancel_poll(origin, poll_index)?;
+    }
+    verify {
+        assert!(!Polls::<T>::contains_key(poll_index));
+    }
+}
+
+impl_benchmark_test_suite!(
+    DappsSignalling,
+    crate::tests::new_test_ext(),
+    crate::tests::Runtime,
+);
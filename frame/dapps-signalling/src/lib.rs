@@ -0,0 +1,283 @@
+//! # dApps Signalling Module
+//!
+//! Non-binding polls over dApps, weighted by dapps staking stake.
+//!
+//! ## Overview
+//!
+//! Governance opens a poll over a list of smart contracts registered for dapps staking.
+//! Stakers signal their preference simply by staking on the contracts, no additional funds are locked.
+//! Once the poll ends, anyone can close it. Stake on each of the options is counted for the last era
+//! which has already finished, so it can't be changed by staking right before the poll is closed.
+//! Results are stored on-chain.
+//!
+//! ## Interface
+//!
+//! * `open_poll` - opens a new poll, must be called by `PollOrigin`.
+//! * `close_poll` - counts the stake of the last finished era on each option of a poll which has ended
+//!   and stores the results.
+//! * `cancel_poll` - removes a poll which hasn't been closed yet, must be called by `PollOrigin`.
+//!
+//! ## Usage
+//!
+//! `StakingInfo` should be set to the dapps staking pallet, which implements `DappsStakingInfo`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use pallet_dapps_staking::EraIndex;
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+pub use pallet::*;
+pub use weights::WeightInfo;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+/// Index of a poll.
+pub type PollIndex = u32;
+
+/// Poll over a list of smart contracts.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct Poll<Hash, Options, BlockNumber> {
+    /// Hash of the poll description, which is kept off-chain.
+    pub description: Hash,
+    /// Contracts the stake is counted for.
+    pub options: Options,
+    /// Block number from which the poll can be closed.
+    pub end: BlockNumber,
+    /// Dapps staking era which was in progress when the poll was opened.
+    pub start_era: EraIndex,
+}
+
+/// Results of a closed poll.
+#[derive(Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct PollResult<SmartContract, Balance> {
+    /// Dapps staking era the stake was counted for.
+    pub era: EraIndex,
+    /// Amount staked on each of the poll options during the era.
+    pub tally: Vec<(SmartContract, Balance)>,
+    /// Total amount staked on all contracts during the era.
+    pub total_staked: Balance,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::{pallet_prelude::*, BoundedVec};
+    use frame_system::{ensure_signed, pallet_prelude::*};
+    use pallet_dapps_staking::DappsStakingInfo;
+    use sp_runtime::traits::{AtLeast32BitUnsigned, Saturating, Zero};
+
+    #[pallet::pallet]
+    #[pallet::generate_store(pub(crate) trait Store)]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    /// Poll as stored by the pallet.
+    pub type PollOf<T> = Poll<
+        <T as frame_system::Config>::Hash,
+        BoundedVec<<T as Config>::SmartContract, <T as Config>::MaxPollOptions>,
+        <T as frame_system::Config>::BlockNumber,
+    >;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// The overarching event type.
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        /// Smart contract type used by dapps staking.
+        type SmartContract: Parameter + Member;
+
+        /// Balance type used by dapps staking.
+        type Balance: Parameter + Member + AtLeast32BitUnsigned + Default + Copy;
+
+        /// Source of the staked amounts.
+        type StakingInfo: DappsStakingInfo<Self::AccountId, Self::SmartContract, Self::Balance>;
+
+        /// Origin which can open and cancel polls.
+        type PollOrigin: EnsureOrigin<Self::Origin>;
+
+        /// Maximum number of options in a single poll.
+        #[pallet::constant]
+        type MaxPollOptions: Get<u32>;
+
+        /// Weight information for extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
+    }
+
+    /// Number of polls opened so far, also the index of the next poll.
+    #[pallet::storage]
+    #[pallet::getter(fn poll_count)]
+    pub type PollCount<T> = StorageValue<_, PollIndex, ValueQuery>;
+
+    /// Polls which were opened and not cancelled.
+    #[pallet::storage]
+    #[pallet::getter(fn polls)]
+    pub type Polls<T: Config> = StorageMap<_, Twox64Concat, PollIndex, PollOf<T>>;
+
+    /// Results of the closed polls.
+    #[pallet::storage]
+    #[pallet::getter(fn poll_results)]
+    pub type PollResults<T: Config> =
+        StorageMap<_, Twox64Concat, PollIndex, PollResult<T::SmartContract, T::Balance>>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(crate) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// Poll was opened. \[poll_index, end\]
+        PollOpened(PollIndex, T::BlockNumber),
+        /// Poll was closed and its results were stored. \[poll_index, era, tally, total_staked\]
+        PollClosed(
+            PollIndex,
+            EraIndex,
+            Vec<(T::SmartContract, T::Balance)>,
+            T::Balance,
+        ),
+        /// Poll was cancelled. \[poll_index\]
+        PollCancelled(PollIndex),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Poll must have at least one option.
+        NoPollOptions,
+        /// Same contract is listed more than once as a poll option.
+        DuplicatePollOption,
+        /// Poll duration must not be zero.
+        ZeroPollDuration,
+        /// Poll doesn't exist.
+        UnknownPoll,
+        /// Poll hasn't ended yet.
+        PollNotEnded,
+        /// Poll is already closed.
+        PollAlreadyClosed,
+        /// Era in which the poll was opened hasn't finished yet.
+        PollEraNotFinished,
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Opens a poll over the given contracts, which can be closed after `duration` blocks.
+        ///
+        /// The dispatch origin for this call must be `PollOrigin`.
+        #[pallet::weight(T::WeightInfo::open_poll(options.len() as u32))]
+        pub fn open_poll(
+            origin: OriginFor<T>,
+            description: T::Hash,
+            options: BoundedVec<T::SmartContract, T::MaxPollOptions>,
+            duration: T::BlockNumber,
+        ) -> DispatchResultWithPostInfo {
+            T::PollOrigin::ensure_origin(origin)?;
+
+            ensure!(!options.is_empty(), Error::<T>::NoPollOptions);
+            for (index, option) in options.iter().enumerate() {
+                ensure!(
+                    !options[..index].contains(option),
+                    Error::<T>::DuplicatePollOption
+                );
+            }
+            ensure!(!duration.is_zero(), Error::<T>::ZeroPollDuration);
+
+            let poll_index = Self::poll_count();
+            PollCount::<T>::put(poll_index.saturating_add(1));
+
+            let end = frame_system::Pallet::<T>::block_number().saturating_add(duration);
+            Polls::<T>::insert(
+                poll_index,
+                Poll {
+                    description,
+                    options,
+                    end,
+                    start_era: T::StakingInfo::current_era(),
+                },
+            );
+
+            Self::deposit_event(Event::<T>::PollOpened(poll_index, end));
+
+            Ok(().into())
+        }
+
+        /// Closes the poll by counting the stake on each of its options.
+        ///
+        /// Stake is counted for the last era which has finished. It must not precede the era
+        /// in which the poll was opened.
+        ///
+        /// Any signed account can close a poll once it has ended.
+        #[pallet::weight(T::WeightInfo::close_poll(T::MaxPollOptions::get()))]
+        pub fn close_poll(
+            origin: OriginFor<T>,
+            #[pallet::compact] poll_index: PollIndex,
+        ) -> DispatchResultWithPostInfo {
+            ensure_signed(origin)?;
+
+            let poll = Self::polls(poll_index).ok_or(Error::<T>::UnknownPoll)?;
+            ensure!(
+                !PollResults::<T>::contains_key(poll_index),
+                Error::<T>::PollAlreadyClosed
+            );
+            ensure!(
+                frame_system::Pallet::<T>::block_number() >= poll.end,
+                Error::<T>::PollNotEnded
+            );
+
+            // Stake of the era in progress can still change so the last finished era is counted.
+            let era = T::StakingInfo::current_era().saturating_sub(1);
+            ensure!(era >= poll.start_era, Error::<T>::PollEraNotFinished);
+
+            let number_of_options = poll.options.len() as u32;
+            let tally: Vec<_> = poll
+                .options
+                .into_inner()
+                .into_iter()
+                .map(|contract| {
+                    let staked = T::StakingInfo::contract_era_stake(&contract, era);
+                    (contract, staked)
+                })
+                .collect();
+            let total_staked = T::StakingInfo::era_total_stake(era);
+
+            PollResults::<T>::insert(
+                poll_index,
+                PollResult {
+                    era,
+                    tally: tally.clone(),
+                    total_staked,
+                },
+            );
+
+            Self::deposit_event(Event::<T>::PollClosed(poll_index, era, tally, total_staked));
+
+            Ok(Some(T::WeightInfo::close_poll(number_of_options)).into())
+        }
+
+        /// Removes a poll which hasn't been closed yet.
+        ///
+        /// The dispatch origin for this call must be `PollOrigin`.
+        #[pallet::weight(T::WeightInfo::cancel_poll())]
+        pub fn cancel_poll(
+            origin: OriginFor<T>,
+            #[pallet::compact] poll_index: PollIndex,
+        ) -> DispatchResultWithPostInfo {
+            T::PollOrigin::ensure_origin(origin)?;
+
+            ensure!(
+                Polls::<T>::contains_key(poll_index),
+                Error::<T>::UnknownPoll
+            );
+            ensure!(
+                !PollResults::<T>::contains_key(poll_index),
+                Error::<T>::PollAlreadyClosed
+            );
+
+            Polls::<T>::remove(poll_index);
+
+            Self::deposit_event(Event::<T>::PollCancelled(poll_index));
+
+            Ok(().into())
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use crate as dapps_signalling;
+use dapps_signalling::*;
+use frame_support::{assert_noop, assert_ok, parameter_types, BoundedVec};
+use pallet_dapps_staking::{DappsStakingInfo, EraIndex};
+use sp_core::{H160, H256};
+use sp_runtime::{
+    testing::Header,
+    traits::{BadOrigin, BlakeTwo256, IdentityLookup},
+};
+use std::{cell::RefCell, collections::BTreeMap, convert::TryInto};
+
+type AccountId = u64;
+type Balance = u128;
+type BlockNumber = u64;
+type Block = frame_system::mocking::MockBlock<Runtime>;
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Runtime>;
+
+frame_support::construct_runtime!(
+    pub enum Runtime where
+       Block = Block,
+       NodeBlock = Block,
+       UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        DappsSignalling: dapps_signalling::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+}
+
+impl frame_system::Config for Runtime {
+    type Origin = Origin;
+    type BaseCallFilter = frame_support::traits::Everything;
+    type Index = u32;
+    type BlockNumber = BlockNumber;
+    type Call = Call;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = ();
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type DbWeight = ();
+    type SystemWeightInfo = ();
+    type BlockWeights = ();
+    type BlockLength = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+}
+
+thread_local! {
+    static STAKES: RefCell<BTreeMap<(AccountId, H160), Balance>> = RefCell::new(BTreeMap::new());
+    static CURRENT_ERA: RefCell<EraIndex> = RefCell::new(1);
+    static ERA_STAKES: RefCell<BTreeMap<EraIndex, BTreeMap<H160, Balance>>> = RefCell::new(BTreeMap::new());
+}
+
+/// Sets the amount `staker` has staked on `contract`.
+///
+/// Stake becomes effective in the next era.
+fn set_stake(staker: AccountId, contract: H160, staked: Balance) {
+    STAKES.with(|stakes| {
+        stakes.borrow_mut().insert((staker, contract), staked);
+    });
+}
+
+/// Starts the next era in which the current stake is effective.
+fn advance_era() {
+    let era = CURRENT_ERA.with(|era| {
+        *era.borrow_mut() += 1;
+        *era.borrow()
+    });
+    let contract_stakes = STAKES.with(|stakes| {
+        stakes
+            .borrow()
+            .iter()
+            .fold(BTreeMap::new(), |mut acc, ((_, contract), staked)| {
+                *acc.entry(*contract).or_insert(0) += staked;
+                acc
+            })
+    });
+    ERA_STAKES.with(|era_stakes| era_stakes.borrow_mut().insert(era, contract_stakes));
+}
+
+pub struct MockStakingInfo;
+impl DappsStakingInfo<AccountId, H160, Balance> for MockStakingInfo {
+    fn staker_total_stake(staker: &AccountId) -> Balance {
+        STAKES.with(|stakes| {
+            stakes
+                .borrow()
+                .iter()
+                .filter(|((account, _), _)| account == staker)
+                .map(|(_, staked)| staked)
+                .sum()
+        })
+    }
+
+    fn staker_contract_stake(staker: &AccountId, contract: &H160) -> Balance {
+        STAKES.with(|stakes| {
+            stakes
+                .borrow()
+                .get(&(*staker, *contract))
+                .copied()
+                .unwrap_or_default()
+        })
+    }
+
+    fn contract_total_stake(contract: &H160) -> Balance {
+        STAKES.with(|stakes| {
+            stakes
+                .borrow()
+                .iter()
+                .filter(|((_, staked_contract), _)| staked_contract == contract)
+                .map(|(_, staked)| staked)
+                .sum()
+        })
+    }
+
+    fn total_staked() -> Balance {
+        STAKES.with(|stakes| stakes.borrow().values().sum())
+    }
+
+    fn current_era() -> EraIndex {
+        CURRENT_ERA.with(|era| *era.borrow())
+    }
+
+    fn contract_era_stake(contract: &H160, era: EraIndex) -> Balance {
+        ERA_STAKES.with(|era_stakes| {
+            era_stakes
+                .borrow()
+                .get(&era)
+                .and_then(|contract_stakes| contract_stakes.get(contract).copied())
+                .unwrap_or_default()
+        })
+    }
+
+    fn era_total_stake(era: EraIndex) -> Balance {
+        ERA_STAKES.with(|era_stakes| {
+            era_stakes
+                .borrow()
+                .get(&era)
+                .map(|contract_stakes| contract_stakes.values().sum())
+                .unwrap_or_default()
+        })
+    }
+}
+
+parameter_types! {
+    pub const MaxPollOptions: u32 = 3;
+}
+
+impl Config for Runtime {
+    type Event = Event;
+    type SmartContract = H160;
+    type Balance = Balance;
+    type StakingInfo = MockStakingInfo;
+    type PollOrigin = frame_system::EnsureRoot<AccountId>;
+    type MaxPollOptions = MaxPollOptions;
+    type WeightInfo = ();
+}
+
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+    STAKES.with(|stakes| stakes.borrow_mut().clear());
+    CURRENT_ERA.with(|era| *era.borrow_mut() = 1);
+    ERA_STAKES.with(|era_stakes| era_stakes.borrow_mut().clear());
+    let storage = frame_system::GenesisConfig::default()
+        .build_storage::<Runtime>()
+        .unwrap();
+    let mut ext = sp_io::TestExternalities::from(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+fn last_event() -> Event {
+    System::events().pop().expect("Event expected").event
+}
+
+const DESCRIPTION: H256 = H256::repeat_byte(0xaa);
+
+/// Creates poll options from the given contracts.
+fn options(contracts: Vec<H160>) -> BoundedVec<H160, MaxPollOptions> {
+    contracts.try_into().expect("Options fit into the bound.")
+}
+
+#[test]
+fn open_poll_is_ok() {
+    new_test_ext().execute_with(|| {
+        let options = options(vec![H160::repeat_byte(0x01), H160::repeat_byte(0x02)]);
+
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options.clone(),
+            10
+        ));
+        assert_eq!(
+            last_event(),
+            Event::DappsSignalling(crate::Event::PollOpened(0, 11))
+        );
+        assert_eq!(
+            DappsSignalling::polls(0),
+            Some(Poll {
+                description: DESCRIPTION,
+                options,
+                end: 11,
+                start_era: 1,
+            })
+        );
+        assert_eq!(DappsSignalling::poll_count(), 1);
+
+        // Polls are indexed in the order they are opened.
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options(vec![H160::repeat_byte(0x03)]),
+            5
+        ));
+        assert_eq!(
+            last_event(),
+            Event::DappsSignalling(crate::Event::PollOpened(1, 6))
+        );
+        assert_eq!(DappsSignalling::poll_count(), 2);
+    })
+}
+
+#[test]
+fn open_poll_is_not_ok() {
+    new_test_ext().execute_with(|| {
+        let contract = H160::repeat_byte(0x01);
+
+        assert_noop!(
+            DappsSignalling::open_poll(Origin::signed(1), DESCRIPTION, options(vec![contract]), 10),
+            BadOrigin
+        );
+        assert_noop!(
+            DappsSignalling::open_poll(Origin::root(), DESCRIPTION, options(vec![]), 10),
+            Error::<Runtime>::NoPollOptions
+        );
+        assert_noop!(
+            DappsSignalling::open_poll(
+                Origin::root(),
+                DESCRIPTION,
+                options(vec![contract, H160::repeat_byte(0x02), contract]),
+                10
+            ),
+            Error::<Runtime>::DuplicatePollOption
+        );
+        assert_noop!(
+            DappsSignalling::open_poll(Origin::root(), DESCRIPTION, options(vec![contract]), 0),
+            Error::<Runtime>::ZeroPollDuration
+        );
+    })
+}
+
+#[test]
+fn close_poll_is_ok() {
+    new_test_ext().execute_with(|| {
+        let first_contract = H160::repeat_byte(0x01);
+        let second_contract = H160::repeat_byte(0x02);
+        let other_contract = H160::repeat_byte(0x03);
+
+        set_stake(1, first_contract, 100);
+        set_stake(2, first_contract, 50);
+        set_stake(2, other_contract, 500);
+
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options(vec![first_contract, second_contract]),
+            10
+        ));
+        advance_era();
+
+        // Stake made during the counted era isn't effective until the next era.
+        set_stake(1, first_contract, 20);
+        set_stake(3, second_contract, 300);
+        advance_era();
+
+        // Stake of the era in progress isn't counted.
+        set_stake(4, second_contract, 1000);
+        System::set_block_number(11);
+
+        assert_ok!(DappsSignalling::close_poll(Origin::signed(4), 0));
+
+        let tally = vec![(first_contract, 150), (second_contract, 0)];
+        assert_eq!(
+            last_event(),
+            Event::DappsSignalling(crate::Event::PollClosed(0, 2, tally.clone(), 650))
+        );
+        assert_eq!(
+            DappsSignalling::poll_results(0),
+            Some(PollResult {
+                era: 2,
+                tally,
+                total_staked: 650,
+            })
+        );
+
+        // Poll is kept so results can be matched with the description.
+        assert!(DappsSignalling::polls(0).is_some());
+    })
+}
+
+#[test]
+fn close_poll_is_not_ok() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options(vec![H160::repeat_byte(0x01)]),
+            10
+        ));
+
+        assert_noop!(DappsSignalling::close_poll(Origin::none(), 0), BadOrigin);
+        assert_noop!(
+            DappsSignalling::close_poll(Origin::signed(1), 1),
+            Error::<Runtime>::UnknownPoll
+        );
+
+        System::set_block_number(10);
+        assert_noop!(
+            DappsSignalling::close_poll(Origin::signed(1), 0),
+            Error::<Runtime>::PollNotEnded
+        );
+
+        // Era in which the poll was opened must finish first.
+        System::set_block_number(11);
+        assert_noop!(
+            DappsSignalling::close_poll(Origin::signed(1), 0),
+            Error::<Runtime>::PollEraNotFinished
+        );
+
+        advance_era();
+        assert_ok!(DappsSignalling::close_poll(Origin::signed(1), 0));
+        assert_noop!(
+            DappsSignalling::close_poll(Origin::signed(1), 0),
+            Error::<Runtime>::PollAlreadyClosed
+        );
+    })
+}
+
+#[test]
+fn cancel_poll_is_ok() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options(vec![H160::repeat_byte(0x01)]),
+            10
+        ));
+        assert_ok!(DappsSignalling::open_poll(
+            Origin::root(),
+            DESCRIPTION,
+            options(vec![H160::repeat_byte(0x02)]),
+            10
+        ));
+
+        assert_noop!(
+            DappsSignalling::cancel_poll(Origin::signed(1), 0),
+            BadOrigin
+        );
+        assert_ok!(DappsSignalling::cancel_poll(Origin::root(), 0));
+        assert_eq!(
+            last_event(),
+            Event::DappsSignalling(crate::Event::PollCancelled(0))
+        );
+        assert!(DappsSignalling::polls(0).is_none());
+        assert_noop!(
+            DappsSignalling::cancel_poll(Origin::root(), 0),
+            Error::<Runtime>::UnknownPoll
+        );
+
+        // Closed polls can't be cancelled.
+        System::set_block_number(11);
+        advance_era();
+        assert_ok!(DappsSignalling::close_poll(Origin::signed(1), 1));
+        assert_noop!(
+            DappsSignalling::cancel_poll(Origin::root(), 1),
+            Error::<Runtime>::PollAlreadyClosed
+        );
+
+        // Cancelled poll can't be closed.
+        assert_noop!(
+            DappsSignalling::close_poll(Origin::signed(1), 0),
+            Error::<Runtime>::UnknownPoll
+        );
+    })
+}
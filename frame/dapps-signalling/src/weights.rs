@@ -0,0 +1,85 @@
+//! Weights for `pallet_dapps_signalling`
+//!
+//! Storage accesses follow the benchmarks in `benchmarking.rs`. Execution times are estimates
+//! until the weights are generated with the benchmark CLI.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_dapps_signalling.
+pub trait WeightInfo {
+    /// n - number of poll options
+    fn open_poll(n: u32) -> Weight;
+    /// n - number of poll options
+    fn close_poll(n: u32) -> Weight;
+    fn cancel_poll() -> Weight;
+}
+
+/// Weights for pallet_dapps_signalling using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsSignalling PollCount (r:1 w:1)
+	// Storage: DappsSignalling Polls (r:0 w:1)
+	fn open_poll(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((200_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: DappsSignalling Polls (r:1 w:0)
+	// Storage: DappsSignalling PollResults (r:1 w:1)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking ContractEraStake (r:16 w:0)
+	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:0)
+	fn close_poll(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((5_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: DappsSignalling Polls (r:1 w:1)
+	// Storage: DappsSignalling PollResults (r:1 w:0)
+	fn cancel_poll() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsSignalling PollCount (r:1 w:1)
+	// Storage: DappsSignalling Polls (r:0 w:1)
+	fn open_poll(n: u32, ) -> Weight {
+		(20_000_000 as Weight)
+			.saturating_add((200_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(2 as Weight))
+	}
+	// Storage: DappsSignalling Polls (r:1 w:0)
+	// Storage: DappsSignalling PollResults (r:1 w:1)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking ContractEraStake (r:16 w:0)
+	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:0)
+	fn close_poll(n: u32, ) -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add((5_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(RocksDbWeight::get().reads(4 as Weight))
+			.saturating_add(RocksDbWeight::get().reads((1 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+	// Storage: DappsSignalling Polls (r:1 w:1)
+	// Storage: DappsSignalling PollResults (r:1 w:0)
+	fn cancel_poll() -> Weight {
+		(15_000_000 as Weight)
+			.saturating_add(RocksDbWeight::get().reads(2 as Weight))
+			.saturating_add(RocksDbWeight::get().writes(1 as Weight))
+	}
+}
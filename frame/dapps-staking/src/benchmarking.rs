@@ -4,9 +4,13 @@ use super::*;
 use crate::Pallet as DappsStaking;
 
 use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
-use frame_support::traits::{Get, OnFinalize, OnInitialize, OnUnbalanced};
+use frame_support::{
+    traits::{Get, OnFinalize, OnInitialize, OnUnbalanced},
+    BoundedVec,
+};
 use frame_system::{Pallet as System, RawOrigin};
-use sp_runtime::traits::{Bounded, One};
+use sp_runtime::traits::{Bounded, One, Zero};
+use sp_std::convert::TryInto;
 
 const SEED: u32 = 9000;
 const BLOCK_REWARD: u32 = 1000u32;
@@ -18,6 +22,7 @@ const BLOCK_REWARD: u32 = 1000u32;
 fn initialize<T: Config>() {
     // Remove everything from storage.
     Ledger::<T>::remove_all(None);
+    StakerPositions::<T>::remove_all(None);
     RegisteredDevelopers::<T>::remove_all(None);
     RegisteredDapps::<T>::remove_all(None);
     EraRewardsAndStakes::<T>::remove_all(None);
@@ -25,6 +30,12 @@ fn initialize<T: Config>() {
     CurrentEra::<T>::kill();
     BlockRewardAccumulator::<T>::kill();
     PreApprovalIsEnabled::<T>::kill();
+    StakingReceipts::<T>::remove_all(None);
+    StakingReceiptsAreEnabled::<T>::kill();
+    ApproxTopContracts::<T>::kill();
+    ContractEraUsage::<T>::remove_all(None);
+    EraUsage::<T>::remove_all(None);
+    ContractUsageCallers::<T>::remove_all(None);
 
     // Initialize the first block.
     DappsStaking::<T>::on_unbalanced(T::Currency::issue(BLOCK_REWARD.into()));
@@ -62,6 +73,34 @@ fn register_contract<T: Config>() -> Result<(T::AccountId, T::SmartContract), &'
     Ok((developer, contract_id))
 }
 
+/// Used to create a distinct smart contract id from the given seed.
+///
+/// Assumes that the first variant of the smart contract type wraps a 20 byte address (e.g. `H160`).
+fn contract_from_seed<T: Config>(seed: u32) -> T::SmartContract {
+    let mut encoded = [0u8; 21];
+    encoded[1..5].copy_from_slice(&seed.to_le_bytes());
+    T::SmartContract::decode(&mut &encoded[..]).expect("Contract can be decoded from 21 bytes.")
+}
+
+/// Used to register the specified amount of contracts, each by a different developer account.
+///
+/// Registered contracts are returned.
+fn register_contracts<T: Config>(
+    number_of_contracts: u32,
+) -> Result<Vec<T::SmartContract>, &'static str> {
+    let mut contracts = Vec::new();
+
+    for id in 0..number_of_contracts {
+        let developer: T::AccountId = account("developer", id, SEED);
+        T::Currency::make_free_balance_be(&developer, BalanceOf::<T>::max_value());
+        let contract_id = contract_from_seed::<T>(id + 1);
+        DappsStaking::<T>::register(RawOrigin::Signed(developer).into(), contract_id.clone())?;
+        contracts.push(contract_id);
+    }
+
+    Ok(contracts)
+}
+
 /// Used to bond_and_stake the given contract with the specified amount of stakers.
 /// Method will create new staker accounts using the provided seed.
 ///
@@ -163,6 +202,60 @@ benchmarks! {
         assert_last_event::<T>(Event::<T>::UnbondUnstakeAndWithdraw(staker, contract_id, amount).into());
     }
 
+    bond_and_stake_multi {
+        let n in 1 .. T::MaxStakingTargetsPerCall::get();
+        initialize::<T>();
+
+        // Staker already has positions on other contracts so its index is full after the call.
+        let number_of_positions = T::MaxStakedContractsPerStaker::get().saturating_sub(n);
+        let contracts = register_contracts::<T>(number_of_positions + n)?;
+        let staker = whitelisted_caller();
+        let _ = T::Currency::make_free_balance_be(&staker, BalanceOf::<T>::max_value());
+        let amount = T::MinimumStakingAmount::get();
+
+        let (staked_contracts, target_contracts) = contracts.split_at(number_of_positions as usize);
+        for contract_id in staked_contracts.iter() {
+            DappsStaking::<T>::bond_and_stake(RawOrigin::Signed(staker.clone()).into(), contract_id.clone(), amount.clone())?;
+        }
+        // Each target already has the maximum number of stakers except for the staker.
+        for (id, contract_id) in target_contracts.iter().enumerate() {
+            prepare_bond_and_stake::<T>(T::MaxNumberOfStakersPerContract::get() - 1, contract_id, SEED + id as u32)?;
+        }
+
+        let targets: Vec<(T::SmartContract, BalanceOf<T>)> = target_contracts.iter().map(|contract_id| (contract_id.clone(), amount)).collect();
+        let targets: BoundedVec<_, T::MaxStakingTargetsPerCall> = targets.try_into().map_err(|_| "Too many targets.")?;
+        let last_contract = contracts[contracts.len() - 1].clone();
+
+    }: _(RawOrigin::Signed(staker.clone()), targets)
+    verify {
+        assert_last_event::<T>(Event::<T>::BondAndStake(staker, last_contract, amount).into());
+    }
+
+    unbond_all {
+        let n in 1 .. T::MaxStakingTargetsPerCall::get();
+        initialize::<T>();
+
+        let contracts = register_contracts::<T>(n)?;
+        let staker = whitelisted_caller();
+        let _ = T::Currency::make_free_balance_be(&staker, BalanceOf::<T>::max_value());
+        let amount = T::MinimumStakingAmount::get();
+        DappsStaking::<T>::enable_staking_receipts(RawOrigin::Root.into(), true)?;
+
+        // Each contract has the maximum number of stakers and the staker has a receipt for each position.
+        for (id, contract_id) in contracts.iter().enumerate() {
+            prepare_bond_and_stake::<T>(T::MaxNumberOfStakersPerContract::get() - 1, contract_id, SEED + id as u32)?;
+            DappsStaking::<T>::bond_and_stake(RawOrigin::Signed(staker.clone()).into(), contract_id.clone(), amount.clone())?;
+            DappsStaking::<T>::mint_staking_receipt(RawOrigin::Signed(staker.clone()).into(), contract_id.clone())?;
+        }
+        // Stake is effective in the current era so it's removed from both the current and the next era.
+        advance_to_era::<T>(2);
+
+    }: _(RawOrigin::Signed(staker.clone()))
+    verify {
+        assert!(DappsStaking::<T>::ledger(&staker).is_zero());
+        assert!(DappsStaking::<T>::staker_positions(&staker).is_empty());
+    }
+
     claim {
         let n in 2 .. T::MaxNumberOfStakersPerContract::get();
 
@@ -170,7 +263,8 @@ benchmarks! {
         let (developer_id, contract_id) = register_contract::<T>()?;
 
         let number_of_stakers = n - 1;
-        let claim_era = DappsStaking::<T>::current_era();
+        // Stake becomes effective in the era following the one in which it was made
+        let claim_era = DappsStaking::<T>::current_era() + 1u32;
         prepare_bond_and_stake::<T>(number_of_stakers, &contract_id, SEED)?;
 
         advance_to_era::<T>(claim_era + 1u32);
@@ -180,9 +274,78 @@ benchmarks! {
         let claimer: T::AccountId = whitelisted_caller();
     }: _(RawOrigin::Signed(claimer.clone()), contract_id.clone(), claim_era)
 
+    enable_staking_receipts {
+    }: _(RawOrigin::Root, true)
+    verify {
+        assert!(DappsStaking::<T>::staking_receipts_are_enabled());
+    }
+
+    mint_staking_receipt {
+        initialize::<T>();
+        let (_, contract_id) = register_contract::<T>()?;
+
+        let staker = whitelisted_caller();
+        let _ = T::Currency::make_free_balance_be(&staker, BalanceOf::<T>::max_value());
+        DappsStaking::<T>::bond_and_stake(RawOrigin::Signed(staker.clone()).into(), contract_id.clone(), T::MinimumStakingAmount::get())?;
+        DappsStaking::<T>::enable_staking_receipts(RawOrigin::Root.into(), true)?;
+
+    }: _(RawOrigin::Signed(staker.clone()), contract_id.clone())
+    verify {
+        assert_last_event::<T>(Event::<T>::StakingReceiptMinted(staker, contract_id).into());
+    }
+
+    transfer_staking_receipt {
+        initialize::<T>();
+        let (_, contract_id) = register_contract::<T>()?;
+        let amount = T::MinimumStakingAmount::get();
+
+        // Receiver already has a position on the contract so the two need to be merged.
+        let staker = whitelisted_caller();
+        let receiver: T::AccountId = account("receiver", 0, SEED);
+        for account in [staker.clone(), receiver.clone()].iter() {
+            let _ = T::Currency::make_free_balance_be(account, BalanceOf::<T>::max_value() / 2u32.into());
+            DappsStaking::<T>::bond_and_stake(RawOrigin::Signed(account.clone()).into(), contract_id.clone(), amount.clone())?;
+        }
+        DappsStaking::<T>::enable_staking_receipts(RawOrigin::Root.into(), true)?;
+        DappsStaking::<T>::mint_staking_receipt(RawOrigin::Signed(staker.clone()).into(), contract_id.clone())?;
+        advance_to_era::<T>(2);
+
+    }: _(RawOrigin::Signed(staker.clone()), contract_id.clone(), receiver.clone())
+    verify {
+        assert_last_event::<T>(Event::<T>::StakingReceiptTransferred(staker, receiver, contract_id, amount).into());
+    }
+
+    burn_staking_receipt {
+        initialize::<T>();
+        let (_, contract_id) = register_contract::<T>()?;
+
+        let staker = whitelisted_caller();
+        let _ = T::Currency::make_free_balance_be(&staker, BalanceOf::<T>::max_value());
+        DappsStaking::<T>::bond_and_stake(RawOrigin::Signed(staker.clone()).into(), contract_id.clone(), T::MinimumStakingAmount::get())?;
+        DappsStaking::<T>::enable_staking_receipts(RawOrigin::Root.into(), true)?;
+        DappsStaking::<T>::mint_staking_receipt(RawOrigin::Signed(staker.clone()).into(), contract_id.clone())?;
+
+    }: _(RawOrigin::Signed(staker.clone()), contract_id.clone())
+    verify {
+        assert_last_event::<T>(Event::<T>::StakingReceiptBurned(staker, contract_id).into());
+    }
+
     force_new_era {
     }: _(RawOrigin::Root)
 
+    note_contract_usage {
+        initialize::<T>();
+        let (_, contract_id) = register_contract::<T>()?;
+        let caller: T::AccountId = whitelisted_caller();
+        let current_era = DappsStaking::<T>::current_era();
+
+    }: {
+        DappsStaking::<T>::note_contract_usage(&contract_id, &caller);
+    }
+    verify {
+        assert_eq!(DappsStaking::<T>::contract_era_usage(&contract_id, current_era), 1);
+    }
+
 }
 
 impl_benchmark_test_suite!(
@@ -0,0 +1,495 @@
+//! Dapps staking economics simulator.
+//!
+//! Drives the pallet with the simulator runtime according to a JSON scenario and outputs per-era CSV
+//! with the staked amount, rewards, APR, rewards per contract and the pallet account balance.
+//!
+//! Usage: `dapps-staking-simulator <scenario.json> [output.csv]`
+//!
+//! Example scenario:
+//! ```json
+//! {
+//!     "eras": 1000,
+//!     "blocks_per_era": 10,
+//!     "block_reward": 1000,
+//!     "developer_reward_percentage": 80,
+//!     "bonus_era_duration": 30,
+//!     "history_depth": 30,
+//!     "claim_delay": 1,
+//!     "contracts": 3,
+//!     "stakers": 10,
+//!     "staker_balance": 1000000,
+//!     "actions": [
+//!         { "action": "stake", "era": 1, "staker": 0, "contract": 0, "amount": 5000 },
+//!         { "action": "unstake", "era": 20, "staker": 0, "contract": 0, "amount": 2000 }
+//!     ],
+//!     "random_behavior": {
+//!         "seed": 42,
+//!         "stake_probability": 0.05,
+//!         "unstake_probability": 0.02,
+//!         "min_amount": 100,
+//!         "max_amount": 10000
+//!     }
+//! }
+//! ```
+
+use frame_support::traits::{Currency, Get, OnFinalize, OnInitialize, OnUnbalanced};
+use pallet_dapps_staking::Error;
+use runtime::{
+    new_externalities, AccountId, Balance, Balances, BlockPerEra, BonusEraDuration, DappsStaking,
+    DeveloperRewardPercentage, EraIndex, Event, HistoryDepth, MaxNumberOfStakersPerContract,
+    MaxStakedContractsPerStaker, Origin, SimulatedContract, SimulatorRuntime, System,
+};
+use serde::Deserialize;
+use sp_core::H160;
+use sp_runtime::{traits::AccountIdConversion, Perbill};
+use std::{collections::BTreeMap, env, fs, io::Write, process};
+
+mod runtime;
+
+/// First account used for developers.
+const DEVELOPER_ACCOUNT_OFFSET: AccountId = 100_000;
+/// First account used for stakers.
+const STAKER_ACCOUNT_OFFSET: AccountId = 200_000;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Scenario {
+    /// Number of eras to simulate.
+    eras: EraIndex,
+    /// Number of blocks per era.
+    blocks_per_era: u64,
+    /// Reward which dapps staking receives for each block.
+    block_reward: Balance,
+    /// Percentage of the stake based contract reward paid to the developer.
+    developer_reward_percentage: u32,
+    /// Number of eras with doubled claim rewards.
+    bonus_era_duration: u32,
+    /// Number of eras which can still be claimed.
+    history_depth: u32,
+    /// Maximum number of stakers per contract.
+    max_stakers_per_contract: u32,
+    /// Used to annualize era rewards when calculating APR.
+    eras_per_year: u32,
+    /// Balance of the pallet account before the first era.
+    initial_pallet_balance: Balance,
+    /// Each era is claimed for every contract this many eras after it ends.
+    claim_delay: u32,
+    /// Number of registered contracts.
+    contracts: u32,
+    /// Number of staker accounts.
+    stakers: u32,
+    /// Free balance of each staker account.
+    staker_balance: Balance,
+    /// Explicit staker actions.
+    actions: Vec<Action>,
+    /// Synthetic staker behavior applied each era in addition to explicit actions.
+    random_behavior: Option<RandomBehavior>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            eras: 100,
+            blocks_per_era: 10,
+            block_reward: 1000,
+            developer_reward_percentage: 80,
+            bonus_era_duration: 0,
+            history_depth: 30,
+            max_stakers_per_contract: 512,
+            eras_per_year: 365,
+            initial_pallet_balance: 0,
+            claim_delay: 1,
+            contracts: 1,
+            stakers: 1,
+            staker_balance: 1_000_000,
+            actions: Vec::new(),
+            random_behavior: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+enum Action {
+    Stake {
+        era: EraIndex,
+        staker: u32,
+        contract: u32,
+        amount: Balance,
+    },
+    Unstake {
+        era: EraIndex,
+        staker: u32,
+        contract: u32,
+        amount: Balance,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RandomBehavior {
+    seed: u64,
+    /// Probability that a staker stakes on a random contract in an era.
+    stake_probability: f64,
+    /// Probability that a staker unstakes from a random contract in an era.
+    unstake_probability: f64,
+    min_amount: Balance,
+    max_amount: Balance,
+}
+
+/// Simple deterministic LCG so simulations can be reproduced.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 11
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.next_u64() as f64 / (1u64 << 53) as f64
+    }
+
+    fn next_range(&mut self, low: u128, high: u128) -> u128 {
+        low + (self.next_u64() as u128) % (high.saturating_sub(low) + 1)
+    }
+}
+
+/// Statistics collected for a single era.
+#[derive(Default)]
+struct EraStats {
+    staked: Balance,
+    era_reward: Balance,
+    staker_rewards: Balance,
+    developer_rewards: Balance,
+    contract_rewards: Vec<Balance>,
+    pallet_balance: Balance,
+    failed_claims: u32,
+}
+
+/// Statistics of the entire simulation.
+struct Simulation {
+    contracts: Vec<SimulatedContract>,
+    developers: Vec<AccountId>,
+    stakers: Vec<AccountId>,
+    eras: BTreeMap<EraIndex, EraStats>,
+    failed_actions: u32,
+}
+
+impl Simulation {
+    /// Registers contracts and funds staker accounts.
+    fn new(scenario: &Scenario) -> Self {
+        let contracts: Vec<_> = (0..scenario.contracts)
+            .map(|id| SimulatedContract(H160::from_low_u64_be(id as u64 + 1)))
+            .collect();
+        let developers: Vec<_> = (0..scenario.contracts)
+            .map(|id| DEVELOPER_ACCOUNT_OFFSET + id as AccountId)
+            .collect();
+        let stakers: Vec<_> = (0..scenario.stakers)
+            .map(|id| STAKER_ACCOUNT_OFFSET + id as AccountId)
+            .collect();
+
+        for (developer, contract) in developers.iter().zip(contracts.iter()) {
+            let deposit =
+                <SimulatorRuntime as pallet_dapps_staking::Config>::RegisterDeposit::get() * 2;
+            let _ = Balances::deposit_creating(developer, deposit);
+            DappsStaking::register(Origin::signed(*developer), *contract)
+                .expect("Each developer registers a single contract.");
+        }
+        for staker in stakers.iter() {
+            let _ = Balances::deposit_creating(staker, scenario.staker_balance);
+        }
+
+        Simulation {
+            contracts,
+            developers,
+            stakers,
+            eras: BTreeMap::new(),
+            failed_actions: 0,
+        }
+    }
+
+    fn stake(&mut self, staker: u32, contract: u32, amount: Balance) {
+        let (staker, contract) = match self.accounts(staker, contract) {
+            Some(accounts) => accounts,
+            None => return,
+        };
+        if DappsStaking::bond_and_stake(Origin::signed(staker), contract, amount).is_err() {
+            self.failed_actions += 1;
+        }
+    }
+
+    fn unstake(&mut self, staker: u32, contract: u32, amount: Balance) {
+        let (staker, contract) = match self.accounts(staker, contract) {
+            Some(accounts) => accounts,
+            None => return,
+        };
+        if DappsStaking::unbond_unstake_and_withdraw(Origin::signed(staker), contract, amount)
+            .is_err()
+        {
+            self.failed_actions += 1;
+        }
+    }
+
+    /// Returns the staker account and the contract for the given scenario indices.
+    fn accounts(&mut self, staker: u32, contract: u32) -> Option<(AccountId, SimulatedContract)> {
+        match (
+            self.stakers.get(staker as usize),
+            self.contracts.get(contract as usize),
+        ) {
+            (Some(staker), Some(contract)) => Some((*staker, *contract)),
+            _ => {
+                self.failed_actions += 1;
+                None
+            }
+        }
+    }
+
+    /// Claims the era for all contracts and records the paid out rewards.
+    fn claim(&mut self, era: EraIndex) {
+        let contracts = self.contracts.clone();
+        let number_of_contracts = contracts.len();
+        let developers = self.developers.clone();
+
+        for contract in contracts {
+            System::reset_events();
+            let result = DappsStaking::claim(Origin::signed(developers[0]), contract, era);
+
+            let stats = self.eras.entry(era).or_default();
+            stats
+                .contract_rewards
+                .resize(number_of_contracts, Default::default());
+            match result {
+                Ok(_) => (),
+                // Contracts without stakers have nothing to claim.
+                Err(e) if e.error == Error::<SimulatorRuntime>::NotStaked.into() => continue,
+                Err(_) => {
+                    stats.failed_claims += 1;
+                    continue;
+                }
+            }
+
+            for record in System::events() {
+                if let Event::DappsStaking(pallet_dapps_staking::Event::Reward(
+                    account,
+                    contract,
+                    _,
+                    reward,
+                )) = record.event
+                {
+                    if developers.contains(&account) {
+                        stats.developer_rewards += reward;
+                    } else {
+                        stats.staker_rewards += reward;
+                    }
+                    if let Some(index) = self.contracts.iter().position(|c| *c == contract) {
+                        stats.contract_rewards[index] += reward;
+                    }
+                }
+            }
+        }
+        System::reset_events();
+    }
+
+    /// Writes the collected statistics as CSV.
+    fn write_csv(&self, out: &mut dyn Write, eras_per_year: u32) -> std::io::Result<()> {
+        write!(
+            out,
+            "era,staked,era_reward,staker_rewards,developer_rewards,apr_percent,pallet_balance,failed_claims"
+        )?;
+        for id in 0..self.contracts.len() {
+            write!(out, ",contract_{}_reward", id)?;
+        }
+        writeln!(out)?;
+
+        for (era, stats) in self.eras.iter() {
+            let apr = if stats.staked == 0 {
+                0.0
+            } else {
+                stats.staker_rewards as f64 / stats.staked as f64 * eras_per_year as f64 * 100.0
+            };
+            write!(
+                out,
+                "{},{},{},{},{},{:.4},{},{}",
+                era,
+                stats.staked,
+                stats.era_reward,
+                stats.staker_rewards,
+                stats.developer_rewards,
+                apr,
+                stats.pallet_balance,
+                stats.failed_claims
+            )?;
+            for id in 0..self.contracts.len() {
+                write!(
+                    out,
+                    ",{}",
+                    stats.contract_rewards.get(id).copied().unwrap_or_default()
+                )?;
+            }
+            writeln!(out)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs a single block which receives the block reward.
+fn run_block(block_reward: Balance) {
+    DappsStaking::on_finalize(System::block_number());
+    System::set_block_number(System::block_number() + 1);
+    DappsStaking::on_unbalanced(Balances::issue(block_reward));
+    DappsStaking::on_initialize(System::block_number());
+}
+
+fn pallet_account() -> AccountId {
+    <SimulatorRuntime as pallet_dapps_staking::Config>::PalletId::get().into_account()
+}
+
+fn simulate(scenario: &Scenario) -> Simulation {
+    let mut simulation = None;
+
+    new_externalities().execute_with(|| {
+        BlockPerEra::set(&scenario.blocks_per_era);
+        DeveloperRewardPercentage::set(&Perbill::from_percent(
+            scenario.developer_reward_percentage,
+        ));
+        BonusEraDuration::set(&scenario.bonus_era_duration);
+        HistoryDepth::set(&scenario.history_depth);
+        MaxNumberOfStakersPerContract::set(&scenario.max_stakers_per_contract);
+        // Stakers can stake on all of the simulated contracts.
+        MaxStakedContractsPerStaker::set(&scenario.contracts);
+
+        let _ = Balances::deposit_creating(&pallet_account(), scenario.initial_pallet_balance);
+
+        // Initialize the first block which starts the first era.
+        DappsStaking::on_unbalanced(Balances::issue(scenario.block_reward));
+        DappsStaking::on_initialize(System::block_number());
+
+        let mut sim = Simulation::new(scenario);
+        let mut rng = scenario
+            .random_behavior
+            .as_ref()
+            .map(|behavior| Rng(behavior.seed));
+
+        for era in 1..=scenario.eras {
+            for action in scenario.actions.iter() {
+                match *action {
+                    Action::Stake {
+                        era: action_era,
+                        staker,
+                        contract,
+                        amount,
+                    } if action_era == era => sim.stake(staker, contract, amount),
+                    Action::Unstake {
+                        era: action_era,
+                        staker,
+                        contract,
+                        amount,
+                    } if action_era == era => sim.unstake(staker, contract, amount),
+                    _ => (),
+                }
+            }
+
+            if let (Some(behavior), Some(rng)) = (scenario.random_behavior.as_ref(), rng.as_mut()) {
+                for staker in 0..scenario.stakers {
+                    if rng.next_f64() < behavior.stake_probability {
+                        let contract = rng.next_range(0, scenario.contracts as u128 - 1) as u32;
+                        let amount = rng.next_range(behavior.min_amount, behavior.max_amount);
+                        sim.stake(staker, contract, amount);
+                    }
+                    if rng.next_f64() < behavior.unstake_probability {
+                        let contract = rng.next_range(0, scenario.contracts as u128 - 1) as u32;
+                        let amount = rng.next_range(behavior.min_amount, behavior.max_amount);
+                        sim.unstake(staker, contract, amount);
+                    }
+                }
+            }
+
+            if era > scenario.claim_delay {
+                sim.claim(era - scenario.claim_delay);
+            }
+
+            while DappsStaking::current_era() == era {
+                run_block(scenario.block_reward);
+            }
+            System::reset_events();
+
+            let reward_and_stake = DappsStaking::era_reward_and_stake(era).unwrap_or_default();
+            let stats = sim.eras.entry(era).or_default();
+            stats.staked = reward_and_stake.staked();
+            stats.era_reward = reward_and_stake.rewards();
+            stats.pallet_balance = Balances::free_balance(&pallet_account());
+        }
+
+        // Claim the remaining eras so all of them have their rewards reported.
+        let first_unclaimed_era = scenario.eras.saturating_sub(scenario.claim_delay) + 1;
+        for era in first_unclaimed_era.max(1)..=scenario.eras {
+            sim.claim(era);
+        }
+
+        simulation = Some(sim);
+    });
+
+    simulation.expect("Simulation is executed in externalities.")
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("Usage: {} <scenario.json> [output.csv]", args[0]);
+        process::exit(1);
+    }
+
+    let scenario: Scenario = fs::read_to_string(&args[1])
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load scenario {}: {}", args[1], e);
+            process::exit(1);
+        });
+    if scenario.contracts == 0 || scenario.blocks_per_era == 0 || scenario.claim_delay == 0 {
+        eprintln!("Scenario needs at least one contract, one block per era and claim delay of at least one era.");
+        process::exit(1);
+    }
+
+    let simulation = simulate(&scenario);
+    eprintln!(
+        "Simulated {} eras, {} staker actions failed.",
+        scenario.eras, simulation.failed_actions
+    );
+
+    let result = match args.get(2) {
+        Some(path) => fs::File::create(path)
+            .and_then(|mut file| simulation.write_csv(&mut file, scenario.eras_per_year)),
+        None => simulation.write_csv(&mut std::io::stdout(), scenario.eras_per_year),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to write output: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn golden_scenario_output_is_unchanged() {
+        let scenario: Scenario = serde_json::from_str(include_str!("golden/scenario.json"))
+            .expect("Golden scenario is valid.");
+
+        let mut output = Vec::new();
+        simulate(&scenario)
+            .write_csv(&mut output, scenario.eras_per_year)
+            .expect("Writing to a vector doesn't fail.");
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            include_str!("golden/output.csv")
+        );
+    }
+}
@@ -0,0 +1,138 @@
+//! Runtime which the simulator drives the pallet with.
+//!
+//! Economic parameters are kept in storage so they can be set by the scenario.
+
+use codec::{Decode, Encode};
+use frame_support::{construct_runtime, parameter_types, PalletId};
+use sp_core::{H160, H256};
+use sp_io::TestExternalities;
+use sp_runtime::{
+    testing::Header,
+    traits::{BlakeTwo256, IdentityLookup},
+    Perbill,
+};
+
+pub type AccountId = u64;
+pub type BlockNumber = u64;
+pub type Balance = u128;
+pub type EraIndex = u32;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<SimulatorRuntime>;
+type Block = frame_system::mocking::MockBlock<SimulatorRuntime>;
+
+construct_runtime!(
+    pub enum SimulatorRuntime where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+        DappsStaking: pallet_dapps_staking::{Pallet, Call, Storage, Event<T>},
+    }
+);
+
+parameter_types! {
+    pub const BlockHashCount: u64 = 250;
+}
+
+impl frame_system::Config for SimulatorRuntime {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type Origin = Origin;
+    type Index = u64;
+    type Call = Call;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = AccountId;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = BlockHashCount;
+    type DbWeight = ();
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ();
+    type OnSetCode = ();
+}
+
+parameter_types! {
+    pub const MaxLocks: u32 = 4;
+    pub const ExistentialDeposit: Balance = 2;
+}
+
+impl pallet_balances::Config for SimulatorRuntime {
+    type MaxLocks = MaxLocks;
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type Event = Event;
+    type DustRemoval = ();
+    type ExistentialDeposit = ExistentialDeposit;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const RegisterDeposit: Balance = 10;
+    pub storage BlockPerEra: BlockNumber = 10;
+    pub storage MaxNumberOfStakersPerContract: u32 = 512;
+    pub const MinimumStakingAmount: Balance = 10;
+    pub storage HistoryDepth: u32 = 30;
+    pub storage DeveloperRewardPercentage: Perbill = Perbill::from_percent(80);
+    pub const DappsStakingPalletId: PalletId = PalletId(*b"simdpstk");
+    pub const MinimumRemainingAmount: Balance = 1;
+    pub storage BonusEraDuration: u32 = 0;
+    pub const MaxStakingTargetsPerCall: u32 = 3;
+    pub storage MaxStakedContractsPerStaker: u32 = 1;
+    pub const MaxTopContracts: u32 = 10;
+    pub const UsageRewardPercentage: Perbill = Perbill::from_percent(20);
+}
+
+impl pallet_dapps_staking::Config for SimulatorRuntime {
+    type Event = Event;
+    type Currency = Balances;
+    type BlockPerEra = BlockPerEra;
+    type RegisterDeposit = RegisterDeposit;
+    type DeveloperRewardPercentage = DeveloperRewardPercentage;
+    type SmartContract = SimulatedContract;
+    type WeightInfo = ();
+    type MaxNumberOfStakersPerContract = MaxNumberOfStakersPerContract;
+    type HistoryDepth = HistoryDepth;
+    type BonusEraDuration = BonusEraDuration;
+    type MinimumStakingAmount = MinimumStakingAmount;
+    type PalletId = DappsStakingPalletId;
+    type MinimumRemainingAmount = MinimumRemainingAmount;
+    type MaxStakingTargetsPerCall = MaxStakingTargetsPerCall;
+    type MaxStakedContractsPerStaker = MaxStakedContractsPerStaker;
+    type MaxTopContracts = MaxTopContracts;
+    type Hooks = ();
+    type UsageRewardPercentage = UsageRewardPercentage;
+}
+
+/// Contract identified by its EVM address.
+#[derive(PartialEq, Eq, Copy, Clone, Default, Encode, Decode, Debug, scale_info::TypeInfo)]
+pub struct SimulatedContract(pub H160);
+
+impl pallet_dapps_staking::IsContract for SimulatedContract {
+    fn is_valid(&self) -> bool {
+        true
+    }
+}
+
+/// Builds externalities with empty balances, starting at the first block.
+pub fn new_externalities() -> TestExternalities {
+    let storage = frame_system::GenesisConfig::default()
+        .build_storage::<SimulatorRuntime>()
+        .unwrap();
+
+    let mut ext = TestExternalities::from(storage);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -65,6 +65,18 @@ pub struct EraRewardAndStake<Balance> {
     staked: Balance,
 }
 
+impl<Balance: Copy> EraRewardAndStake<Balance> {
+    /// Total amount of rewards for an era
+    pub fn rewards(&self) -> Balance {
+        self.rewards
+    }
+
+    /// Total staked amount for an era
+    pub fn staked(&self) -> Balance {
+        self.staked
+    }
+}
+
 /// Used to split total EraPayout among contracts.
 /// Each tuple (contract, era) has this structure.
 /// This will be used to reward contracts developer and his stakers.
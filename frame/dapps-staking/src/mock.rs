@@ -3,6 +3,7 @@ use crate::{self as pallet_dapps_staking, weights};
 use frame_support::{
     construct_runtime, parameter_types,
     traits::{Currency, OnFinalize, OnInitialize, OnUnbalanced},
+    weights::Weight,
     PalletId,
 };
 use sp_core::{H160, H256};
@@ -14,6 +15,7 @@ use sp_runtime::{
     traits::{AccountIdConversion, BlakeTwo256, IdentityLookup},
     Perbill,
 };
+use std::cell::RefCell;
 
 pub(crate) type AccountId = u64;
 pub(crate) type BlockNumber = u64;
@@ -31,6 +33,10 @@ pub(crate) const MINIMUM_STAKING_AMOUNT: Balance = 10;
 pub(crate) const DEVELOPER_REWARD_PERCENTAGE: u32 = 80;
 pub(crate) const MINIMUM_REMAINING_AMOUNT: Balance = 1;
 pub(crate) const HISTORY_DEPTH: u32 = 30;
+pub(crate) const MAX_STAKING_TARGETS_PER_CALL: u32 = 3;
+pub(crate) const USAGE_REWARD_PERCENTAGE: u32 = 20;
+pub(crate) const MAX_TOP_CONTRACTS: u32 = 3;
+pub(crate) const MAX_STAKED_CONTRACTS_PER_STAKER: u32 = 4;
 
 // Do note that this needs to at least be 3 for tests to be valid. It can be greater but not smaller.
 pub(crate) const BLOCKS_PER_ERA: BlockNumber = 3;
@@ -41,6 +47,10 @@ pub(crate) const REGISTER_DEPOSIT: Balance = 10;
 // reward for dapps-staking will be BLOCK_REWARD/2 = 1000
 pub(crate) const BLOCK_REWARD: Balance = 1000;
 
+pub(crate) const STAKE_CHANGED_HOOK_WEIGHT: Weight = 1_000;
+pub(crate) const CONTRACT_UNREGISTERED_HOOK_WEIGHT: Weight = 2_000;
+pub(crate) const REWARD_PAID_HOOK_WEIGHT: Weight = 3_000;
+
 construct_runtime!(
     pub enum TestRuntime where
         Block = Block,
@@ -124,6 +134,10 @@ parameter_types! {
     pub const DappsStakingPalletId: PalletId = PalletId(*b"mokdpstk");
     pub const MinimumRemainingAmount: Balance = MINIMUM_REMAINING_AMOUNT;
     pub const BonusEraDuration: u32 = 3;
+    pub const MaxStakingTargetsPerCall: u32 = MAX_STAKING_TARGETS_PER_CALL;
+    pub const MaxStakedContractsPerStaker: u32 = MAX_STAKED_CONTRACTS_PER_STAKER;
+    pub const MaxTopContracts: u32 = MAX_TOP_CONTRACTS;
+    pub const UsageRewardPercentage: Perbill = Perbill::from_percent(USAGE_REWARD_PERCENTAGE);
 }
 
 impl pallet_dapps_staking::Config for TestRuntime {
@@ -140,6 +154,11 @@ impl pallet_dapps_staking::Config for TestRuntime {
     type MinimumStakingAmount = MinimumStakingAmount;
     type PalletId = DappsStakingPalletId;
     type MinimumRemainingAmount = MinimumRemainingAmount;
+    type MaxStakingTargetsPerCall = MaxStakingTargetsPerCall;
+    type MaxStakedContractsPerStaker = MaxStakedContractsPerStaker;
+    type MaxTopContracts = MaxTopContracts;
+    type Hooks = MockHooks;
+    type UsageRewardPercentage = UsageRewardPercentage;
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, Debug, scale_info::TypeInfo)]
@@ -163,6 +182,75 @@ impl<AccountId> pallet_dapps_staking::IsContract for MockSmartContract<AccountId
     }
 }
 
+/// Single invocation of a dapps staking hook.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub(crate) enum HookCall {
+    StakeChanged(AccountId, MockSmartContract<AccountId>, Balance),
+    ContractUnregistered(MockSmartContract<AccountId>),
+    RewardPaid(AccountId, MockSmartContract<AccountId>, EraIndex, Balance),
+}
+
+thread_local! {
+    static HOOK_CALLS: RefCell<Vec<HookCall>> = RefCell::new(Vec::new());
+}
+
+/// Records all hook invocations so they can be inspected by tests.
+pub struct MockHooks;
+
+impl pallet_dapps_staking::DappsStakingHooks<AccountId, MockSmartContract<AccountId>, Balance>
+    for MockHooks
+{
+    fn on_stake_changed(
+        staker: &AccountId,
+        contract: &MockSmartContract<AccountId>,
+        staked: Balance,
+    ) {
+        HOOK_CALLS.with(|calls| {
+            calls
+                .borrow_mut()
+                .push(HookCall::StakeChanged(*staker, *contract, staked))
+        });
+    }
+
+    fn on_contract_unregistered(contract: &MockSmartContract<AccountId>) {
+        HOOK_CALLS.with(|calls| {
+            calls
+                .borrow_mut()
+                .push(HookCall::ContractUnregistered(*contract))
+        });
+    }
+
+    fn on_reward_paid(
+        account: &AccountId,
+        contract: &MockSmartContract<AccountId>,
+        era: EraIndex,
+        reward: Balance,
+    ) {
+        HOOK_CALLS.with(|calls| {
+            calls
+                .borrow_mut()
+                .push(HookCall::RewardPaid(*account, *contract, era, reward))
+        });
+    }
+
+    fn on_stake_changed_weight() -> Weight {
+        STAKE_CHANGED_HOOK_WEIGHT
+    }
+
+    fn on_contract_unregistered_weight() -> Weight {
+        CONTRACT_UNREGISTERED_HOOK_WEIGHT
+    }
+
+    fn on_reward_paid_weight() -> Weight {
+        REWARD_PAID_HOOK_WEIGHT
+    }
+}
+
+/// Used to take all the recorded hook calls, clearing the record.
+pub(crate) fn take_hook_calls() -> Vec<HookCall> {
+    HOOK_CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+}
+
 pub struct ExternalityBuilder;
 
 impl ExternalityBuilder {
@@ -7,22 +7,26 @@ use frame_support::{
     pallet_prelude::*,
     traits::{
         Currency, ExistenceRequirement, Get, Imbalance, LockIdentifier, LockableCurrency,
-        OnUnbalanced, ReservableCurrency, WithdrawReasons,
+        OnUnbalanced, ReservableCurrency, StorageVersion, WithdrawReasons,
     },
+    transactional,
     weights::Weight,
-    PalletId,
+    BoundedVec, PalletId,
 };
 use frame_system::{ensure_root, ensure_signed, pallet_prelude::*};
 use sp_runtime::{
     traits::{AccountIdConversion, CheckedAdd, Saturating, Zero},
     ArithmeticError, Perbill,
 };
-use sp_std::convert::From;
+use sp_std::convert::{From, TryInto};
 
 const STAKING_ID: LockIdentifier = *b"dapstake";
 
 pub(crate) const REWARD_SCALING: u32 = 2;
 
+/// The current storage version.
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
@@ -33,6 +37,7 @@ pub mod pallet {
 
     #[pallet::pallet]
     #[pallet::generate_store(pub(crate) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     pub struct Pallet<T>(PhantomData<T>);
 
     // Negative imbalance type of this pallet.
@@ -97,6 +102,27 @@ pub mod pallet {
         #[pallet::constant]
         type MinimumRemainingAmount: Get<BalanceOf<Self>>;
 
+        /// Percentage of the era reward which is distributed according to the contract usage.
+        /// Usage part of the reward is paid out to the developer.
+        /// In case no contract was used during an era, entire era reward is distributed according to stake.
+        #[pallet::constant]
+        type UsageRewardPercentage: Get<Perbill>;
+
+        /// Maximum number of contracts that can be staked on with a single `bond_and_stake_multi` call.
+        #[pallet::constant]
+        type MaxStakingTargetsPerCall: Get<u32>;
+
+        /// Maximum number of contracts a single staker can have stake on.
+        #[pallet::constant]
+        type MaxStakedContractsPerStaker: Get<u32>;
+
+        /// Maximum number of contracts kept in the approximate top contracts index.
+        #[pallet::constant]
+        type MaxTopContracts: Get<u32>;
+
+        /// Callbacks invoked whenever staking positions or rewards change.
+        type Hooks: DappsStakingHooks<Self::AccountId, Self::SmartContract, BalanceOf<Self>>;
+
         /// The overarching event type.
         type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 
@@ -110,6 +136,19 @@ pub mod pallet {
     pub(crate) type Ledger<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
 
+    /// Contracts the staker has stake on and the amount staked on each of them.
+    ///
+    /// Amounts include the stake which becomes effective in the next era.
+    #[pallet::storage]
+    #[pallet::getter(fn staker_positions)]
+    pub type StakerPositions<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        BoundedVec<(T::SmartContract, BalanceOf<T>), T::MaxStakedContractsPerStaker>,
+        ValueQuery,
+    >;
+
     /// The current era index.
     #[pallet::storage]
     #[pallet::getter(fn current_era)]
@@ -160,6 +199,50 @@ pub mod pallet {
         EraStakingPoints<T::AccountId, BalanceOf<T>>,
     >;
 
+    /// Number of successful calls made to a contract per era
+    #[pallet::storage]
+    #[pallet::getter(fn contract_era_usage)]
+    pub(crate) type ContractEraUsage<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::SmartContract,
+        Twox64Concat,
+        EraIndex,
+        u32,
+        ValueQuery,
+    >;
+
+    /// Total number of successful calls made to all registered contracts per era
+    #[pallet::storage]
+    #[pallet::getter(fn era_usage)]
+    pub(crate) type EraUsage<T> = StorageMap<_, Twox64Concat, EraIndex, u32, ValueQuery>;
+
+    /// Era in which the call of an account to a contract was last counted as usage
+    #[pallet::storage]
+    #[pallet::getter(fn contract_usage_era)]
+    pub(crate) type ContractUsageCallers<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::SmartContract,
+        Blake2_128Concat,
+        T::AccountId,
+        EraIndex,
+    >;
+
+    /// Approximation of the contracts with the most stake and the amount staked on them, sorted in descending order.
+    ///
+    /// Index is updated whenever stake on a contract changes so the amounts include the stake
+    /// which becomes effective in the next era. Contracts which don't fit into the index are dropped
+    /// and are considered again only once their stake changes, so a contract outside of the index
+    /// can have more stake than the last contract in it. Amounts of the indexed contracts are exact.
+    #[pallet::storage]
+    #[pallet::getter(fn approx_top_contracts)]
+    pub type ApproxTopContracts<T: Config> = StorageValue<
+        _,
+        BoundedVec<(T::SmartContract, BalanceOf<T>), T::MaxTopContracts>,
+        ValueQuery,
+    >;
+
     #[pallet::type_value]
     pub(crate) fn PreApprovalOnEmpty() -> bool {
         false
@@ -176,6 +259,24 @@ pub mod pallet {
     pub(crate) type PreApprovedDevelopers<T: Config> =
         StorageMap<_, Twox64Concat, T::AccountId, (), ValueQuery>;
 
+    /// Enable or disable minting of staking receipts
+    #[pallet::storage]
+    #[pallet::getter(fn staking_receipts_are_enabled)]
+    pub(crate) type StakingReceiptsAreEnabled<T> = StorageValue<_, bool, ValueQuery>;
+
+    /// Staking positions for which a transferable receipt was minted, keyed by the owner of the position
+    #[pallet::storage]
+    #[pallet::getter(fn staking_receipt)]
+    pub(crate) type StakingReceipts<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        T::SmartContract,
+        (),
+        OptionQuery,
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(crate) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -191,6 +292,12 @@ pub mod pallet {
         NewDappStakingEra(EraIndex),
         /// Reward paid to staker or developer.
         Reward(T::AccountId, T::SmartContract, EraIndex, BalanceOf<T>),
+        /// Receipt minted for the staking position of an account on a smart contract.
+        StakingReceiptMinted(T::AccountId, T::SmartContract),
+        /// Receipt and the staking position on a smart contract transferred from one account to another.
+        StakingReceiptTransferred(T::AccountId, T::AccountId, T::SmartContract, BalanceOf<T>),
+        /// Receipt for the staking position of an account on a smart contract burned.
+        StakingReceiptBurned(T::AccountId, T::SmartContract),
     }
 
     #[pallet::error]
@@ -227,6 +334,20 @@ pub mod pallet {
         RequiredContractPreApproval,
         /// Developer's account is already part of pre-approved list
         AlreadyPreApprovedDeveloper,
+        /// Same contract is listed more than once as a staking target
+        DuplicateStakingTarget,
+        /// Free balance isn't enough to stake the requested amount on all targets
+        InsufficientStakingBalance,
+        /// Minting of staking receipts is disabled
+        StakingReceiptsDisabled,
+        /// Receipt was already minted for this staking position
+        AlreadyMintedReceipt,
+        /// Staking position doesn't have a receipt
+        NoStakingReceipt,
+        /// Receipt can't be transferred to its owner
+        ReceiptTransferToSelf,
+        /// Staker can't stake on more contracts
+        TooManyStakedContracts,
     }
 
     #[pallet::hooks]
@@ -256,6 +377,34 @@ pub mod pallet {
 
             T::DbWeight::get().writes(5)
         }
+
+        fn on_runtime_upgrade() -> Weight {
+            if StorageVersion::get::<Pallet<T>>() == 0 {
+                let weight = Self::migrate_staker_positions();
+                STORAGE_VERSION.put::<Pallet<T>>();
+                weight.saturating_add(T::DbWeight::get().reads_writes(1, 1))
+            } else {
+                T::DbWeight::get().reads(1)
+            }
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn pre_upgrade() -> Result<(), &'static str> {
+            if StorageVersion::get::<Pallet<T>>() == 0 {
+                Self::ensure_staker_positions_migratable()
+            } else {
+                Ok(())
+            }
+        }
+
+        #[cfg(feature = "try-runtime")]
+        fn post_upgrade() -> Result<(), &'static str> {
+            ensure!(
+                StorageVersion::get::<Pallet<T>>() == STORAGE_VERSION,
+                "Storage version wasn't updated."
+            );
+            Self::ensure_storage_invariants()
+        }
     }
 
     #[pallet::call]
@@ -304,7 +453,12 @@ pub mod pallet {
         /// This must be called by the developer who registered the contract.
         ///
         /// Warning: After this action contract can not be assigned again.
-        #[pallet::weight(T::WeightInfo::unregister(T::MaxNumberOfStakersPerContract::get()))]
+        #[pallet::weight(
+            T::WeightInfo::unregister(T::MaxNumberOfStakersPerContract::get())
+                .saturating_add(Pallet::<T>::unregister_hooks_weight(
+                    T::MaxNumberOfStakersPerContract::get()
+                ))
+        )]
         pub fn unregister(
             origin: OriginFor<T>,
             contract_id: T::SmartContract,
@@ -321,38 +475,49 @@ pub mod pallet {
                 Error::<T>::NotOwnedContract,
             );
 
-            // We need to unstake all funds that are currently staked
+            // We need to unstake all funds that are currently staked, including the stake
+            // which would become effective in the next era.
             let current_era = Self::current_era();
-            let staking_info = Self::staking_info(&contract_id, current_era);
+            let next_era = current_era + 1;
+            let current_staking_info = Self::staking_info(&contract_id, current_era);
+            let staking_info = Self::staking_info(&contract_id, next_era);
             for (staker, amount) in staking_info.stakers.iter() {
                 let ledger = Self::ledger(staker);
                 Self::update_ledger(staker, ledger.saturating_sub(*amount));
             }
 
             // Need to update total amount staked
-            let staking_total = staking_info.total;
-            EraRewardsAndStakes::<T>::mutate(
-                &current_era,
-                // XXX: RewardsAndStakes should be set by `on_initialize` for each era
-                |value| {
-                    if let Some(x) = value {
-                        x.staked = x.staked.saturating_sub(staking_total)
-                    }
-                },
+            Self::remove_from_era_staked(
+                current_era,
+                current_staking_info.total,
+                staking_info.total,
             );
 
-            // Nett to update staking data for next era
+            // Need to clear staking data for current and next era
             let empty_staking_info = EraStakingPoints::<T::AccountId, BalanceOf<T>>::default();
-            ContractEraStake::<T>::insert(contract_id.clone(), current_era, empty_staking_info);
+            ContractEraStake::<T>::insert(&contract_id, current_era, empty_staking_info.clone());
+            ContractEraStake::<T>::insert(&contract_id, next_era, empty_staking_info);
+            Self::update_approx_top_contracts(&contract_id, Zero::zero());
 
             // Developer account released but contract can not be released more.
             T::Currency::unreserve(&developer, T::RegisterDeposit::get());
             RegisteredDevelopers::<T>::remove(&developer);
 
+            for staker in staking_info.stakers.keys() {
+                Self::remove_staker_position(staker, &contract_id);
+                StakingReceipts::<T>::remove(staker, &contract_id);
+                T::Hooks::on_stake_changed(staker, &contract_id, Zero::zero());
+            }
+            T::Hooks::on_contract_unregistered(&contract_id);
+
             Self::deposit_event(Event::<T>::ContractRemoved(developer, contract_id));
 
-            let number_of_stakers = staking_info.stakers.len();
-            Ok(Some(T::WeightInfo::unregister(number_of_stakers as u32)).into())
+            let number_of_stakers = staking_info.stakers.len() as u32;
+            Ok(Some(
+                T::WeightInfo::unregister(number_of_stakers)
+                    .saturating_add(Self::unregister_hooks_weight(number_of_stakers)),
+            )
+            .into())
         }
 
         /// Lock up and stake balance of the origin account.
@@ -363,8 +528,12 @@ pub mod pallet {
         /// The dispatch origin for this call must be _Signed_ by the staker's account.
         ///
         /// Effects of staking will be felt at the beginning of the next era.
+        /// Stake only earns rewards for eras during which it was held for the entire era.
         ///
-        #[pallet::weight(T::WeightInfo::bond_and_stake())]
+        #[pallet::weight(
+            T::WeightInfo::bond_and_stake()
+                .saturating_add(Pallet::<T>::stake_changed_hooks_weight(1))
+        )]
         pub fn bond_and_stake(
             origin: OriginFor<T>,
             contract_id: T::SmartContract,
@@ -382,68 +551,52 @@ pub mod pallet {
             let mut ledger = Self::ledger(&staker);
 
             // Ensure that staker has enough balance to bond & stake.
-            let free_balance =
-                T::Currency::free_balance(&staker).saturating_sub(T::MinimumRemainingAmount::get());
-
-            // Remove already locked funds from the free balance
-            let available_balance = free_balance.saturating_sub(ledger);
+            let available_balance = Self::available_staking_balance(&staker, ledger);
             let value_to_stake = value.min(available_balance);
             ensure!(
                 value_to_stake > Zero::zero(),
                 Error::<T>::StakingWithNoValue
             );
 
-            // Get the latest era staking point info or create it if contract hasn't been staked yet so far.
+            // Get the next era staking point info or create it if contract hasn't been staked yet so far.
+            // Stake starts counting from the next era.
             let current_era = Self::current_era();
-            let mut staking_info = Self::staking_info(&contract_id, current_era);
-
-            // Ensure that we can add additional staker for the contract.
-            if !staking_info.stakers.contains_key(&staker) {
-                ensure!(
-                    staking_info.stakers.len() < T::MaxNumberOfStakersPerContract::get() as usize,
-                    Error::<T>::MaxNumberOfStakersExceeded,
-                );
-            }
+            let mut staking_info = Self::staking_info(&contract_id, current_era + 1);
+            Self::stake_on_contract(&mut staking_info, &staker, value_to_stake)?;
 
-            // Increment ledger and total staker value for contract. Overflow shouldn't be possible but the check is here just for safety.
+            // Increment ledger. Overflow shouldn't be possible but the check is here just for safety.
             ledger = ledger
                 .checked_add(&value_to_stake)
                 .ok_or(ArithmeticError::Overflow)?;
-            staking_info.total = staking_info
-                .total
-                .checked_add(&value_to_stake)
-                .ok_or(ArithmeticError::Overflow)?;
 
-            // Increment personal staking amount.
-            let entry = staking_info.stakers.entry(staker.clone()).or_default();
-            *entry = entry
-                .checked_add(&value_to_stake)
-                .ok_or(ArithmeticError::Overflow)?;
-
-            ensure!(
-                *entry >= T::MinimumStakingAmount::get(),
-                Error::<T>::InsufficientValue,
-            );
+            // Ensure that staker doesn't exceed the number of contracts it can stake on.
+            let staked_on_contract = Self::staked_amount(&staking_info, &staker);
+            let positions = Self::updated_staker_positions(
+                &staker,
+                &[(contract_id.clone(), staked_on_contract)],
+            )?;
 
-            // Update total staked value in era.
-            EraRewardsAndStakes::<T>::mutate(&current_era, |value| {
-                if let Some(x) = value {
-                    x.staked = x.staked.saturating_add(value_to_stake)
-                }
-            });
+            // Update total staked value in the next era.
+            Self::add_to_next_era_staked(current_era, value_to_stake);
 
             // Update ledger and payee
             Self::update_ledger(&staker, ledger);
+            Self::set_staker_positions(&staker, positions);
 
-            // Update staked information for contract in current era
-            ContractEraStake::<T>::insert(contract_id.clone(), current_era, staking_info);
+            // Update staked information for contract in the next era
+            Self::update_approx_top_contracts(&contract_id, staking_info.total);
+            ContractEraStake::<T>::insert(contract_id.clone(), current_era + 1, staking_info);
+            T::Hooks::on_stake_changed(&staker, &contract_id, staked_on_contract);
 
             Self::deposit_event(Event::<T>::BondAndStake(
                 staker,
                 contract_id,
                 value_to_stake,
             ));
-            Ok(Some(T::WeightInfo::bond_and_stake()).into())
+            Ok(Some(
+                T::WeightInfo::bond_and_stake().saturating_add(Self::stake_changed_hooks_weight(1)),
+            )
+            .into())
         }
 
         /// Unbond, unstake and withdraw balance from the contract.
@@ -453,7 +606,13 @@ pub mod pallet {
         /// In case remaining staked balance on contract is below minimum staking amount,
         /// entire stake for that contract will be unstaked.
         ///
-        #[pallet::weight(T::WeightInfo::unbond_unstake_and_withdraw())]
+        /// Unstaked value doesn't earn rewards for the current era either, unless it was
+        /// staked during the current era and wasn't earning yet.
+        ///
+        #[pallet::weight(
+            T::WeightInfo::unbond_unstake_and_withdraw()
+                .saturating_add(Pallet::<T>::stake_changed_hooks_weight(1))
+        )]
         pub fn unbond_unstake_and_withdraw(
             origin: OriginFor<T>,
             contract_id: T::SmartContract,
@@ -467,42 +626,19 @@ pub mod pallet {
                 Error::<T>::NotOperatedContract,
             );
 
-            // Get the latest era staking points for the contract.
+            // Update the staking points of the current and the next era.
             let current_era = Self::current_era();
-            let mut staking_info = Self::staking_info(&contract_id, current_era);
-
-            ensure!(
-                staking_info.stakers.contains_key(&staker),
-                Error::<T>::NotStakedContract,
-            );
-            let staked_value = staking_info.stakers[&staker];
-
-            ensure!(value <= staked_value, Error::<T>::InsufficientValue);
-
-            // Calculate the value which will be unstaked.
-            let remaining = staked_value.saturating_sub(value);
-            let value_to_unstake = if remaining < T::MinimumStakingAmount::get() {
-                staking_info.stakers.remove(&staker);
-                staked_value
-            } else {
-                staking_info.stakers.insert(staker.clone(), remaining);
-                value
-            };
+            let (value_to_unstake, current_era_unstaked, staked_on_contract) =
+                Self::unstake_position(&contract_id, &staker, value, current_era)?;
 
             // Get the staking ledger and update it
             let ledger = Self::ledger(&staker);
             Self::update_ledger(&staker, ledger.saturating_sub(value_to_unstake));
 
-            // Update total staked value in era.
-            EraRewardsAndStakes::<T>::mutate(&current_era, |value| {
-                if let Some(x) = value {
-                    x.staked = x.staked.saturating_sub(value_to_unstake)
-                }
-            });
+            // Update total staked value in the current and the next era.
+            Self::remove_from_era_staked(current_era, current_era_unstaked, value_to_unstake);
 
-            // Update the era staking points
-            staking_info.total = staking_info.total.saturating_sub(value_to_unstake);
-            ContractEraStake::<T>::insert(contract_id.clone(), current_era, staking_info);
+            T::Hooks::on_stake_changed(&staker, &contract_id, staked_on_contract);
 
             Self::deposit_event(Event::<T>::UnbondUnstakeAndWithdraw(
                 staker,
@@ -510,7 +646,163 @@ pub mod pallet {
                 value_to_unstake,
             ));
 
-            Ok(Some(T::WeightInfo::unbond_unstake_and_withdraw()).into())
+            Ok(Some(
+                T::WeightInfo::unbond_unstake_and_withdraw()
+                    .saturating_add(Self::stake_changed_hooks_weight(1)),
+            )
+            .into())
+        }
+
+        /// Lock up and stake balance of the origin account on multiple contracts at once.
+        ///
+        /// Each target is checked in the same way as in `bond_and_stake`, and the sum of all
+        /// values must be covered by the staker's available balance. Unlike `bond_and_stake`,
+        /// values are not capped to the available balance; the entire call fails instead.
+        ///
+        /// The dispatch origin for this call must be _Signed_ by the staker's account.
+        ///
+        /// Effects of staking will be felt at the beginning of the next era.
+        ///
+        #[pallet::weight(
+            T::WeightInfo::bond_and_stake_multi(targets.len() as u32)
+                .saturating_add(Pallet::<T>::stake_changed_hooks_weight(targets.len() as u32))
+        )]
+        pub fn bond_and_stake_multi(
+            origin: OriginFor<T>,
+            targets: BoundedVec<(T::SmartContract, BalanceOf<T>), T::MaxStakingTargetsPerCall>,
+        ) -> DispatchResultWithPostInfo {
+            let staker = ensure_signed(origin)?;
+            ensure!(!targets.is_empty(), Error::<T>::StakingWithNoValue);
+
+            let current_era = Self::current_era();
+            let mut ledger = Self::ledger(&staker);
+
+            // Check all targets and prepare their staking points before anything is written.
+            let mut total_value_to_stake: BalanceOf<T> = Zero::zero();
+            let mut staking_infos = Vec::with_capacity(targets.len());
+            for (contract_id, value) in targets.iter() {
+                ensure!(
+                    Self::is_active(contract_id),
+                    Error::<T>::NotOperatedContract
+                );
+                ensure!(*value > Zero::zero(), Error::<T>::StakingWithNoValue);
+                ensure!(
+                    !staking_infos
+                        .iter()
+                        .any(|(target, _, _)| target == contract_id),
+                    Error::<T>::DuplicateStakingTarget,
+                );
+
+                let mut staking_info = Self::staking_info(contract_id, current_era + 1);
+                Self::stake_on_contract(&mut staking_info, &staker, *value)?;
+
+                total_value_to_stake = total_value_to_stake
+                    .checked_add(value)
+                    .ok_or(ArithmeticError::Overflow)?;
+                staking_infos.push((contract_id.clone(), *value, staking_info));
+            }
+
+            // Ensure that staker can cover the stake on all the targets.
+            ensure!(
+                total_value_to_stake <= Self::available_staking_balance(&staker, ledger),
+                Error::<T>::InsufficientStakingBalance,
+            );
+            ledger = ledger
+                .checked_add(&total_value_to_stake)
+                .ok_or(ArithmeticError::Overflow)?;
+
+            // Ensure that staker doesn't exceed the number of contracts it can stake on.
+            let updated_positions: Vec<_> = staking_infos
+                .iter()
+                .map(|(contract_id, _, staking_info)| {
+                    (
+                        contract_id.clone(),
+                        Self::staked_amount(staking_info, &staker),
+                    )
+                })
+                .collect();
+            let positions = Self::updated_staker_positions(&staker, &updated_positions)?;
+
+            // Update total staked value in the next era.
+            Self::add_to_next_era_staked(current_era, total_value_to_stake);
+
+            Self::update_ledger(&staker, ledger);
+            Self::set_staker_positions(&staker, positions);
+
+            let number_of_targets = staking_infos.len() as u32;
+            for (contract_id, value, staking_info) in staking_infos {
+                let staked_on_contract = Self::staked_amount(&staking_info, &staker);
+                Self::update_approx_top_contracts(&contract_id, staking_info.total);
+                ContractEraStake::<T>::insert(contract_id.clone(), current_era + 1, staking_info);
+                T::Hooks::on_stake_changed(&staker, &contract_id, staked_on_contract);
+                Self::deposit_event(Event::<T>::BondAndStake(staker.clone(), contract_id, value));
+            }
+
+            Ok(Some(
+                T::WeightInfo::bond_and_stake_multi(number_of_targets)
+                    .saturating_add(Self::stake_changed_hooks_weight(number_of_targets)),
+            )
+            .into())
+        }
+
+        /// Unbond, unstake and withdraw the entire stake of the origin account from all
+        /// the active contracts it has staked on.
+        ///
+        /// At most `MaxStakingTargetsPerCall` contracts are unstaked per call. In case staker
+        /// has more positions than that, the call can be repeated.
+        /// In case any of the positions can't be unstaked, none of them is.
+        ///
+        /// The dispatch origin for this call must be _Signed_ by the staker's account.
+        ///
+        #[pallet::weight(
+            T::WeightInfo::unbond_all(T::MaxStakingTargetsPerCall::get())
+                .saturating_add(Pallet::<T>::stake_changed_hooks_weight(
+                    T::MaxStakingTargetsPerCall::get()
+                ))
+        )]
+        #[transactional]
+        pub fn unbond_all(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            let staker = ensure_signed(origin)?;
+
+            let current_era = Self::current_era();
+            let max_positions = T::MaxStakingTargetsPerCall::get() as usize;
+
+            // Positions of unregistered contracts are removed from the index so all of them are active.
+            let positions: Vec<_> = Self::staker_positions(&staker)
+                .into_iter()
+                .take(max_positions)
+                .collect();
+            ensure!(!positions.is_empty(), Error::<T>::NotStakedContract);
+
+            let mut total_unstaked: BalanceOf<T> = Zero::zero();
+            let mut total_current_era_unstaked: BalanceOf<T> = Zero::zero();
+            let number_of_positions = positions.len() as u32;
+            for (contract_id, staked_value) in positions {
+                let (unstaked, current_era_unstaked, _) =
+                    Self::unstake_position(&contract_id, &staker, staked_value, current_era)?;
+                total_unstaked = total_unstaked.saturating_add(unstaked);
+                total_current_era_unstaked =
+                    total_current_era_unstaked.saturating_add(current_era_unstaked);
+
+                T::Hooks::on_stake_changed(&staker, &contract_id, Zero::zero());
+                Self::deposit_event(Event::<T>::UnbondUnstakeAndWithdraw(
+                    staker.clone(),
+                    contract_id,
+                    unstaked,
+                ));
+            }
+
+            let ledger = Self::ledger(&staker);
+            Self::update_ledger(&staker, ledger.saturating_sub(total_unstaked));
+
+            // Update total staked value in the current and the next era.
+            Self::remove_from_era_staked(current_era, total_current_era_unstaked, total_unstaked);
+
+            Ok(Some(
+                T::WeightInfo::unbond_all(number_of_positions)
+                    .saturating_add(Self::stake_changed_hooks_weight(number_of_positions)),
+            )
+            .into())
         }
 
         /// claim the rewards earned by contract_id.
@@ -518,7 +810,12 @@ pub mod pallet {
         /// claim is valid for all unclaimed eras but not longer than history_depth().
         /// Any reward older than history_depth() will go to Treasury.
         /// Any user can call this function.
-        #[pallet::weight(T::WeightInfo::claim(T::MaxNumberOfStakersPerContract::get() + 1))]
+        #[pallet::weight(
+            T::WeightInfo::claim(T::MaxNumberOfStakersPerContract::get() + 1)
+                .saturating_add(Pallet::<T>::reward_paid_hooks_weight(
+                    T::MaxNumberOfStakersPerContract::get() + 1
+                ))
+        )]
         pub fn claim(
             origin: OriginFor<T>,
             contract_id: T::SmartContract,
@@ -544,22 +841,33 @@ pub mod pallet {
                 Error::<T>::AlreadyClaimedInThisEra,
             );
 
-            ensure!(!staking_info.stakers.is_empty(), Error::<T>::NotStaked,);
+            // Usage reward belongs to the developer so it can be claimed even if contract had no stakers.
+            ensure!(
+                !staking_info.stakers.is_empty()
+                    || !Self::contract_era_usage(&contract_id, era).is_zero(),
+                Error::<T>::NotStaked,
+            );
 
             let reward_and_stake =
                 Self::era_reward_and_stake(era).ok_or(Error::<T>::UnknownEraReward)?;
 
             // Calculate the contract reward for this era.
-            let reward_ratio = Perbill::from_rational(staking_info.total, reward_and_stake.staked);
-            let contract_reward = if era < T::BonusEraDuration::get() {
+            let era_reward = if era < T::BonusEraDuration::get() {
                 // Double reward as a bonus.
-                reward_ratio
-                    * reward_and_stake
-                        .rewards
-                        .saturating_mul(REWARD_SCALING.into())
+                reward_and_stake
+                    .rewards
+                    .saturating_mul(REWARD_SCALING.into())
             } else {
-                reward_ratio * reward_and_stake.rewards
+                reward_and_stake.rewards
             };
+            let (stake_reward, usage_reward) = Self::contract_stake_and_usage_reward(
+                &contract_id,
+                era,
+                &staking_info,
+                &reward_and_stake,
+                era_reward,
+            );
+            let contract_reward = stake_reward.saturating_add(usage_reward);
 
             // Withdraw reward funds from the dapps staking
             let reward_pool = T::Currency::withdraw(
@@ -569,17 +877,21 @@ pub mod pallet {
                 ExistenceRequirement::AllowDeath,
             )?;
 
-            // Divide reward between stakers and the developer of the contract
-            let (developer_reward, mut stakers_reward) =
-                reward_pool.split(T::DeveloperRewardPercentage::get() * contract_reward);
+            // Divide reward between stakers and the developer of the contract.
+            // Usage part of the reward belongs to the developer.
+            let (developer_reward, mut stakers_reward) = reward_pool.split(
+                (T::DeveloperRewardPercentage::get() * stake_reward).saturating_add(usage_reward),
+            );
 
+            let developer_reward_value = developer_reward.peek();
             Self::deposit_event(Event::<T>::Reward(
                 developer.clone(),
                 contract_id.clone(),
                 era,
-                developer_reward.peek(),
+                developer_reward_value,
             ));
             T::Currency::resolve_creating(&developer, developer_reward);
+            T::Hooks::on_reward_paid(&developer, &contract_id, era, developer_reward_value);
 
             // Calculate & pay rewards for all stakers
             let stakers_total_reward = stakers_reward.peek();
@@ -589,22 +901,28 @@ pub mod pallet {
                     stakers_reward.split(ratio * stakers_total_reward);
                 stakers_reward = new_stakers_reward;
 
+                let reward_value = reward.peek();
                 Self::deposit_event(Event::<T>::Reward(
                     staker.clone(),
                     contract_id.clone(),
                     era,
-                    reward.peek(),
+                    reward_value,
                 ));
                 T::Currency::resolve_creating(staker, reward);
+                T::Hooks::on_reward_paid(staker, &contract_id, era, reward_value);
             }
 
-            let number_of_payees = staking_info.stakers.len() + 1;
+            let number_of_payees = staking_info.stakers.len() as u32 + 1;
 
             // updated counter for total rewards paid to the contract
             staking_info.claimed_rewards = contract_reward;
             <ContractEraStake<T>>::insert(&contract_id, era, staking_info);
 
-            Ok(Some(T::WeightInfo::claim(number_of_payees as u32)).into())
+            Ok(Some(
+                T::WeightInfo::claim(number_of_payees)
+                    .saturating_add(Self::reward_paid_hooks_weight(number_of_payees)),
+            )
+            .into())
         }
 
         /// Force there to be a new era at the end of the next block. After this, it will be
@@ -657,9 +975,177 @@ pub mod pallet {
             PreApprovalIsEnabled::<T>::put(enabled);
             Ok(().into())
         }
+
+        /// Enable or disable minting of staking receipts.
+        ///
+        /// Already minted receipts can still be transferred and burned while minting is disabled.
+        #[pallet::weight(T::WeightInfo::enable_staking_receipts())]
+        pub fn enable_staking_receipts(
+            origin: OriginFor<T>,
+            enabled: bool,
+        ) -> DispatchResultWithPostInfo {
+            ensure_root(origin)?;
+            StakingReceiptsAreEnabled::<T>::put(enabled);
+            Ok(().into())
+        }
+
+        /// Mint a transferable receipt for the staking position of the origin account on the contract.
+        ///
+        /// Receipt is burned automatically once the position is fully unstaked.
+        ///
+        /// The dispatch origin for this call must be _Signed_ by the staker's account.
+        ///
+        #[pallet::weight(T::WeightInfo::mint_staking_receipt())]
+        pub fn mint_staking_receipt(
+            origin: OriginFor<T>,
+            contract_id: T::SmartContract,
+        ) -> DispatchResultWithPostInfo {
+            let staker = ensure_signed(origin)?;
+
+            ensure!(
+                Self::staking_receipts_are_enabled(),
+                Error::<T>::StakingReceiptsDisabled
+            );
+            ensure!(
+                Self::is_active(&contract_id),
+                Error::<T>::NotOperatedContract
+            );
+            ensure!(
+                !StakingReceipts::<T>::contains_key(&staker, &contract_id),
+                Error::<T>::AlreadyMintedReceipt
+            );
+
+            let staking_info = Self::staking_info(&contract_id, Self::current_era() + 1);
+            ensure!(
+                staking_info.stakers.contains_key(&staker),
+                Error::<T>::NotStakedContract
+            );
+
+            StakingReceipts::<T>::insert(&staker, &contract_id, ());
+
+            Self::deposit_event(Event::<T>::StakingReceiptMinted(staker, contract_id));
+
+            Ok(().into())
+        }
+
+        /// Transfer the staking receipt and with it the staking position on the contract to `dest`.
+        ///
+        /// Staked funds are transferred to `dest` and locked there. Position is moved starting with
+        /// the current era so unclaimed rewards of the previous eras still belong to the origin account.
+        /// In case `dest` already has a position on the contract, the two positions are merged.
+        ///
+        /// The dispatch origin for this call must be _Signed_ by the owner of the receipt.
+        ///
+        #[pallet::weight(
+            T::WeightInfo::transfer_staking_receipt()
+                .saturating_add(Pallet::<T>::stake_changed_hooks_weight(2))
+        )]
+        #[transactional]
+        pub fn transfer_staking_receipt(
+            origin: OriginFor<T>,
+            contract_id: T::SmartContract,
+            dest: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            let owner = ensure_signed(origin)?;
+
+            ensure!(owner != dest, Error::<T>::ReceiptTransferToSelf);
+            ensure!(
+                StakingReceipts::<T>::contains_key(&owner, &contract_id),
+                Error::<T>::NoStakingReceipt
+            );
+            ensure!(
+                Self::is_active(&contract_id),
+                Error::<T>::NotOperatedContract
+            );
+
+            let current_era = Self::current_era();
+            let mut next_staking_info = Self::staking_info(&contract_id, current_era + 1);
+            let value = Self::staked_amount(&next_staking_info, &owner);
+            ensure!(!value.is_zero(), Error::<T>::NotStakedContract);
+
+            let dest_ledger = Self::ledger(&dest)
+                .checked_add(&value)
+                .ok_or(ArithmeticError::Overflow)?;
+
+            // Ensure that receiver doesn't exceed the number of contracts it can stake on.
+            let staked_by_dest =
+                Self::staked_amount(&next_staking_info, &dest).saturating_add(value);
+            let dest_positions =
+                Self::updated_staker_positions(&dest, &[(contract_id.clone(), staked_by_dest)])?;
+
+            // Funds need to be unlocked before they can be moved to the new owner.
+            Self::update_ledger(&owner, Self::ledger(&owner).saturating_sub(value));
+            T::Currency::transfer(&owner, &dest, value, ExistenceRequirement::KeepAlive)?;
+            Self::update_ledger(&dest, dest_ledger);
+
+            let mut current_staking_info = Self::staking_info(&contract_id, current_era);
+            if Self::move_position(&mut current_staking_info, &owner, &dest) {
+                ContractEraStake::<T>::insert(&contract_id, current_era, current_staking_info);
+            }
+            Self::move_position(&mut next_staking_info, &owner, &dest);
+            ContractEraStake::<T>::insert(&contract_id, current_era + 1, next_staking_info);
+
+            Self::remove_staker_position(&owner, &contract_id);
+            Self::set_staker_positions(&dest, dest_positions);
+
+            StakingReceipts::<T>::remove(&owner, &contract_id);
+            StakingReceipts::<T>::insert(&dest, &contract_id, ());
+
+            T::Hooks::on_stake_changed(&owner, &contract_id, Zero::zero());
+            T::Hooks::on_stake_changed(&dest, &contract_id, staked_by_dest);
+
+            Self::deposit_event(Event::<T>::StakingReceiptTransferred(
+                owner,
+                dest,
+                contract_id,
+                value,
+            ));
+
+            Ok(().into())
+        }
+
+        /// Burn the staking receipt of the origin account for the contract.
+        ///
+        /// Staking position itself remains unchanged but it can no longer be transferred.
+        ///
+        /// The dispatch origin for this call must be _Signed_ by the owner of the receipt.
+        ///
+        #[pallet::weight(T::WeightInfo::burn_staking_receipt())]
+        pub fn burn_staking_receipt(
+            origin: OriginFor<T>,
+            contract_id: T::SmartContract,
+        ) -> DispatchResultWithPostInfo {
+            let staker = ensure_signed(origin)?;
+
+            ensure!(
+                StakingReceipts::<T>::contains_key(&staker, &contract_id),
+                Error::<T>::NoStakingReceipt
+            );
+            StakingReceipts::<T>::remove(&staker, &contract_id);
+
+            Self::deposit_event(Event::<T>::StakingReceiptBurned(staker, contract_id));
+
+            Ok(().into())
+        }
     }
 
     impl<T: Config> Pallet<T> {
+        /// Weight of `on_stake_changed` hooks invoked for the given number of staking positions.
+        fn stake_changed_hooks_weight(positions: u32) -> Weight {
+            T::Hooks::on_stake_changed_weight().saturating_mul(positions.into())
+        }
+
+        /// Weight of the hooks invoked when a contract with the given number of stakers is unregistered.
+        fn unregister_hooks_weight(stakers: u32) -> Weight {
+            Self::stake_changed_hooks_weight(stakers)
+                .saturating_add(T::Hooks::on_contract_unregistered_weight())
+        }
+
+        /// Weight of `on_reward_paid` hooks invoked for the given number of payees.
+        fn reward_paid_hooks_weight(payees: u32) -> Weight {
+            T::Hooks::on_reward_paid_weight().saturating_mul(payees.into())
+        }
+
         /// Get AccountId assigned to the pallet.
         fn account_id() -> T::AccountId {
             T::PalletId::get().into_account()
@@ -677,6 +1163,80 @@ pub mod pallet {
             }
         }
 
+        /// Returns the amount staker can additionally bond & stake.
+        ///
+        /// This is the free balance reduced by the minimum remaining amount and the already locked funds.
+        fn available_staking_balance(staker: &T::AccountId, ledger: BalanceOf<T>) -> BalanceOf<T> {
+            T::Currency::free_balance(staker)
+                .saturating_sub(T::MinimumRemainingAmount::get())
+                .saturating_sub(ledger)
+        }
+
+        /// Adds `value` to the staker's stake in the given staking points.
+        ///
+        /// Ensures that the maximum number of stakers isn't exceeded and that the resulting
+        /// staked amount isn't below the minimum staking amount.
+        fn stake_on_contract(
+            staking_info: &mut EraStakingPoints<T::AccountId, BalanceOf<T>>,
+            staker: &T::AccountId,
+            value: BalanceOf<T>,
+        ) -> DispatchResult {
+            // Ensure that we can add additional staker for the contract.
+            if !staking_info.stakers.contains_key(staker) {
+                ensure!(
+                    staking_info.stakers.len() < T::MaxNumberOfStakersPerContract::get() as usize,
+                    Error::<T>::MaxNumberOfStakersExceeded,
+                );
+            }
+
+            // Increment total staker value for contract. Overflow shouldn't be possible but the check is here just for safety.
+            staking_info.total = staking_info
+                .total
+                .checked_add(&value)
+                .ok_or(ArithmeticError::Overflow)?;
+
+            // Increment personal staking amount.
+            let entry = staking_info.stakers.entry(staker.clone()).or_default();
+            *entry = entry.checked_add(&value).ok_or(ArithmeticError::Overflow)?;
+
+            ensure!(
+                *entry >= T::MinimumStakingAmount::get(),
+                Error::<T>::InsufficientValue,
+            );
+
+            Ok(())
+        }
+
+        /// Removes up to `value` from the staker's stake in the given staking points.
+        ///
+        /// In case remaining staked amount would be below the minimum staking amount,
+        /// the entire stake is removed. Returns the amount that was actually unstaked.
+        fn unstake_from_contract(
+            staking_info: &mut EraStakingPoints<T::AccountId, BalanceOf<T>>,
+            staker: &T::AccountId,
+            value: BalanceOf<T>,
+        ) -> Result<BalanceOf<T>, DispatchError> {
+            let staked_value = *staking_info
+                .stakers
+                .get(staker)
+                .ok_or(Error::<T>::NotStakedContract)?;
+
+            ensure!(value <= staked_value, Error::<T>::InsufficientValue);
+
+            // Calculate the value which will be unstaked.
+            let remaining = staked_value.saturating_sub(value);
+            let value_to_unstake = if remaining < T::MinimumStakingAmount::get() {
+                staking_info.stakers.remove(staker);
+                staked_value
+            } else {
+                staking_info.stakers.insert(staker.clone(), remaining);
+                value
+            };
+            staking_info.total = staking_info.total.saturating_sub(value_to_unstake);
+
+            Ok(value_to_unstake)
+        }
+
         /// The block rewards are accumulated on the pallets's account during an era.
         /// This function takes a snapshot of the pallet's balance accrued during current era
         /// and stores it for future distribution
@@ -686,14 +1246,8 @@ pub mod pallet {
             // Get the reward and stake information for previous era
             let mut reward_and_stake = Self::era_reward_and_stake(era).unwrap_or_default();
 
-            // Prepare info for the next era
-            EraRewardsAndStakes::<T>::insert(
-                era + 1,
-                EraRewardAndStake {
-                    rewards: Zero::zero(),
-                    staked: reward_and_stake.staked.clone(),
-                },
-            );
+            // Prepare info for the next era. Stake added during this era is already accounted for.
+            EraRewardsAndStakes::<T>::insert(era + 1, Self::next_era_reward_and_stake(era));
 
             // Set the reward for the previous era.
             reward_and_stake.rewards = reward;
@@ -722,6 +1276,356 @@ pub mod pallet {
             }
         }
 
+        /// Returns the reward and stake record for the era following `current_era`.
+        ///
+        /// In case it doesn't exist yet, it's derived from the `current_era` record.
+        fn next_era_reward_and_stake(current_era: EraIndex) -> EraRewardAndStake<BalanceOf<T>> {
+            Self::era_reward_and_stake(current_era + 1).unwrap_or_else(|| EraRewardAndStake {
+                rewards: Zero::zero(),
+                staked: Self::era_reward_and_stake(current_era)
+                    .map(|x| x.staked)
+                    .unwrap_or_else(Zero::zero),
+            })
+        }
+
+        /// Adds `value` to the total staked value of the era following `current_era`.
+        fn add_to_next_era_staked(current_era: EraIndex, value: BalanceOf<T>) {
+            let mut next_era_reward_and_stake = Self::next_era_reward_and_stake(current_era);
+            next_era_reward_and_stake.staked =
+                next_era_reward_and_stake.staked.saturating_add(value);
+            EraRewardsAndStakes::<T>::insert(current_era + 1, next_era_reward_and_stake);
+        }
+
+        /// Removes the given values from the total staked value of `current_era` and the era following it.
+        fn remove_from_era_staked(
+            current_era: EraIndex,
+            current_era_value: BalanceOf<T>,
+            next_era_value: BalanceOf<T>,
+        ) {
+            // Next era record must be read before the current era is updated since it might be derived from it.
+            let mut next_era_reward_and_stake = Self::next_era_reward_and_stake(current_era);
+            next_era_reward_and_stake.staked = next_era_reward_and_stake
+                .staked
+                .saturating_sub(next_era_value);
+            EraRewardsAndStakes::<T>::insert(current_era + 1, next_era_reward_and_stake);
+
+            EraRewardsAndStakes::<T>::mutate(&current_era, |value| {
+                if let Some(x) = value {
+                    x.staked = x.staked.saturating_sub(current_era_value)
+                }
+            });
+        }
+
+        /// Unstakes up to `value` from the staker's position on the contract.
+        ///
+        /// Position is reduced in the next era first. Stake of the current era is reduced only by the amount which
+        /// exceeds the remaining position since stake which isn't held for the entire era mustn't earn rewards.
+        ///
+        /// Returns the unstaked value, the value unstaked from the current era and the remaining position.
+        fn unstake_position(
+            contract_id: &T::SmartContract,
+            staker: &T::AccountId,
+            value: BalanceOf<T>,
+            current_era: EraIndex,
+        ) -> Result<(BalanceOf<T>, BalanceOf<T>, BalanceOf<T>), DispatchError> {
+            let mut next_staking_info = Self::staking_info(contract_id, current_era + 1);
+            let value_to_unstake =
+                Self::unstake_from_contract(&mut next_staking_info, staker, value)?;
+            let remaining = Self::staked_amount(&next_staking_info, staker);
+            let positions =
+                Self::updated_staker_positions(staker, &[(contract_id.clone(), remaining)])?;
+            Self::set_staker_positions(staker, positions);
+
+            let mut current_staking_info = Self::staking_info(contract_id, current_era);
+            let current_staked = Self::staked_amount(&current_staking_info, staker);
+            let current_era_unstaked = current_staked.saturating_sub(remaining);
+            if !current_era_unstaked.is_zero() {
+                if remaining.is_zero() {
+                    current_staking_info.stakers.remove(staker);
+                } else {
+                    current_staking_info
+                        .stakers
+                        .insert(staker.clone(), remaining);
+                }
+                current_staking_info.total = current_staking_info
+                    .total
+                    .saturating_sub(current_era_unstaked);
+                ContractEraStake::<T>::insert(contract_id, current_era, current_staking_info);
+            }
+            Self::update_approx_top_contracts(contract_id, next_staking_info.total);
+            ContractEraStake::<T>::insert(contract_id, current_era + 1, next_staking_info);
+
+            // Receipt has nothing to represent once the position is gone.
+            if remaining.is_zero() {
+                StakingReceipts::<T>::remove(staker, contract_id);
+            }
+
+            Ok((value_to_unstake, current_era_unstaked, remaining))
+        }
+
+        /// Returns the staker's positions index with the given stakes on contracts applied.
+        ///
+        /// Contracts with zero stake are removed from the index.
+        /// Fails if the staker would have stake on more than `MaxStakedContractsPerStaker` contracts.
+        fn updated_staker_positions(
+            staker: &T::AccountId,
+            updates: &[(T::SmartContract, BalanceOf<T>)],
+        ) -> Result<
+            BoundedVec<(T::SmartContract, BalanceOf<T>), T::MaxStakedContractsPerStaker>,
+            DispatchError,
+        > {
+            let mut positions: Vec<_> = Self::staker_positions(staker).into();
+            for (contract_id, staked) in updates {
+                match positions
+                    .iter_mut()
+                    .find(|(contract, _)| contract == contract_id)
+                {
+                    Some(position) => position.1 = *staked,
+                    None => positions.push((contract_id.clone(), *staked)),
+                }
+            }
+            positions.retain(|(_, staked)| !staked.is_zero());
+
+            positions
+                .try_into()
+                .map_err(|_| Error::<T>::TooManyStakedContracts.into())
+        }
+
+        /// Stores the staker's positions index, removing it if there are no positions.
+        fn set_staker_positions(
+            staker: &T::AccountId,
+            positions: BoundedVec<(T::SmartContract, BalanceOf<T>), T::MaxStakedContractsPerStaker>,
+        ) {
+            if positions.is_empty() {
+                StakerPositions::<T>::remove(staker);
+            } else {
+                StakerPositions::<T>::insert(staker, positions);
+            }
+        }
+
+        /// Removes the contract from the staker's positions index.
+        fn remove_staker_position(staker: &T::AccountId, contract_id: &T::SmartContract) {
+            // Removing a position can't exceed the bound.
+            if let Ok(positions) =
+                Self::updated_staker_positions(staker, &[(contract_id.clone(), Zero::zero())])
+            {
+                Self::set_staker_positions(staker, positions);
+            }
+        }
+
+        /// Collects the next era stake of all active contracts per staker.
+        ///
+        /// Returns the positions together with the number of storage reads needed to collect them.
+        fn collect_staker_positions() -> (
+            BTreeMap<T::AccountId, Vec<(T::SmartContract, BalanceOf<T>)>>,
+            Weight,
+        ) {
+            let next_era = Self::current_era() + 1;
+            let mut positions: BTreeMap<T::AccountId, Vec<(T::SmartContract, BalanceOf<T>)>> =
+                BTreeMap::new();
+            let mut reads: Weight = 1;
+
+            for contract_id in RegisteredDapps::<T>::iter_keys() {
+                reads = reads.saturating_add(3);
+                if !Self::is_active(&contract_id) {
+                    continue;
+                }
+
+                // Every stored era of the contract is read to find the latest one.
+                let mut latest_staking_info = None;
+                for (era, staking_info) in ContractEraStake::<T>::iter_prefix(&contract_id) {
+                    reads = reads.saturating_add(1);
+                    let is_latest = match &latest_staking_info {
+                        Some((latest_era, _)) => era > *latest_era,
+                        None => true,
+                    };
+                    if era <= next_era && is_latest {
+                        latest_staking_info = Some((era, staking_info));
+                    }
+                }
+
+                if let Some((_, staking_info)) = latest_staking_info {
+                    for (staker, staked) in staking_info.stakers.into_iter() {
+                        positions
+                            .entry(staker)
+                            .or_insert_with(Vec::new)
+                            .push((contract_id.clone(), staked));
+                    }
+                }
+            }
+
+            (positions, reads)
+        }
+
+        /// Builds the staker positions index from the stake of all active contracts.
+        ///
+        /// Stakers with stake on more than `MaxStakedContractsPerStaker` contracts can't be indexed
+        /// and are left out of the index. `pre_upgrade` fails in case there are any.
+        fn migrate_staker_positions() -> Weight {
+            let (positions, reads) = Self::collect_staker_positions();
+
+            let mut writes: Weight = 0;
+            for (staker, staker_positions) in positions.into_iter() {
+                let staker_positions: Result<BoundedVec<_, T::MaxStakedContractsPerStaker>, _> =
+                    staker_positions.try_into();
+                match staker_positions {
+                    Ok(staker_positions) => {
+                        StakerPositions::<T>::insert(staker, staker_positions);
+                        writes = writes.saturating_add(1);
+                    }
+                    Err(_) => frame_support::log::error!(
+                        target: "dapps-staking",
+                        "Staker {:?} has stake on more contracts than can be indexed.",
+                        staker,
+                    ),
+                }
+            }
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        /// Checks that the staker positions index can be built in a single block and
+        /// that no staker has stake on more contracts than the index can hold.
+        ///
+        /// Meant to be used by `try-runtime` and tests since it iterates over entire storage maps.
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn ensure_staker_positions_migratable() -> Result<(), &'static str> {
+            let (positions, reads) = Self::collect_staker_positions();
+
+            let max_staked_contracts = positions
+                .values()
+                .map(|staker_positions| staker_positions.len())
+                .max()
+                .unwrap_or_default();
+            ensure!(
+                max_staked_contracts <= T::MaxStakedContractsPerStaker::get() as usize,
+                "Staker has stake on more contracts than MaxStakedContractsPerStaker."
+            );
+
+            let weight = T::DbWeight::get().reads_writes(reads, positions.len() as Weight);
+            ensure!(
+                weight <= T::BlockWeights::get().max_block,
+                "Staker positions migration doesn't fit into a block."
+            );
+
+            Ok(())
+        }
+
+        /// Moves the entire stake of `from` in the staking points to `to`, merging it with the existing stake of `to`.
+        ///
+        /// Returns `true` if staking points were changed.
+        fn move_position(
+            staking_info: &mut EraStakingPoints<T::AccountId, BalanceOf<T>>,
+            from: &T::AccountId,
+            to: &T::AccountId,
+        ) -> bool {
+            match staking_info.stakers.remove(from) {
+                Some(value) => {
+                    let staked = staking_info
+                        .stakers
+                        .entry(to.clone())
+                        .or_insert_with(Zero::zero);
+                    *staked = staked.saturating_add(value);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Updates the position of the contract in the approximate top contracts index.
+        ///
+        /// Contract is removed from the index if `total_staked` is zero.
+        /// Contracts which don't fit into the index are dropped and are considered again once their stake changes.
+        /// Contracts outside of the index aren't known, so none of them is promoted when an indexed contract loses stake.
+        fn update_approx_top_contracts(contract_id: &T::SmartContract, total_staked: BalanceOf<T>) {
+            let mut top_contracts: Vec<_> = Self::approx_top_contracts().into();
+            top_contracts.retain(|(contract, _)| contract != contract_id);
+
+            if !total_staked.is_zero() {
+                // Contracts which reached the same stake earlier keep their position.
+                let index = top_contracts
+                    .iter()
+                    .position(|(_, staked)| *staked < total_staked)
+                    .unwrap_or_else(|| top_contracts.len());
+                top_contracts.insert(index, (contract_id.clone(), total_staked));
+            }
+            top_contracts.truncate(T::MaxTopContracts::get() as usize);
+
+            // Conversion can't fail since the index was truncated to the maximum length.
+            let top_contracts: BoundedVec<_, T::MaxTopContracts> =
+                top_contracts.try_into().unwrap_or_default();
+            ApproxTopContracts::<T>::put(top_contracts);
+        }
+
+        /// Notes a successful call made by `caller` to the contract in the current era.
+        ///
+        /// Each caller is counted at most once per contract and era, so repeating calls from a single
+        /// account doesn't increase the usage share of the contract. Calls to contracts which aren't active are ignored.
+        ///
+        /// Runtime is expected to call this for each transaction targeting a contract which executed successfully.
+        /// Weight of the call is registered as extra weight of the block.
+        pub fn note_contract_usage(contract_id: &T::SmartContract, caller: &T::AccountId) {
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                T::WeightInfo::note_contract_usage(),
+                DispatchClass::Normal,
+            );
+
+            if !Self::is_active(contract_id) {
+                return;
+            }
+
+            let current_era = Self::current_era();
+            if Self::contract_usage_era(contract_id, caller) == Some(current_era) {
+                return;
+            }
+            ContractUsageCallers::<T>::insert(contract_id, caller, current_era);
+            ContractEraUsage::<T>::mutate(contract_id, current_era, |usage| {
+                *usage = usage.saturating_add(1)
+            });
+            EraUsage::<T>::mutate(current_era, |usage| *usage = usage.saturating_add(1));
+        }
+
+        /// Calculates the stake based and the usage based part of the contract reward for an era.
+        ///
+        /// `era_reward` is the total reward of the era, bonus included.
+        /// In case there was no usage in the era, entire era reward is distributed according to stake.
+        fn contract_stake_and_usage_reward(
+            contract_id: &T::SmartContract,
+            era: EraIndex,
+            staking_info: &EraStakingPoints<T::AccountId, BalanceOf<T>>,
+            reward_and_stake: &EraRewardAndStake<BalanceOf<T>>,
+            era_reward: BalanceOf<T>,
+        ) -> (BalanceOf<T>, BalanceOf<T>) {
+            let stake_ratio = Perbill::from_rational(staking_info.total, reward_and_stake.staked);
+
+            let era_usage = Self::era_usage(era);
+            if era_usage.is_zero() {
+                return (stake_ratio * era_reward, Zero::zero());
+            }
+
+            let era_usage_reward = T::UsageRewardPercentage::get() * era_reward;
+            let era_stake_reward = era_reward.saturating_sub(era_usage_reward);
+            let usage_ratio =
+                Perbill::from_rational(Self::contract_era_usage(contract_id, era), era_usage);
+
+            (
+                stake_ratio * era_stake_reward,
+                usage_ratio * era_usage_reward,
+            )
+        }
+
+        /// Returns the amount staked by `staker` in the given staking points, zero if there's no stake.
+        fn staked_amount(
+            staking_info: &EraStakingPoints<T::AccountId, BalanceOf<T>>,
+            staker: &T::AccountId,
+        ) -> BalanceOf<T> {
+            staking_info
+                .stakers
+                .get(staker)
+                .copied()
+                .unwrap_or_else(Zero::zero)
+        }
+
         /// Check that contract have active developer linkage.
         fn is_active(contract_id: &T::SmartContract) -> bool {
             if let Some(developer) = RegisteredDapps::<T>::get(contract_id) {
@@ -731,5 +1635,140 @@ pub mod pallet {
             }
             false
         }
+
+        /// Checks that the pallet storage is consistent.
+        ///
+        /// Verifies that:
+        /// * each registered developer points to a dapp which points back to the developer,
+        /// * stake of each contract equals the sum of its stakers in the current and the next era,
+        /// * contracts which aren't active don't have any stake in the next era,
+        /// * total staked value of the current and the next era equals the sum of contract stakes,
+        /// * each ledger equals the sum of the staker's positions in the next era,
+        /// * staker positions index matches the stakers of all contracts in the next era.
+        ///
+        /// Meant to be used by `try-runtime` and tests since it iterates over entire storage maps.
+        #[cfg(any(feature = "try-runtime", test))]
+        pub fn ensure_storage_invariants() -> Result<(), &'static str> {
+            for (developer, contract_id) in RegisteredDevelopers::<T>::iter() {
+                ensure!(
+                    RegisteredDapps::<T>::get(&contract_id) == Some(developer),
+                    "Registered developer and dapp don't point at each other."
+                );
+            }
+
+            let current_era = Self::current_era();
+            let mut current_era_staked: BalanceOf<T> = Zero::zero();
+            let mut next_era_staked: BalanceOf<T> = Zero::zero();
+            let mut positions: BTreeMap<T::AccountId, BalanceOf<T>> = BTreeMap::new();
+            let mut indexed_positions: BTreeMap<
+                T::AccountId,
+                Vec<(T::SmartContract, BalanceOf<T>)>,
+            > = BTreeMap::new();
+
+            for contract_id in RegisteredDapps::<T>::iter_keys() {
+                let current_staking_info = Self::staking_info(&contract_id, current_era);
+                let next_staking_info = Self::staking_info(&contract_id, current_era + 1);
+
+                for staking_info in [&current_staking_info, &next_staking_info].iter() {
+                    let stakers_total = staking_info
+                        .stakers
+                        .values()
+                        .fold(Zero::zero(), |acc: BalanceOf<T>, x| acc.saturating_add(*x));
+                    ensure!(
+                        staking_info.total == stakers_total,
+                        "Contract stake doesn't match the sum of its stakers."
+                    );
+                }
+                ensure!(
+                    Self::is_active(&contract_id) || next_staking_info.total.is_zero(),
+                    "Contract which isn't active has stake."
+                );
+
+                current_era_staked = current_era_staked.saturating_add(current_staking_info.total);
+                next_era_staked = next_era_staked.saturating_add(next_staking_info.total);
+                for (staker, staked) in next_staking_info.stakers.iter() {
+                    let position = positions.entry(staker.clone()).or_insert_with(Zero::zero);
+                    *position = position.saturating_add(*staked);
+                    indexed_positions
+                        .entry(staker.clone())
+                        .or_insert_with(Vec::new)
+                        .push((contract_id.clone(), *staked));
+                }
+            }
+
+            let era_staked = Self::era_reward_and_stake(current_era)
+                .map(|x| x.staked)
+                .unwrap_or_else(Zero::zero);
+            ensure!(
+                era_staked == current_era_staked,
+                "Current era stake doesn't match the sum of contract stakes."
+            );
+            ensure!(
+                Self::next_era_reward_and_stake(current_era).staked == next_era_staked,
+                "Next era stake doesn't match the sum of contract stakes."
+            );
+
+            let ledgers: BTreeMap<T::AccountId, BalanceOf<T>> = Ledger::<T>::iter().collect();
+            ensure!(
+                ledgers == positions,
+                "Ledgers don't match the sum of staker positions."
+            );
+
+            for (staker, staker_positions) in StakerPositions::<T>::iter() {
+                let expected_positions = indexed_positions.remove(&staker).unwrap_or_default();
+                ensure!(
+                    staker_positions.len() == expected_positions.len()
+                        && staker_positions
+                            .iter()
+                            .all(|position| expected_positions.contains(position)),
+                    "Staker positions index doesn't match contract stakers."
+                );
+            }
+            ensure!(
+                indexed_positions.is_empty(),
+                "Staker positions index is missing stakers."
+            );
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> DappsStakingInfo<T::AccountId, T::SmartContract, BalanceOf<T>> for Pallet<T> {
+        fn staker_total_stake(staker: &T::AccountId) -> BalanceOf<T> {
+            Self::ledger(staker)
+        }
+
+        fn staker_contract_stake(
+            staker: &T::AccountId,
+            contract: &T::SmartContract,
+        ) -> BalanceOf<T> {
+            Self::staker_positions(staker)
+                .iter()
+                .find(|(staked_contract, _)| staked_contract == contract)
+                .map(|(_, staked)| *staked)
+                .unwrap_or_else(Zero::zero)
+        }
+
+        fn contract_total_stake(contract: &T::SmartContract) -> BalanceOf<T> {
+            Self::staking_info(contract, Self::current_era() + 1).total
+        }
+
+        fn total_staked() -> BalanceOf<T> {
+            Self::next_era_reward_and_stake(Self::current_era()).staked
+        }
+
+        fn current_era() -> EraIndex {
+            Self::current_era()
+        }
+
+        fn contract_era_stake(contract: &T::SmartContract, era: EraIndex) -> BalanceOf<T> {
+            Self::staking_info(contract, era).total
+        }
+
+        fn era_total_stake(era: EraIndex) -> BalanceOf<T> {
+            Self::era_reward_and_stake(era)
+                .map(|x| x.staked)
+                .unwrap_or_else(Zero::zero)
+        }
     }
 }
@@ -1,7 +1,8 @@
 use super::*;
-use frame_support::assert_ok;
+use frame_support::{assert_ok, BoundedVec};
 use mock::{EraIndex, *};
 use sp_runtime::{traits::AccountIdConversion, Perbill};
+use sp_std::convert::TryInto;
 
 /// Used to fetch the free balance of dapps staking account
 pub(crate) fn free_balance_of_dapps_staking_account() -> Balance {
@@ -53,6 +54,24 @@ pub(crate) fn unbond_unstake_and_withdraw_with_verification(
     ));
 }
 
+/// Used to build bounded staking targets for `bond_and_stake_multi`.
+pub(crate) fn staking_targets(
+    targets: Vec<(MockSmartContract<AccountId>, Balance)>,
+) -> BoundedVec<(MockSmartContract<AccountId>, Balance), MaxStakingTargetsPerCall> {
+    targets.try_into().unwrap()
+}
+
+/// Used to perform bond_and_stake_multi with success assertion.
+pub(crate) fn bond_and_stake_multi_with_verification(
+    staker_id: AccountId,
+    targets: Vec<(MockSmartContract<AccountId>, Balance)>,
+) {
+    assert_ok!(DappsStaking::bond_and_stake_multi(
+        Origin::signed(staker_id),
+        staking_targets(targets),
+    ));
+}
+
 /// Used to verify ledger content.
 pub(crate) fn verify_ledger(staker_id: AccountId, staked_value: Balance) {
     // Verify that ledger storage values are as expected.
@@ -99,6 +118,67 @@ pub(crate) fn verify_pallet_era_staked_and_reward(
     assert_eq!(total_reward_value, era_rewards.rewards);
 }
 
+/// Used to verify that total staked value of each era, up to and including the next one,
+/// equals the sum of stakes of all the given contracts.
+///
+/// Also verifies that each contract's total matches the sum of its stakers, that ledgers cover
+/// the stake which will be effective in the next era and that top contracts index is up to date.
+pub(crate) fn verify_era_staked_consistency(contracts: &[MockSmartContract<AccountId>]) {
+    let current_era = DappsStaking::current_era();
+    let current_era_staked = DappsStaking::era_reward_and_stake(current_era)
+        .map(|x| x.staked)
+        .unwrap_or_default();
+
+    for era in 1..=current_era + 1 {
+        let mut contracts_total: Balance = 0;
+        for contract in contracts {
+            let staking_info = DappsStaking::staking_info(contract, era);
+            assert_eq!(
+                staking_info.total,
+                staking_info.stakers.values().sum::<Balance>()
+            );
+            contracts_total += staking_info.total;
+        }
+
+        // Record for the next era might not exist yet in which case it's derived from the current era.
+        let era_staked = match DappsStaking::era_reward_and_stake(era) {
+            Some(reward_and_stake) => reward_and_stake.staked,
+            None if era > current_era => current_era_staked,
+            None => 0,
+        };
+        assert_eq!(
+            era_staked, contracts_total,
+            "Inconsistent stake in era {}",
+            era
+        );
+    }
+
+    let ledgers_total: Balance = Ledger::<TestRuntime>::iter_values().sum();
+    let next_era_staked = DappsStaking::era_reward_and_stake(current_era + 1)
+        .map(|x| x.staked)
+        .unwrap_or(current_era_staked);
+    assert_eq!(ledgers_total, next_era_staked);
+
+    // Approximate top contracts must be sorted and must reflect the latest stake.
+    let top_contracts = DappsStaking::approx_top_contracts();
+    for (contract, staked) in top_contracts.iter() {
+        assert_eq!(
+            *staked,
+            DappsStaking::staking_info(contract, current_era + 1).total
+        );
+    }
+    assert!(top_contracts.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+
+    // Stake which counts in the current era can never exceed the position.
+    for contract in contracts {
+        let current_staking_info = DappsStaking::staking_info(contract, current_era);
+        let next_staking_info = DappsStaking::staking_info(contract, current_era + 1);
+        for (staker, staked) in current_staking_info.stakers.iter() {
+            assert!(*staked <= *next_staking_info.stakers.get(staker).unwrap_or(&0));
+        }
+    }
+}
+
 /// Used to perform claim with success assertion
 pub(crate) fn claim_with_verification(
     claimer: AccountId,
@@ -115,11 +195,9 @@ pub(crate) fn claim_with_verification(
     ));
 
     // Calculated expected reward that will be distributed for the contract.
-    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
     let staking_points = DappsStaking::contract_era_stake(&contract, &claim_era).unwrap();
-    let calculated_reward = Perbill::from_rational(staking_points.total, rewards_and_stakes.staked)
-        * rewards_and_stakes.rewards
-        * reward_scaling_factor(claim_era);
+    let calculated_reward = calc_expected_contract_stake_reward(claim_era, staking_points.total)
+        + calc_expected_developer_usage_reward(claim_era, &contract);
 
     // Collect all Reward events and sum up all the rewards.
     let emitted_rewards: Balance = dapps_staking_events()
@@ -145,31 +223,64 @@ pub(crate) fn reward_scaling_factor(era: EraIndex) -> Balance {
     }
 }
 
+/// Used to calculate the part of the era reward which is distributed according to stake
+pub(crate) fn calc_expected_era_stake_reward(claim_era: EraIndex) -> Balance {
+    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
+    let era_reward = rewards_and_stakes.rewards * reward_scaling_factor(claim_era);
+    if DappsStaking::era_usage(claim_era) == 0 {
+        era_reward
+    } else {
+        era_reward - UsageRewardPercentage::get() * era_reward
+    }
+}
+
+/// Used to calculate the stake based reward for the contract
+pub(crate) fn calc_expected_contract_stake_reward(
+    claim_era: EraIndex,
+    contract_stake: Balance,
+) -> Balance {
+    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
+    Perbill::from_rational(contract_stake, rewards_and_stakes.staked)
+        * calc_expected_era_stake_reward(claim_era)
+}
+
+/// Used to calculate the usage based reward for the developer
+pub(crate) fn calc_expected_developer_usage_reward(
+    claim_era: EraIndex,
+    contract: &MockSmartContract<AccountId>,
+) -> Balance {
+    let era_usage = DappsStaking::era_usage(claim_era);
+    if era_usage == 0 {
+        return 0;
+    }
+
+    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
+    let era_reward = rewards_and_stakes.rewards * reward_scaling_factor(claim_era);
+    Perbill::from_rational(
+        DappsStaking::contract_era_usage(contract, claim_era),
+        era_usage,
+    ) * (UsageRewardPercentage::get() * era_reward)
+}
+
 /// Used to calculate the expected reward for the staker
 pub(crate) fn calc_expected_staker_reward(
     claim_era: EraIndex,
     contract_stake: Balance,
     staker_stake: Balance,
 ) -> Balance {
-    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
-    let contract_reward = Perbill::from_rational(contract_stake, rewards_and_stakes.staked)
-        * rewards_and_stakes.rewards
-        * reward_scaling_factor(claim_era);
+    let contract_reward = calc_expected_contract_stake_reward(claim_era, contract_stake);
     let contract_reward_staker_part =
         Perbill::from_percent(100 - DEVELOPER_REWARD_PERCENTAGE) * contract_reward;
 
     Perbill::from_rational(staker_stake, contract_stake) * contract_reward_staker_part
 }
 
-/// Used to calculate the expected reward for the developer
+/// Used to calculate the expected stake based reward for the developer
 pub(crate) fn calc_expected_developer_reward(
     claim_era: EraIndex,
     contract_stake: Balance,
 ) -> Balance {
-    let rewards_and_stakes = DappsStaking::era_reward_and_stake(&claim_era).unwrap();
-    let contract_reward = Perbill::from_rational(contract_stake, rewards_and_stakes.staked)
-        * rewards_and_stakes.rewards
-        * reward_scaling_factor(claim_era);
+    let contract_reward = calc_expected_contract_stake_reward(claim_era, contract_stake);
     Perbill::from_percent(DEVELOPER_REWARD_PERCENTAGE) * contract_reward
 }
 
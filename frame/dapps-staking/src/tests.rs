@@ -1,11 +1,12 @@
 use super::{pallet::pallet::Error, Event, *};
 use frame_support::{
     assert_noop, assert_ok,
-    traits::{OnInitialize, OnUnbalanced},
+    traits::{OnInitialize, OnRuntimeUpgrade, OnUnbalanced, StorageVersion},
+    weights::{GetDispatchInfo, Weight},
 };
 use mock::{Balances, MockSmartContract, *};
 use sp_core::H160;
-use sp_runtime::traits::Zero;
+use sp_runtime::{traits::Zero, Perbill};
 
 use testing_utils::*;
 
@@ -87,24 +88,29 @@ fn staking_info_is_ok() {
 
         // Checks
 
-        // Check first interval
-        for era in starting_era..mid_era {
+        // Check first interval. Stake counts from the era following the one in which it was made.
+        for era in (starting_era + 1)..mid_era {
             let staking_info = DappsStaking::staking_info(&contract_id, era);
             assert_eq!(2_usize, staking_info.stakers.len());
             assert!(staking_info.stakers.contains_key(&staker_1));
-            assert!(staking_info.stakers.contains_key(&staker_1));
+            assert!(staking_info.stakers.contains_key(&staker_2));
         }
 
+        // Unstaked value stops counting immediately while new stake doesn't count yet
+        let staking_info = DappsStaking::staking_info(&contract_id, mid_era);
+        assert_eq!(1_usize, staking_info.stakers.len());
+        assert!(staking_info.stakers.contains_key(&staker_1));
+
         // Check second interval
-        for era in mid_era..=final_era {
+        for era in (mid_era + 1)..=final_era {
             let staking_info = DappsStaking::staking_info(&contract_id, era);
             assert_eq!(2_usize, staking_info.stakers.len());
             assert!(staking_info.stakers.contains_key(&staker_1));
             assert!(staking_info.stakers.contains_key(&staker_3));
         }
 
-        // Check that before starting era nothing exists
-        let staking_info = DappsStaking::staking_info(&contract_id, starting_era - 1);
+        // Check that up to and including starting era nothing exists
+        let staking_info = DappsStaking::staking_info(&contract_id, starting_era);
         assert!(staking_info.stakers.is_empty());
 
         // TODO: Do we want such behavior?
@@ -313,6 +319,7 @@ fn unregister_with_staked_contracts_is_ok() {
             contract_id,
         )));
         verify_storage_after_unregister(&developer, &contract_id);
+        assert_ok!(DappsStaking::ensure_storage_invariants());
 
         // Ensure ledger contains expected stake values. We have a single staked contract remaining.
         assert_eq!(staked_value_1, DappsStaking::ledger(&staker_1));
@@ -439,14 +446,17 @@ fn bond_and_stake_different_eras_is_ok() {
         )));
 
         // Verify storage values to see if contract was successfully bonded and staked.
+        // Stake only counts from the next era.
         verify_ledger(staker_id, first_stake_value);
         verify_era_staking_points(
             &contract_id,
             first_stake_value,
-            current_era,
+            current_era + 1,
             vec![(staker_id, first_stake_value)],
         );
-        verify_pallet_era_staked(current_era, first_stake_value);
+        verify_pallet_era_staked(current_era + 1, first_stake_value);
+        assert!(ContractEraStake::<TestRuntime>::get(&contract_id, current_era).is_none());
+        verify_pallet_era_staked(current_era, 0);
 
         // Prepare new values and advance some eras.
         let second_stake_value = 300;
@@ -475,10 +485,15 @@ fn bond_and_stake_different_eras_is_ok() {
         verify_era_staking_points(
             &contract_id,
             total_stake_value,
-            current_era,
+            current_era + 1,
             vec![(staker_id, total_stake_value)],
         );
-        verify_pallet_era_staked(current_era, total_stake_value);
+        verify_pallet_era_staked(current_era + 1, total_stake_value);
+
+        // Only the first stake counts in the current era
+        let staking_info = DappsStaking::staking_info(&contract_id, current_era);
+        assert_eq!(first_stake_value, staking_info.total);
+        verify_pallet_era_staked(current_era, first_stake_value);
     })
 }
 
@@ -516,17 +531,17 @@ fn bond_and_stake_two_different_contracts_is_ok() {
         verify_era_staking_points(
             &first_contract_id,
             first_stake_value,
-            current_era,
+            current_era + 1,
             vec![(staker_id, first_stake_value)],
         );
         verify_era_staking_points(
             &second_contract_id,
             second_stake_value,
-            current_era,
+            current_era + 1,
             vec![(staker_id, second_stake_value)],
         );
         assert_eq!(
-            EraRewardsAndStakes::<TestRuntime>::get(current_era)
+            EraRewardsAndStakes::<TestRuntime>::get(current_era + 1)
                 .unwrap()
                 .staked,
             total_stake_value,
@@ -568,14 +583,14 @@ fn bond_and_stake_two_stakers_one_contract_is_ok() {
         verify_era_staking_points(
             &contract_id,
             total_stake_value,
-            current_era,
+            current_era + 1,
             vec![
                 (first_staker_id, first_stake_value),
                 (second_staker_id, second_stake_value),
             ],
         );
         assert_eq!(
-            EraRewardsAndStakes::<TestRuntime>::get(current_era)
+            EraRewardsAndStakes::<TestRuntime>::get(current_era + 1)
                 .unwrap()
                 .staked,
             total_stake_value,
@@ -696,34 +711,25 @@ fn bond_and_stake_history_depth_has_passed_is_ok() {
         let total_staked = first_staking_amount + second_staking_amount;
         let current_era = DappsStaking::current_era();
 
-        // Verify storage values related to the current era
+        // Verify storage values related to the next era
         verify_ledger(staker_id, total_staked);
         verify_era_staking_points(
             &contract_id,
             total_staked,
-            current_era,
+            current_era + 1,
             vec![(staker_id, total_staked)],
         );
-        assert_eq!(
-            EraRewardsAndStakes::<TestRuntime>::get(current_era)
-                .unwrap()
-                .staked,
-            total_staked,
-        );
+        verify_pallet_era_staked(current_era + 1, total_staked);
+        verify_pallet_era_staked(current_era, first_staking_amount);
 
         // Also ensure that former values still exists even if they're beyond 'history depth'
         verify_era_staking_points(
             &contract_id,
             first_staking_amount,
-            start_era,
+            start_era + 1,
             vec![(staker_id, first_staking_amount)],
         );
-        assert_eq!(
-            EraRewardsAndStakes::<TestRuntime>::get(current_era)
-                .unwrap()
-                .staked,
-            total_staked,
-        );
+        verify_pallet_era_staked(start_era + 1, first_staking_amount);
     })
 }
 
@@ -851,13 +857,19 @@ fn unbond_unstake_and_withdraw_multiple_time_is_ok() {
         );
         verify_pallet_era_staked(new_era, new_staked_value);
 
-        // Also verify that the storage values for the old era haven't been changed due to unstaking
+        // Unstaked value mustn't count in the next era either
         verify_era_staking_points(
             &contract_id,
-            original_staked_value,
-            old_era,
-            vec![(staker_id, original_staked_value)],
+            new_staked_value,
+            new_era + 1,
+            vec![(staker_id, new_staked_value)],
         );
+        verify_pallet_era_staked(new_era + 1, new_staked_value);
+
+        // Also verify that stake didn't count in the era in which it was made
+        assert!(DappsStaking::staking_info(&contract_id, old_era)
+            .stakers
+            .is_empty());
 
         // Unbond yet again, but don't advance era
         // Unstake such an amount so there will remain staked funds on the contract
@@ -928,13 +940,9 @@ fn unbond_unstake_and_withdraw_value_below_staking_threshold() {
         )));
         assert!(!Ledger::<TestRuntime>::contains_key(staker_id));
 
-        verify_era_staking_points(&contract_id, Zero::zero(), current_era, vec![]);
-        assert_eq!(
-            EraRewardsAndStakes::<TestRuntime>::get(current_era)
-                .unwrap()
-                .staked,
-            Zero::zero(),
-        );
+        verify_era_staking_points(&contract_id, Zero::zero(), current_era + 1, vec![]);
+        verify_pallet_era_staked(current_era + 1, Zero::zero());
+        verify_pallet_era_staked(current_era, Zero::zero());
     })
 }
 
@@ -1192,6 +1200,307 @@ fn unbond_unstake_and_withdraw_unstake_not_possible() {
     })
 }
 
+#[test]
+fn bond_and_stake_multi_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let first_stake_value = 100;
+        let second_stake_value = 300;
+        let current_era = DappsStaking::current_era();
+
+        register_contract(5, &first_contract_id);
+        register_contract(6, &second_contract_id);
+
+        // Stake on both contracts with a single call.
+        bond_and_stake_multi_with_verification(
+            staker_id,
+            vec![
+                (first_contract_id.clone(), first_stake_value),
+                (second_contract_id.clone(), second_stake_value),
+            ],
+        );
+        System::assert_has_event(mock::Event::DappsStaking(Event::BondAndStake(
+            staker_id,
+            first_contract_id.clone(),
+            first_stake_value,
+        )));
+        System::assert_last_event(mock::Event::DappsStaking(Event::BondAndStake(
+            staker_id,
+            second_contract_id.clone(),
+            second_stake_value,
+        )));
+
+        // Verify that storage is the same as if two separate calls were made.
+        let total_stake_value = first_stake_value + second_stake_value;
+        verify_ledger(staker_id, total_stake_value);
+        verify_era_staking_points(
+            &first_contract_id,
+            first_stake_value,
+            current_era + 1,
+            vec![(staker_id, first_stake_value)],
+        );
+        verify_era_staking_points(
+            &second_contract_id,
+            second_stake_value,
+            current_era + 1,
+            vec![(staker_id, second_stake_value)],
+        );
+        verify_pallet_era_staked(current_era + 1, total_stake_value);
+
+        // Stake again, adding to the existing stake on one of the contracts.
+        bond_and_stake_multi_with_verification(
+            staker_id,
+            vec![(first_contract_id.clone(), first_stake_value)],
+        );
+        verify_ledger(staker_id, total_stake_value + first_stake_value);
+        verify_era_staking_points(
+            &first_contract_id,
+            2 * first_stake_value,
+            current_era + 1,
+            vec![(staker_id, 2 * first_stake_value)],
+        );
+    })
+}
+
+#[test]
+fn bond_and_stake_multi_invalid_targets_not_works() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let unregistered_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x03));
+
+        register_contract(5, &first_contract_id);
+        register_contract(6, &second_contract_id);
+
+        // Empty list of targets isn't allowed.
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(Origin::signed(staker_id), staking_targets(vec![])),
+            Error::<TestRuntime>::StakingWithNoValue
+        );
+
+        // A single invalid target fails the entire call.
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), 100),
+                    (unregistered_contract_id.clone(), 100),
+                ])
+            ),
+            Error::<TestRuntime>::NotOperatedContract
+        );
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), 100),
+                    (second_contract_id.clone(), 0),
+                ])
+            ),
+            Error::<TestRuntime>::StakingWithNoValue
+        );
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), 100),
+                    (second_contract_id.clone(), MINIMUM_STAKING_AMOUNT - 1),
+                ])
+            ),
+            Error::<TestRuntime>::InsufficientValue
+        );
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), 100),
+                    (first_contract_id.clone(), 100),
+                ])
+            ),
+            Error::<TestRuntime>::DuplicateStakingTarget
+        );
+
+        // Values aren't capped to the free balance, the entire call fails instead.
+        let staker_free_balance = Balances::free_balance(&staker_id);
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), staker_free_balance / 2),
+                    (second_contract_id.clone(), staker_free_balance / 2),
+                ])
+            ),
+            Error::<TestRuntime>::InsufficientStakingBalance
+        );
+    })
+}
+
+#[test]
+fn bond_and_stake_multi_too_many_stakers_per_contract() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+
+        // Fill up the second contract with stakers.
+        for staker_id in 1..=MAX_NUMBER_OF_STAKERS {
+            bond_and_stake_with_verification(staker_id.into(), &second_contract_id, 100);
+        }
+
+        // Staking on both contracts must fail since second contract cannot accept a new staker.
+        let staker_id = (1 + MAX_NUMBER_OF_STAKERS).into();
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![
+                    (first_contract_id.clone(), 100),
+                    (second_contract_id.clone(), 100),
+                ])
+            ),
+            Error::<TestRuntime>::MaxNumberOfStakersExceeded
+        );
+    })
+}
+
+#[test]
+fn unbond_all_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let other_staker_id = 2;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let third_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x03));
+
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        register_contract(5, &third_contract_id);
+
+        bond_and_stake_multi_with_verification(
+            staker_id,
+            vec![
+                (first_contract_id.clone(), 100),
+                (second_contract_id.clone(), 200),
+            ],
+        );
+        bond_and_stake_with_verification(other_staker_id, &first_contract_id, 50);
+        bond_and_stake_with_verification(other_staker_id, &third_contract_id, 70);
+
+        advance_to_era(DappsStaking::current_era() + 1);
+        let current_era = DappsStaking::current_era();
+
+        // Exit all positions, only the ones belonging to the caller must be affected.
+        assert_ok!(DappsStaking::unbond_all(Origin::signed(staker_id)));
+        System::assert_has_event(mock::Event::DappsStaking(Event::UnbondUnstakeAndWithdraw(
+            staker_id,
+            first_contract_id.clone(),
+            100,
+        )));
+        System::assert_has_event(mock::Event::DappsStaking(Event::UnbondUnstakeAndWithdraw(
+            staker_id,
+            second_contract_id.clone(),
+            200,
+        )));
+
+        verify_ledger(staker_id, 0);
+        verify_ledger(other_staker_id, 120);
+        verify_era_staking_points(
+            &first_contract_id,
+            50,
+            current_era,
+            vec![(other_staker_id, 50)],
+        );
+        verify_era_staking_points(&second_contract_id, 0, current_era, vec![]);
+        verify_pallet_era_staked(current_era, 120);
+        verify_pallet_era_staked(current_era + 1, 120);
+        assert_ok!(DappsStaking::ensure_storage_invariants());
+
+        // Nothing is left to unstake.
+        assert_noop!(
+            DappsStaking::unbond_all(Origin::signed(staker_id)),
+            Error::<TestRuntime>::NotStakedContract
+        );
+    })
+}
+
+#[test]
+fn unbond_all_is_atomic() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        bond_and_stake_multi_with_verification(
+            staker_id,
+            vec![
+                (first_contract_id.clone(), 100),
+                (second_contract_id.clone(), 200),
+            ],
+        );
+
+        // Stake on the second contract is lost while the staker still has a position on it.
+        let next_era = DappsStaking::current_era() + 1;
+        let mut staking_info = DappsStaking::staking_info(&second_contract_id, next_era);
+        staking_info.stakers.remove(&staker_id);
+        staking_info.total -= 200;
+        ContractEraStake::<TestRuntime>::insert(&second_contract_id, next_era, staking_info);
+
+        // Unstaking the second position fails so the first one is left untouched.
+        assert_noop!(
+            DappsStaking::unbond_all(Origin::signed(staker_id)),
+            Error::<TestRuntime>::NotStakedContract
+        );
+        verify_ledger(staker_id, 300);
+        verify_era_staking_points(&first_contract_id, 100, next_era, vec![(staker_id, 100)]);
+        assert_eq!(
+            DappsStaking::staker_positions(staker_id).into_inner(),
+            vec![
+                (first_contract_id.clone(), 100),
+                (second_contract_id.clone(), 200)
+            ]
+        );
+    })
+}
+
+#[test]
+fn unbond_all_skips_unregistered_contracts() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let developer = 10;
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+
+        register_contract(developer, &contract_id);
+        bond_and_stake_with_verification(staker_id, &contract_id, 100);
+
+        // Unregistering the contract unstakes all of its stakers so nothing remains.
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(developer),
+            contract_id.clone()
+        ));
+        assert_noop!(
+            DappsStaking::unbond_all(Origin::signed(staker_id)),
+            Error::<TestRuntime>::NotStakedContract
+        );
+    })
+}
+
 #[test]
 fn new_era_is_ok() {
     ExternalityBuilder::build().execute_with(|| {
@@ -1234,8 +1543,10 @@ fn new_era_is_ok() {
         assert!(block_reward.is_zero());
 
         let expected_era_reward = get_total_reward_per_era();
-        // verify that .staked is copied and .reward is added
-        verify_pallet_era_staked_and_reward(starting_era, staked_amount, expected_era_reward);
+        // verify that .reward is added while stake made during the era doesn't count in it
+        verify_pallet_era_staked_and_reward(starting_era, 0, expected_era_reward);
+        // verify that .staked is carried over to the new era
+        verify_pallet_era_staked_and_reward(current_era, staked_amount, 0);
     })
 }
 
@@ -1331,7 +1642,8 @@ fn claim_twice_in_same_era() {
         register_contract(developer, &contract);
         bond_and_stake_with_verification(claimer, &contract, 100);
 
-        advance_to_era(DappsStaking::current_era() + 1);
+        // Stake counts from the next era so we need to advance one more era to be able to claim it
+        advance_to_era(DappsStaking::current_era() + 2);
 
         let claim_era = DappsStaking::current_era() - 1;
         claim_with_verification(claimer, contract, claim_era);
@@ -1416,9 +1728,9 @@ fn claim_after_unregister_is_ok() {
         ));
         let unregistered_era = DappsStaking::current_era();
 
-        // Ensure that contract can still be claimed.
+        // Ensure that contract can still be claimed. Stake made in the first era counts from the second one.
         let current_era = DappsStaking::current_era();
-        for era in 1..current_era {
+        for era in 2..current_era {
             claim_with_verification(staker, contract.clone(), era);
         }
 
@@ -1457,7 +1769,7 @@ fn claim_one_contract_one_staker() {
         bond_and_stake_with_verification(staker1, &contract, stake_amount_1);
 
         // Advance some eras to be able to claim rewards. Verify storage is consolidated
-        advance_to_era(start_era + 1);
+        advance_to_era(start_era + 2);
         let claim_era = DappsStaking::current_era() - 1;
         claim_with_verification(staker1, contract, claim_era);
         // calculate reward per stakers
@@ -1579,7 +1891,7 @@ fn claim_two_contracts_three_stakers_new() {
         bond_and_stake_with_verification(staker1, &contract1, staker_1_amount);
         bond_and_stake_with_verification(staker2, &contract1, staker_2_amount_1);
         let contract_1_stake = staker_1_amount + staker_2_amount_1;
-        let first_claim_era = start_era;
+        let first_claim_era = start_era + 1;
 
         // Advance eras and then bond&stake with two stakers on second contract.
         advance_to_era(start_era + 3);
@@ -1588,10 +1900,10 @@ fn claim_two_contracts_three_stakers_new() {
         bond_and_stake_with_verification(staker3, &contract2, staker_3_amount);
         let contract_2_stake = staker_2_amount_2 + staker_3_amount;
 
-        // Advance era again by one, so rewards can be claimed for previous era as well.
+        // Advance era again by two, so rewards can be claimed for the era in which the new stake counts.
         let current_era = DappsStaking::current_era();
-        let second_claim_era = current_era;
-        advance_to_era(current_era + 1);
+        let second_claim_era = current_era + 1;
+        advance_to_era(current_era + 2);
 
         // Claim first contract rewards for the two prepared eras and verify storage content is as expected.
         claim_with_verification(staker1, contract1.clone(), first_claim_era);
@@ -1689,3 +2001,1148 @@ fn claim_two_contracts_three_stakers_new() {
         check_paidout_rewards_for_contract(&contract2, second_claim_era, expected_contract2_reward);
     })
 }
+
+#[test]
+fn hooks_are_called_on_stake_changes() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        take_hook_calls();
+
+        bond_and_stake_with_verification(staker_id, &first_contract_id, 100);
+        bond_and_stake_with_verification(staker_id, &first_contract_id, 50);
+        unbond_unstake_and_withdraw_with_verification(staker_id, &first_contract_id, 30);
+        bond_and_stake_multi_with_verification(staker_id, vec![(second_contract_id.clone(), 70)]);
+        assert_ok!(DappsStaking::unbond_all(Origin::signed(staker_id)));
+
+        // Each call reports the new total staked amount on the contract.
+        assert_eq!(
+            take_hook_calls(),
+            vec![
+                HookCall::StakeChanged(staker_id, first_contract_id, 100),
+                HookCall::StakeChanged(staker_id, first_contract_id, 150),
+                HookCall::StakeChanged(staker_id, first_contract_id, 120),
+                HookCall::StakeChanged(staker_id, second_contract_id, 70),
+                HookCall::StakeChanged(staker_id, first_contract_id, 0),
+                HookCall::StakeChanged(staker_id, second_contract_id, 0),
+            ]
+        );
+
+        // Failed calls mustn't invoke hooks.
+        assert_noop!(
+            DappsStaking::unbond_all(Origin::signed(staker_id)),
+            Error::<TestRuntime>::NotStakedContract
+        );
+        assert!(take_hook_calls().is_empty());
+    })
+}
+
+#[test]
+fn hooks_are_called_on_unregister() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let developer = 10;
+        let (staker_1, staker_2) = (1, 2);
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(developer, &contract_id);
+        bond_and_stake_with_verification(staker_1, &contract_id, 100);
+        bond_and_stake_with_verification(staker_2, &contract_id, 200);
+        take_hook_calls();
+
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(developer),
+            contract_id.clone()
+        ));
+
+        assert_eq!(
+            take_hook_calls(),
+            vec![
+                HookCall::StakeChanged(staker_1, contract_id, 0),
+                HookCall::StakeChanged(staker_2, contract_id, 0),
+                HookCall::ContractUnregistered(contract_id),
+            ]
+        );
+    })
+}
+
+#[test]
+fn hooks_are_called_on_claim() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let developer = 10;
+        let (staker_1, staker_2) = (1, 2);
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(developer, &contract_id);
+        bond_and_stake_with_verification(staker_1, &contract_id, 100);
+        bond_and_stake_with_verification(staker_2, &contract_id, 300);
+
+        let claim_era = DappsStaking::current_era() + 1;
+        advance_to_era(claim_era + 1);
+        take_hook_calls();
+
+        claim_with_verification(staker_1, contract_id, claim_era);
+
+        // Hooks must report exactly what was paid out in the reward events.
+        let expected_calls: Vec<HookCall> = dapps_staking_events()
+            .into_iter()
+            .filter_map(|e| {
+                if let crate::Event::Reward(account, contract, era, reward) = e {
+                    Some(HookCall::RewardPaid(account, contract, era, reward))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        assert_eq!(expected_calls.len(), 3);
+        assert_eq!(take_hook_calls(), expected_calls);
+    })
+}
+
+#[test]
+fn hooks_weight_is_included() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let developer = 10;
+        let (staker_1, staker_2) = (1, 2);
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(developer, &contract_id);
+
+        // Worst case number of hook calls is included in the dispatch weight.
+        let max_payees = MAX_NUMBER_OF_STAKERS + 1;
+        assert_eq!(
+            crate::Call::<TestRuntime>::bond_and_stake {
+                contract_id: contract_id.clone(),
+                value: 100
+            }
+            .get_dispatch_info()
+            .weight,
+            <TestRuntime as Config>::WeightInfo::bond_and_stake() + STAKE_CHANGED_HOOK_WEIGHT
+        );
+        assert_eq!(
+            crate::Call::<TestRuntime>::unbond_all {}
+                .get_dispatch_info()
+                .weight,
+            <TestRuntime as Config>::WeightInfo::unbond_all(MAX_STAKING_TARGETS_PER_CALL)
+                + STAKE_CHANGED_HOOK_WEIGHT * MAX_STAKING_TARGETS_PER_CALL as Weight
+        );
+        assert_eq!(
+            crate::Call::<TestRuntime>::claim {
+                contract_id: contract_id.clone(),
+                era: 1
+            }
+            .get_dispatch_info()
+            .weight,
+            <TestRuntime as Config>::WeightInfo::claim(max_payees)
+                + REWARD_PAID_HOOK_WEIGHT * max_payees as Weight
+        );
+        assert_eq!(
+            crate::Call::<TestRuntime>::unregister {
+                contract_id: contract_id.clone()
+            }
+            .get_dispatch_info()
+            .weight,
+            <TestRuntime as Config>::WeightInfo::unregister(MAX_NUMBER_OF_STAKERS)
+                + STAKE_CHANGED_HOOK_WEIGHT * MAX_NUMBER_OF_STAKERS as Weight
+                + CONTRACT_UNREGISTERED_HOOK_WEIGHT
+        );
+
+        // Actual weight includes the hooks which were called.
+        let info = DappsStaking::bond_and_stake(Origin::signed(staker_1), contract_id.clone(), 100)
+            .unwrap();
+        assert_eq!(
+            info.actual_weight,
+            Some(<TestRuntime as Config>::WeightInfo::bond_and_stake() + STAKE_CHANGED_HOOK_WEIGHT)
+        );
+        bond_and_stake_with_verification(staker_2, &contract_id, 200);
+
+        let claim_era = DappsStaking::current_era() + 1;
+        advance_to_era(claim_era + 1);
+        let info =
+            DappsStaking::claim(Origin::signed(staker_1), contract_id.clone(), claim_era).unwrap();
+        assert_eq!(
+            info.actual_weight,
+            Some(<TestRuntime as Config>::WeightInfo::claim(3) + REWARD_PAID_HOOK_WEIGHT * 3)
+        );
+
+        let info = DappsStaking::unregister(Origin::signed(developer), contract_id).unwrap();
+        assert_eq!(
+            info.actual_weight,
+            Some(
+                <TestRuntime as Config>::WeightInfo::unregister(2)
+                    + STAKE_CHANGED_HOOK_WEIGHT * 2
+                    + CONTRACT_UNREGISTERED_HOOK_WEIGHT
+            )
+        );
+    })
+}
+
+#[test]
+fn dapps_staking_info_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let (staker_1, staker_2) = (1, 2);
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+
+        // Nothing is staked yet.
+        assert!(DappsStaking::total_staked().is_zero());
+        assert!(DappsStaking::staker_total_stake(&staker_1).is_zero());
+        assert!(DappsStaking::contract_total_stake(&first_contract_id).is_zero());
+
+        bond_and_stake_with_verification(staker_1, &first_contract_id, 100);
+        bond_and_stake_with_verification(staker_1, &second_contract_id, 200);
+        bond_and_stake_with_verification(staker_2, &first_contract_id, 300);
+
+        // Values must be reported in the following eras too.
+        advance_to_era(DappsStaking::current_era() + 2);
+
+        assert_eq!(DappsStaking::staker_total_stake(&staker_1), 300);
+        assert_eq!(DappsStaking::staker_total_stake(&staker_2), 300);
+        assert_eq!(
+            DappsStaking::staker_contract_stake(&staker_1, &first_contract_id),
+            100
+        );
+        assert_eq!(
+            DappsStaking::staker_contract_stake(&staker_2, &first_contract_id),
+            300
+        );
+        assert!(DappsStaking::staker_contract_stake(&staker_2, &second_contract_id).is_zero());
+        assert_eq!(DappsStaking::contract_total_stake(&first_contract_id), 400);
+        assert_eq!(DappsStaking::contract_total_stake(&second_contract_id), 200);
+        assert_eq!(DappsStaking::total_staked(), 600);
+
+        // Stake of an era doesn't include stake which becomes effective in the next era.
+        let current_era = DappsStaking::current_era();
+        bond_and_stake_with_verification(staker_2, &second_contract_id, 50);
+        assert_eq!(DappsStaking::contract_total_stake(&second_contract_id), 250);
+        assert_eq!(
+            DappsStaking::contract_era_stake(&second_contract_id, current_era),
+            200
+        );
+        assert_eq!(
+            DappsStaking::contract_era_stake(&second_contract_id, current_era + 1),
+            250
+        );
+        assert_eq!(DappsStaking::era_total_stake(current_era), 600);
+        assert_eq!(DappsStaking::era_total_stake(current_era + 1), 650);
+    })
+}
+
+#[test]
+fn note_contract_usage_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let developer = 10;
+        let (caller1, caller2) = (1, 2);
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let unregistered_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        register_contract(developer, &contract_id);
+        let current_era = DappsStaking::current_era();
+
+        DappsStaking::note_contract_usage(&contract_id, &caller1);
+        DappsStaking::note_contract_usage(&contract_id, &caller2);
+        assert_eq!(
+            DappsStaking::contract_era_usage(&contract_id, current_era),
+            2
+        );
+        assert_eq!(DappsStaking::era_usage(current_era), 2);
+
+        // Repeated calls of the same caller are counted only once per era.
+        DappsStaking::note_contract_usage(&contract_id, &caller1);
+        assert_eq!(
+            DappsStaking::contract_era_usage(&contract_id, current_era),
+            2
+        );
+        assert_eq!(DappsStaking::era_usage(current_era), 2);
+
+        // Usage of unregistered contracts isn't counted.
+        DappsStaking::note_contract_usage(&unregistered_contract_id, &caller1);
+        assert!(DappsStaking::contract_era_usage(&unregistered_contract_id, current_era).is_zero());
+        assert_eq!(DappsStaking::era_usage(current_era), 2);
+
+        // Usage is counted per era.
+        advance_to_era(current_era + 1);
+        DappsStaking::note_contract_usage(&contract_id, &caller1);
+        assert_eq!(
+            DappsStaking::contract_era_usage(&contract_id, current_era),
+            2
+        );
+        assert_eq!(
+            DappsStaking::contract_era_usage(&contract_id, current_era + 1),
+            1
+        );
+        assert_eq!(DappsStaking::era_usage(current_era + 1), 1);
+
+        // Usage stops being counted once contract is unregistered.
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(developer),
+            contract_id.clone()
+        ));
+        DappsStaking::note_contract_usage(&contract_id, &caller2);
+        assert_eq!(
+            DappsStaking::contract_era_usage(&contract_id, current_era + 1),
+            1
+        );
+        assert_eq!(DappsStaking::era_usage(current_era + 1), 1);
+    })
+}
+
+#[test]
+fn note_contract_usage_registers_weight() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(10, &contract_id);
+
+        let consumed_weight = System::block_weight().total();
+        DappsStaking::note_contract_usage(&contract_id, &1);
+        assert_eq!(
+            System::block_weight().total(),
+            consumed_weight + <TestRuntime as Config>::WeightInfo::note_contract_usage()
+        );
+    })
+}
+
+#[test]
+fn claim_with_usage_reward_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let (developer1, developer2) = (10, 20);
+        let (staker1, staker2) = (1, 2);
+        let (staker1_amount, staker2_amount) = (100, 300);
+        let contract1 = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let contract2 = MockSmartContract::Evm(H160::repeat_byte(0x02));
+
+        register_contract(developer1, &contract1);
+        register_contract(developer2, &contract2);
+        bond_and_stake_with_verification(staker1, &contract1, staker1_amount);
+        bond_and_stake_with_verification(staker2, &contract2, staker2_amount);
+
+        // Stake counts from the next era.
+        advance_to_era(DappsStaking::current_era() + 1);
+
+        // First contract is used three times as much as the second one.
+        let claim_era = DappsStaking::current_era();
+        for caller in 1..=3 {
+            DappsStaking::note_contract_usage(&contract1, &caller);
+        }
+        DappsStaking::note_contract_usage(&contract2, &1);
+        advance_to_era(claim_era + 1);
+
+        let free_balance_developer1 = Balances::free_balance(&developer1);
+        let free_balance_developer2 = Balances::free_balance(&developer2);
+        let free_balance_staker1 = Balances::free_balance(&staker1);
+        let free_balance_staker2 = Balances::free_balance(&staker2);
+
+        claim_with_verification(staker1, contract1, claim_era);
+        claim_with_verification(staker2, contract2, claim_era);
+
+        // Usage part of the era reward is split by usage share and paid to developers only.
+        let era_reward = get_total_reward_per_era() * reward_scaling_factor(claim_era);
+        let usage_reward = Perbill::from_percent(USAGE_REWARD_PERCENTAGE) * era_reward;
+        assert_eq!(
+            calc_expected_developer_usage_reward(claim_era, &contract1),
+            Perbill::from_rational(3u32, 4u32) * usage_reward
+        );
+        assert_eq!(
+            calc_expected_developer_usage_reward(claim_era, &contract2),
+            Perbill::from_rational(1u32, 4u32) * usage_reward
+        );
+        assert_eq!(
+            calc_expected_era_stake_reward(claim_era),
+            era_reward - usage_reward
+        );
+
+        check_rewards_on_balance_and_storage(
+            &developer1,
+            free_balance_developer1,
+            calc_expected_developer_reward(claim_era, staker1_amount)
+                + calc_expected_developer_usage_reward(claim_era, &contract1),
+        );
+        check_rewards_on_balance_and_storage(
+            &developer2,
+            free_balance_developer2,
+            calc_expected_developer_reward(claim_era, staker2_amount)
+                + calc_expected_developer_usage_reward(claim_era, &contract2),
+        );
+        check_rewards_on_balance_and_storage(
+            &staker1,
+            free_balance_staker1,
+            calc_expected_staker_reward(claim_era, staker1_amount, staker1_amount),
+        );
+        check_rewards_on_balance_and_storage(
+            &staker2,
+            free_balance_staker2,
+            calc_expected_staker_reward(claim_era, staker2_amount, staker2_amount),
+        );
+    })
+}
+
+#[test]
+fn claim_usage_reward_without_stakers_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let (developer1, developer2) = (10, 20);
+        let staker = 1;
+        let contract1 = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let contract2 = MockSmartContract::Evm(H160::repeat_byte(0x02));
+
+        // Only the second contract has stakers, only the first one is used.
+        register_contract(developer1, &contract1);
+        register_contract(developer2, &contract2);
+        bond_and_stake_with_verification(staker, &contract2, 100);
+        advance_to_era(DappsStaking::current_era() + 1);
+
+        let claim_era = DappsStaking::current_era();
+        DappsStaking::note_contract_usage(&contract1, &staker);
+        advance_to_era(claim_era + 1);
+
+        // Usage reward is paid to the developer even though nobody staked on the contract.
+        let free_balance_developer1 = Balances::free_balance(&developer1);
+        let usage_reward = calc_expected_developer_usage_reward(claim_era, &contract1);
+        assert!(!usage_reward.is_zero());
+        claim_with_verification(developer1, contract1, claim_era);
+        check_rewards_on_balance_and_storage(&developer1, free_balance_developer1, usage_reward);
+        check_paidout_rewards_for_contract(&contract1, claim_era, usage_reward);
+
+        assert_noop!(
+            DappsStaking::claim(Origin::signed(developer1), contract1, claim_era),
+            Error::<TestRuntime>::AlreadyClaimedInThisEra
+        );
+
+        // Contract without stakers and usage has nothing to claim.
+        let idle_era = claim_era + 1;
+        advance_to_era(idle_era + 1);
+        assert_noop!(
+            DappsStaking::claim(Origin::signed(developer1), contract1, idle_era),
+            Error::<TestRuntime>::NotStaked
+        );
+    })
+}
+
+#[test]
+fn era_staked_matches_contract_stakes_for_random_operations() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let developers: Vec<AccountId> = vec![7, 8, 10];
+        let stakers: Vec<AccountId> = vec![1, 2, 3, 4, 5];
+        let contracts: Vec<_> = (1..=developers.len() as u8)
+            .map(|i| MockSmartContract::Evm(H160::repeat_byte(i)))
+            .collect();
+        for (developer, contract) in developers.iter().zip(contracts.iter()) {
+            register_contract(*developer, contract);
+        }
+
+        // Simple deterministic LCG so failures can be reproduced.
+        let mut seed: u64 = 0x5eed_da99;
+        let mut next = |bound: u64| -> u64 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) % bound
+        };
+
+        let mut unregistered = false;
+        for _ in 0..500 {
+            let staker = stakers[next(stakers.len() as u64) as usize];
+            let contract = contracts[next(contracts.len() as u64) as usize];
+            let value = (next(300) + 1) as Balance;
+
+            // Operations are allowed to fail, storage must remain consistent regardless.
+            match next(20) {
+                0..=5 => {
+                    let _ = DappsStaking::bond_and_stake(Origin::signed(staker), contract, value);
+                }
+                6..=10 => {
+                    let _ = DappsStaking::unbond_unstake_and_withdraw(
+                        Origin::signed(staker),
+                        contract,
+                        value,
+                    );
+                }
+                11..=12 => {
+                    let other_contract = contracts[next(contracts.len() as u64) as usize];
+                    let _ = DappsStaking::bond_and_stake_multi(
+                        Origin::signed(staker),
+                        staking_targets(vec![(contract, value), (other_contract, value)]),
+                    );
+                }
+                13 => {
+                    let _ = DappsStaking::unbond_all(Origin::signed(staker));
+                }
+                14 if !unregistered => {
+                    let index = next(contracts.len() as u64) as usize;
+                    assert_ok!(DappsStaking::unregister(
+                        Origin::signed(developers[index]),
+                        contracts[index]
+                    ));
+                    unregistered = true;
+                }
+                _ => run_for_blocks(1),
+            }
+
+            verify_era_staked_consistency(&contracts);
+            assert_ok!(DappsStaking::ensure_storage_invariants());
+        }
+
+        // Make sure the scenario actually covered multiple eras.
+        assert!(DappsStaking::current_era() > 10);
+    })
+}
+
+#[test]
+fn approx_top_contracts_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let other_staker_id = 2;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let third_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x03));
+        let fourth_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x04));
+
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        register_contract(7, &third_contract_id);
+        register_contract(8, &fourth_contract_id);
+        assert!(DappsStaking::approx_top_contracts().is_empty());
+
+        // Contracts are sorted by stake, contracts which reached the same stake earlier come first.
+        bond_and_stake_with_verification(staker_id, &first_contract_id, 100);
+        bond_and_stake_with_verification(staker_id, &second_contract_id, 200);
+        bond_and_stake_with_verification(other_staker_id, &third_contract_id, 100);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![
+                (second_contract_id, 200),
+                (first_contract_id, 100),
+                (third_contract_id, 100),
+            ]
+        );
+
+        // Index is bounded so contract with the least stake doesn't fit.
+        bond_and_stake_with_verification(other_staker_id, &fourth_contract_id, 50);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().len() as u32,
+            MAX_TOP_CONTRACTS
+        );
+        assert!(DappsStaking::approx_top_contracts()
+            .iter()
+            .all(|(contract, _)| *contract != fourth_contract_id));
+
+        // Increasing the stake moves contract up.
+        bond_and_stake_with_verification(other_staker_id, &fourth_contract_id, 100);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![
+                (second_contract_id, 200),
+                (fourth_contract_id, 150),
+                (first_contract_id, 100),
+            ]
+        );
+
+        // Decreasing the stake moves contract down.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &second_contract_id, 120);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![
+                (fourth_contract_id, 150),
+                (first_contract_id, 100),
+                (second_contract_id, 80),
+            ]
+        );
+
+        // Unregistered contract is removed from the index.
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(8),
+            fourth_contract_id
+        ));
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![(first_contract_id, 100), (second_contract_id, 80)]
+        );
+
+        // Contracts without stake are removed from the index as well.
+        assert_ok!(DappsStaking::unbond_all(Origin::signed(staker_id)));
+        assert!(DappsStaking::approx_top_contracts().is_empty());
+    })
+}
+
+#[test]
+fn approx_top_contracts_does_not_promote_dropped_contracts() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let contracts: Vec<_> = (1..=4)
+            .map(|i| MockSmartContract::Evm(H160::repeat_byte(i)))
+            .collect();
+        for (developer, contract) in [10, 20, 7, 8].iter().zip(contracts.iter()) {
+            register_contract(*developer, contract);
+        }
+        for (contract, amount) in contracts.iter().zip([300, 200, 100, 50].iter()) {
+            bond_and_stake_with_verification(staker_id, contract, *amount);
+        }
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![
+                (contracts[0], 300),
+                (contracts[1], 200),
+                (contracts[2], 100)
+            ]
+        );
+
+        // Dropped contract isn't promoted back when an indexed contract loses stake,
+        // so the index only approximates the contracts with the most stake.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &contracts[2], 80);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![(contracts[0], 300), (contracts[1], 200), (contracts[2], 20)]
+        );
+
+        // Dropped contract is considered again once its stake changes.
+        bond_and_stake_with_verification(staker_id, &contracts[3], 10);
+        assert_eq!(
+            DappsStaking::approx_top_contracts().into_inner(),
+            vec![(contracts[0], 300), (contracts[1], 200), (contracts[3], 60)]
+        );
+    })
+}
+
+#[test]
+fn mint_and_burn_staking_receipt_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(10, &contract_id);
+        bond_and_stake_with_verification(staker_id, &contract_id, 100);
+
+        // Minting is disabled by default.
+        assert_noop!(
+            DappsStaking::mint_staking_receipt(Origin::signed(staker_id), contract_id),
+            Error::<TestRuntime>::StakingReceiptsDisabled
+        );
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), true));
+
+        // Receipt can only be minted for an existing position.
+        assert_noop!(
+            DappsStaking::mint_staking_receipt(Origin::signed(2), contract_id),
+            Error::<TestRuntime>::NotStakedContract
+        );
+
+        assert_ok!(DappsStaking::mint_staking_receipt(
+            Origin::signed(staker_id),
+            contract_id
+        ));
+        System::assert_last_event(mock::Event::DappsStaking(Event::StakingReceiptMinted(
+            staker_id,
+            contract_id,
+        )));
+        assert!(DappsStaking::staking_receipt(staker_id, contract_id).is_some());
+        assert_noop!(
+            DappsStaking::mint_staking_receipt(Origin::signed(staker_id), contract_id),
+            Error::<TestRuntime>::AlreadyMintedReceipt
+        );
+
+        // Burning the receipt doesn't affect the position.
+        assert_ok!(DappsStaking::burn_staking_receipt(
+            Origin::signed(staker_id),
+            contract_id
+        ));
+        System::assert_last_event(mock::Event::DappsStaking(Event::StakingReceiptBurned(
+            staker_id,
+            contract_id,
+        )));
+        assert!(DappsStaking::staking_receipt(staker_id, contract_id).is_none());
+        verify_ledger(staker_id, 100);
+        assert_noop!(
+            DappsStaking::burn_staking_receipt(Origin::signed(staker_id), contract_id),
+            Error::<TestRuntime>::NoStakingReceipt
+        );
+    })
+}
+
+#[test]
+fn staking_receipt_is_removed_with_position() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let other_staker_id = 2;
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(10, &contract_id);
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), true));
+
+        for staker in [staker_id, other_staker_id].iter() {
+            bond_and_stake_with_verification(*staker, &contract_id, 100);
+            assert_ok!(DappsStaking::mint_staking_receipt(
+                Origin::signed(*staker),
+                contract_id
+            ));
+        }
+
+        // Partial unstake keeps the receipt.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &contract_id, 50);
+        assert!(DappsStaking::staking_receipt(staker_id, contract_id).is_some());
+
+        // Receipt is removed together with the position.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &contract_id, 50);
+        assert!(DappsStaking::staking_receipt(staker_id, contract_id).is_none());
+
+        // Unregistering the contract removes all the positions.
+        assert_ok!(DappsStaking::unregister(Origin::signed(10), contract_id));
+        assert!(DappsStaking::staking_receipt(other_staker_id, contract_id).is_none());
+    })
+}
+
+#[test]
+fn transfer_staking_receipt_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let owner = 1;
+        let receiver = 30;
+        let other_staker_id = 2;
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(10, &contract_id);
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), true));
+
+        bond_and_stake_with_verification(owner, &contract_id, 100);
+        bond_and_stake_with_verification(other_staker_id, &contract_id, 50);
+
+        assert_noop!(
+            DappsStaking::transfer_staking_receipt(Origin::signed(owner), contract_id, receiver),
+            Error::<TestRuntime>::NoStakingReceipt
+        );
+        assert_ok!(DappsStaking::mint_staking_receipt(
+            Origin::signed(owner),
+            contract_id
+        ));
+        assert_noop!(
+            DappsStaking::transfer_staking_receipt(Origin::signed(owner), contract_id, owner),
+            Error::<TestRuntime>::ReceiptTransferToSelf
+        );
+
+        // Let the owner earn rewards for an entire era before transferring the position.
+        let owner_era = DappsStaking::current_era() + 1;
+        advance_to_era(owner_era + 1);
+        let current_era = DappsStaking::current_era();
+
+        let owner_balance = Balances::free_balance(&owner);
+        assert_ok!(DappsStaking::transfer_staking_receipt(
+            Origin::signed(owner),
+            contract_id,
+            receiver
+        ));
+        System::assert_last_event(mock::Event::DappsStaking(Event::StakingReceiptTransferred(
+            owner,
+            receiver,
+            contract_id,
+            100,
+        )));
+
+        // Funds and locks follow the position.
+        verify_ledger(owner, 0);
+        verify_ledger(receiver, 100);
+        assert_eq!(Balances::free_balance(&owner), owner_balance - 100);
+        assert_eq!(Balances::free_balance(&receiver), 100);
+        assert!(Balances::usable_balance(&receiver).is_zero());
+        assert!(DappsStaking::staking_receipt(owner, contract_id).is_none());
+        assert!(DappsStaking::staking_receipt(receiver, contract_id).is_some());
+
+        // Position is moved starting with the current era.
+        for era in [current_era, current_era + 1].iter() {
+            verify_era_staking_points(
+                &contract_id,
+                150,
+                *era,
+                vec![(receiver, 100), (other_staker_id, 50)],
+            );
+        }
+        verify_era_staking_points(
+            &contract_id,
+            150,
+            owner_era,
+            vec![(owner, 100), (other_staker_id, 50)],
+        );
+        verify_era_staked_consistency(&[contract_id]);
+        assert_ok!(DappsStaking::ensure_storage_invariants());
+
+        // Rewards of the previous eras stay with the owner while the future ones go to the receiver.
+        advance_to_era(current_era + 1);
+        let rewarded_accounts = || -> Vec<AccountId> {
+            dapps_staking_events()
+                .into_iter()
+                .filter_map(|e| {
+                    if let crate::Event::Reward(account, ..) = e {
+                        Some(account)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        claim_with_verification(owner, contract_id, owner_era);
+        assert!(rewarded_accounts().contains(&owner));
+        assert!(!rewarded_accounts().contains(&receiver));
+
+        claim_with_verification(owner, contract_id, current_era);
+        assert!(!rewarded_accounts().contains(&owner));
+        assert!(rewarded_accounts().contains(&receiver));
+    })
+}
+
+#[test]
+fn transfer_staking_receipt_merges_positions() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let owner = 1;
+        let receiver = 2;
+        let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        register_contract(10, &contract_id);
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), true));
+
+        bond_and_stake_with_verification(owner, &contract_id, 100);
+        bond_and_stake_with_verification(receiver, &contract_id, 50);
+        assert_ok!(DappsStaking::mint_staking_receipt(
+            Origin::signed(owner),
+            contract_id
+        ));
+
+        // Minting can be disabled without affecting the existing receipts.
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), false));
+        take_hook_calls();
+
+        assert_ok!(DappsStaking::transfer_staking_receipt(
+            Origin::signed(owner),
+            contract_id,
+            receiver
+        ));
+
+        verify_ledger(owner, 0);
+        verify_ledger(receiver, 150);
+        verify_era_staking_points(
+            &contract_id,
+            150,
+            DappsStaking::current_era() + 1,
+            vec![(receiver, 150)],
+        );
+        assert!(!StakerPositions::<TestRuntime>::contains_key(owner));
+        assert_eq!(
+            DappsStaking::staker_positions(receiver).into_inner(),
+            vec![(contract_id, 150)]
+        );
+        assert_eq!(
+            take_hook_calls(),
+            vec![
+                HookCall::StakeChanged(owner, contract_id, 0),
+                HookCall::StakeChanged(receiver, contract_id, 150),
+            ]
+        );
+    })
+}
+
+#[test]
+fn staker_positions_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let third_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x03));
+
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        register_contract(7, &third_contract_id);
+        assert!(DappsStaking::staker_positions(staker_id).is_empty());
+
+        bond_and_stake_with_verification(staker_id, &first_contract_id, 100);
+        bond_and_stake_with_verification(staker_id, &first_contract_id, 50);
+        bond_and_stake_multi_with_verification(
+            staker_id,
+            vec![(second_contract_id, 200), (third_contract_id, 300)],
+        );
+        assert_eq!(
+            DappsStaking::staker_positions(staker_id).into_inner(),
+            vec![
+                (first_contract_id, 150),
+                (second_contract_id, 200),
+                (third_contract_id, 300),
+            ]
+        );
+
+        // Positions are updated on unstaking and removed once nothing is staked on the contract.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &first_contract_id, 30);
+        unbond_unstake_and_withdraw_with_verification(staker_id, &second_contract_id, 200);
+        assert_eq!(
+            DappsStaking::staker_positions(staker_id).into_inner(),
+            vec![(first_contract_id, 120), (third_contract_id, 300)]
+        );
+        assert_ok!(DappsStaking::ensure_storage_invariants());
+
+        // Unregistering the contract removes it from the positions of its stakers.
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(7),
+            third_contract_id
+        ));
+        assert_eq!(
+            DappsStaking::staker_positions(staker_id).into_inner(),
+            vec![(first_contract_id, 120)]
+        );
+        assert_eq!(
+            DappsStaking::staker_contract_stake(&staker_id, &third_contract_id),
+            0
+        );
+
+        assert_ok!(DappsStaking::unbond_all(Origin::signed(staker_id)));
+        assert!(!StakerPositions::<TestRuntime>::contains_key(staker_id));
+        assert_ok!(DappsStaking::ensure_storage_invariants());
+    })
+}
+
+#[test]
+fn too_many_staked_contracts_is_not_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let contracts: Vec<_> = (1..=MAX_STAKED_CONTRACTS_PER_STAKER as u8 + 1)
+            .map(|x| MockSmartContract::Evm(H160::repeat_byte(x)))
+            .collect();
+        for (developer, contract_id) in [10, 20, 7, 8, 9].iter().zip(contracts.iter()) {
+            register_contract(*developer, contract_id);
+        }
+
+        let (last_contract_id, staked_contracts) = contracts.split_last().unwrap();
+        for contract_id in staked_contracts {
+            bond_and_stake_with_verification(staker_id, contract_id, 100);
+        }
+
+        assert_noop!(
+            DappsStaking::bond_and_stake(Origin::signed(staker_id), *last_contract_id, 100),
+            Error::<TestRuntime>::TooManyStakedContracts
+        );
+        assert_noop!(
+            DappsStaking::bond_and_stake_multi(
+                Origin::signed(staker_id),
+                staking_targets(vec![(contracts[0], 100), (*last_contract_id, 100)]),
+            ),
+            Error::<TestRuntime>::TooManyStakedContracts
+        );
+
+        // Stake can still be increased on the contracts which are already staked on.
+        bond_and_stake_with_verification(staker_id, &contracts[0], 100);
+
+        // Once a position is closed, staker can stake on another contract.
+        unbond_unstake_and_withdraw_with_verification(staker_id, &contracts[1], 100);
+        bond_and_stake_with_verification(staker_id, last_contract_id, 100);
+        assert_eq!(
+            DappsStaking::staker_positions(staker_id).len() as u32,
+            MAX_STAKED_CONTRACTS_PER_STAKER
+        );
+    })
+}
+
+#[test]
+fn transfer_staking_receipt_to_staker_with_too_many_contracts_is_not_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let owner = 1;
+        let receiver = 3;
+        let contracts: Vec<_> = (1..=MAX_STAKED_CONTRACTS_PER_STAKER as u8 + 1)
+            .map(|x| MockSmartContract::Evm(H160::repeat_byte(x)))
+            .collect();
+        for (developer, contract_id) in [10, 20, 7, 8, 9].iter().zip(contracts.iter()) {
+            register_contract(*developer, contract_id);
+        }
+        assert_ok!(DappsStaking::enable_staking_receipts(Origin::root(), true));
+
+        let (last_contract_id, staked_contracts) = contracts.split_last().unwrap();
+        for contract_id in staked_contracts {
+            bond_and_stake_with_verification(receiver, contract_id, 100);
+        }
+        bond_and_stake_with_verification(owner, last_contract_id, 100);
+        assert_ok!(DappsStaking::mint_staking_receipt(
+            Origin::signed(owner),
+            *last_contract_id
+        ));
+
+        assert_noop!(
+            DappsStaking::transfer_staking_receipt(
+                Origin::signed(owner),
+                *last_contract_id,
+                receiver
+            ),
+            Error::<TestRuntime>::TooManyStakedContracts
+        );
+    })
+}
+
+#[test]
+fn staker_positions_migration_is_ok() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let first_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+        let second_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x02));
+        let third_contract_id = MockSmartContract::Evm(H160::repeat_byte(0x03));
+        register_contract(10, &first_contract_id);
+        register_contract(20, &second_contract_id);
+        register_contract(7, &third_contract_id);
+
+        bond_and_stake_with_verification(1, &first_contract_id, 100);
+        bond_and_stake_with_verification(1, &second_contract_id, 200);
+        bond_and_stake_with_verification(2, &second_contract_id, 300);
+        bond_and_stake_with_verification(2, &third_contract_id, 400);
+        advance_to_era(DappsStaking::current_era() + 1);
+        bond_and_stake_with_verification(1, &first_contract_id, 50);
+        assert_ok!(DappsStaking::unregister(
+            Origin::signed(7),
+            third_contract_id
+        ));
+
+        // Simulate storage prior to the introduction of the staker positions index.
+        let positions: Vec<_> = StakerPositions::<TestRuntime>::iter().collect();
+        StakerPositions::<TestRuntime>::remove_all(None);
+        StorageVersion::new(0).put::<DappsStaking>();
+        assert_ok!(DappsStaking::ensure_staker_positions_migratable());
+
+        DappsStaking::on_runtime_upgrade();
+
+        assert_eq!(
+            StorageVersion::get::<DappsStaking>(),
+            StorageVersion::new(1)
+        );
+        for (staker, staker_positions) in positions {
+            let mut migrated_positions = DappsStaking::staker_positions(staker).into_inner();
+            let mut expected_positions = staker_positions.into_inner();
+            migrated_positions.sort_by_key(|(contract_id, _)| contract_id.encode());
+            expected_positions.sort_by_key(|(contract_id, _)| contract_id.encode());
+            assert_eq!(migrated_positions, expected_positions);
+        }
+        assert_eq!(DappsStaking::staker_positions(1).into_inner().len(), 2);
+        assert_ok!(DappsStaking::ensure_storage_invariants());
+    })
+}
+
+#[test]
+fn staker_positions_migration_with_too_many_staked_contracts() {
+    ExternalityBuilder::build().execute_with(|| {
+        initialize_first_block();
+
+        let staker_id = 1;
+        let contracts: Vec<_> = (1..=MAX_STAKED_CONTRACTS_PER_STAKER as u8 + 1)
+            .map(|x| MockSmartContract::Evm(H160::repeat_byte(x)))
+            .collect();
+        for (developer, contract_id) in [10, 20, 7, 8, 9].iter().zip(contracts.iter()) {
+            register_contract(*developer, contract_id);
+        }
+        for contract_id in contracts
+            .iter()
+            .take(MAX_STAKED_CONTRACTS_PER_STAKER as usize)
+        {
+            bond_and_stake_with_verification(staker_id, contract_id, 100);
+        }
+        bond_and_stake_with_verification(2, &contracts[0], 100);
+
+        // Stake which existed prior to the introduction of the limit.
+        let last_contract_id = contracts.last().unwrap();
+        let next_era = DappsStaking::current_era() + 1;
+        let mut staking_info = DappsStaking::staking_info(last_contract_id, next_era);
+        staking_info.stakers.insert(staker_id, 100);
+        staking_info.total += 100;
+        ContractEraStake::<TestRuntime>::insert(last_contract_id, next_era, staking_info);
+
+        StakerPositions::<TestRuntime>::remove_all(None);
+        StorageVersion::new(0).put::<DappsStaking>();
+        assert!(DappsStaking::ensure_staker_positions_migratable().is_err());
+
+        DappsStaking::on_runtime_upgrade();
+
+        // Staker isn't partially indexed, others are migrated.
+        assert!(DappsStaking::staker_positions(staker_id).is_empty());
+        assert_eq!(
+            DappsStaking::staker_positions(2).into_inner(),
+            vec![(contracts[0].clone(), 100)]
+        );
+    })
+}
+
+#[test]
+fn storage_invariants_detect_inconsistencies() {
+    let staker_id = 1;
+    let developer = 10;
+    let contract_id = MockSmartContract::Evm(H160::repeat_byte(0x01));
+
+    // Prepares consistent storage with a single staked contract.
+    let build = || {
+        let mut ext = ExternalityBuilder::build();
+        ext.execute_with(|| {
+            initialize_first_block();
+            register_contract(developer, &contract_id);
+            bond_and_stake_with_verification(staker_id, &contract_id, 100);
+            advance_to_era(DappsStaking::current_era() + 2);
+            assert_ok!(DappsStaking::ensure_storage_invariants());
+        });
+        ext
+    };
+
+    // Ledger doesn't match staker positions.
+    build().execute_with(|| {
+        Ledger::<TestRuntime>::insert(staker_id, 90);
+        assert!(DappsStaking::ensure_storage_invariants().is_err());
+    });
+
+    // Staker positions index doesn't match contract stakers.
+    build().execute_with(|| {
+        StakerPositions::<TestRuntime>::remove(staker_id);
+        assert!(DappsStaking::ensure_storage_invariants().is_err());
+    });
+
+    // Era stake doesn't match contract stakes.
+    build().execute_with(|| {
+        let current_era = DappsStaking::current_era();
+        EraRewardsAndStakes::<TestRuntime>::mutate(current_era, |value| {
+            value.as_mut().unwrap().staked = 50
+        });
+        assert!(DappsStaking::ensure_storage_invariants().is_err());
+    });
+
+    // Contract stake doesn't match the sum of its stakers.
+    build().execute_with(|| {
+        let next_era = DappsStaking::current_era() + 1;
+        let mut staking_info = DappsStaking::staking_info(&contract_id, next_era);
+        staking_info.stakers.insert(2, 10);
+        ContractEraStake::<TestRuntime>::insert(&contract_id, next_era, staking_info);
+        assert!(DappsStaking::ensure_storage_invariants().is_err());
+    });
+
+    // Registered developer and dapp don't point at each other.
+    build().execute_with(|| {
+        RegisteredDapps::<TestRuntime>::insert(&contract_id, 20);
+        assert!(DappsStaking::ensure_storage_invariants().is_err());
+    });
+}
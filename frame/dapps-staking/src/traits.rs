@@ -1,5 +1,84 @@
+use crate::EraIndex;
+use frame_support::weights::Weight;
+
 // TODO: document this and sort it out
 pub trait IsContract: Default {
     /// Used to check whether the struct represents a valid contract or not.
     fn is_valid(&self) -> bool;
 }
+
+/// Callbacks invoked by dapps staking whenever staking positions or rewards change.
+///
+/// Allows other pallets to follow dapps staking state without having to parse events.
+/// All the callbacks are infallible and are invoked after the storage has been updated.
+/// Their weight is included in the weight of the dispatchables invoking them, as many times as
+/// they can be invoked in the worst case.
+pub trait DappsStakingHooks<AccountId, SmartContract, Balance> {
+    /// Called when amount staked by `staker` on `contract` changes.
+    /// `staked` is the new total amount staked by the staker on the contract, zero if the position was exited.
+    fn on_stake_changed(staker: &AccountId, contract: &SmartContract, staked: Balance);
+
+    /// Called when `contract` has been unregistered.
+    /// `on_stake_changed` is called for each of its stakers beforehand.
+    fn on_contract_unregistered(contract: &SmartContract);
+
+    /// Called when `reward` for `era` has been paid out to `account`, either a staker or the developer of `contract`.
+    fn on_reward_paid(
+        account: &AccountId,
+        contract: &SmartContract,
+        era: EraIndex,
+        reward: Balance,
+    );
+
+    /// Weight of a single `on_stake_changed` call.
+    fn on_stake_changed_weight() -> Weight;
+
+    /// Weight of a single `on_contract_unregistered` call.
+    fn on_contract_unregistered_weight() -> Weight;
+
+    /// Weight of a single `on_reward_paid` call.
+    fn on_reward_paid_weight() -> Weight;
+}
+
+impl<AccountId, SmartContract, Balance> DappsStakingHooks<AccountId, SmartContract, Balance>
+    for ()
+{
+    fn on_stake_changed(_: &AccountId, _: &SmartContract, _: Balance) {}
+    fn on_contract_unregistered(_: &SmartContract) {}
+    fn on_reward_paid(_: &AccountId, _: &SmartContract, _: EraIndex, _: Balance) {}
+    fn on_stake_changed_weight() -> Weight {
+        0
+    }
+    fn on_contract_unregistered_weight() -> Weight {
+        0
+    }
+    fn on_reward_paid_weight() -> Weight {
+        0
+    }
+}
+
+/// Read-only view on the dapps staking positions.
+///
+/// Values which aren't tied to an era include the stake which becomes effective in the next era.
+pub trait DappsStakingInfo<AccountId, SmartContract, Balance> {
+    /// Total amount staked by `staker` on all contracts.
+    fn staker_total_stake(staker: &AccountId) -> Balance;
+
+    /// Amount staked by `staker` on `contract`.
+    fn staker_contract_stake(staker: &AccountId, contract: &SmartContract) -> Balance;
+
+    /// Total amount staked on `contract` by all of its stakers.
+    fn contract_total_stake(contract: &SmartContract) -> Balance;
+
+    /// Total amount staked on all contracts.
+    fn total_staked() -> Balance;
+
+    /// Index of the era which is in progress.
+    fn current_era() -> EraIndex;
+
+    /// Total amount staked on `contract` during `era`.
+    fn contract_era_stake(contract: &SmartContract, era: EraIndex) -> Balance;
+
+    /// Total amount staked on all contracts during `era`.
+    fn era_total_stake(era: EraIndex) -> Balance;
+}
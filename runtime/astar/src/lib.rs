@@ -7,7 +7,7 @@
 use codec::{Decode, Encode};
 use frame_support::{
     construct_runtime, parameter_types,
-    traits::{Contains, Currency, FindAuthor, Imbalance, OnUnbalanced},
+    traits::{Contains, Currency, FindAuthor, OnUnbalanced},
     weights::{
         constants::{BlockExecutionWeight, ExtrinsicBaseWeight, WEIGHT_PER_SECOND},
         DispatchClass, Weight, WeightToFeeCoefficient, WeightToFeeCoefficients,
@@ -16,6 +16,7 @@ use frame_support::{
     ConsensusEngineId, PalletId,
 };
 use frame_system::limits::{BlockLength, BlockWeights};
+use pallet_block_reward::RewardDistributionConfig;
 use pallet_evm::{FeeCalculator, Runner};
 use pallet_transaction_payment::{
     FeeDetails, Multiplier, RuntimeDispatchInfo, TargetedFeeAdjustment,
@@ -30,7 +31,7 @@ use sp_runtime::{
         AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
         OpaqueKeys, Verify,
     },
-    transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
+    transaction_validity::{TransactionSource, TransactionValidity},
     ApplyExtrinsicResult, FixedPointNumber, Perbill, Perquintill,
 };
 use sp_std::prelude::*;
@@ -245,21 +246,25 @@ impl pallet_multisig::Config for Runtime {
 }
 
 parameter_types! {
-    pub const EcdsaUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
-    pub const CallFee: Balance = ASTR / 10;
     pub const CallMagicNumber: u16 = 0x0250;
+    pub const MaxBatchCalls: u32 = 16;
+    pub CustomSignaturesFeeMultiplier: Multiplier = TransactionPayment::next_fee_multiplier();
 }
 
 impl pallet_custom_signatures::Config for Runtime {
     type Event = Event;
     type Call = Call;
+    // Foreign wallet signatures are enabled on Shibuya first
     type Signature = pallet_custom_signatures::ethereum::EthereumSignature;
     type Signer = <Signature as Verify>::Signer;
+    type SignedMessage = pallet_custom_signatures::ScaleMessage;
     type CallMagicNumber = CallMagicNumber;
+    type MaxBatchCalls = MaxBatchCalls;
     type Currency = Balances;
-    type CallFee = CallFee;
+    type WeightToFee = WeightToFee;
+    type TransactionByteFee = TransactionByteFee;
+    type FeeMultiplier = CustomSignaturesFeeMultiplier;
     type OnChargeTransaction = ToStakingPot;
-    type UnsignedPriority = EcdsaUnsignedPriority;
 }
 
 impl pallet_utility::Config for Runtime {
@@ -306,7 +311,7 @@ impl pallet_authorship::Config for Runtime {
     type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
     type UncleGenerations = UncleGenerations;
     type FilterUncle = ();
-    type EventHandler = (CollatorSelection,);
+    type EventHandler = (CollatorSelection, BlockReward);
 }
 
 parameter_types! {
@@ -364,29 +369,48 @@ impl OnUnbalanced<NegativeImbalance> for ToStakingPot {
     }
 }
 
-pub struct OnBlockReward;
-impl OnUnbalanced<NegativeImbalance> for OnBlockReward {
+pub struct ToTreasury;
+impl OnUnbalanced<NegativeImbalance> for ToTreasury {
     fn on_nonzero_unbalanced(amount: NegativeImbalance) {
-        let (dapps, maintain) = amount.ration(50, 50);
-        // dapp staking block reward
-        Balances::resolve_creating(&DappsStakingPalletId::get().into_account(), dapps);
-
-        let (treasury, collators) = maintain.ration(40, 10);
-        // treasury slice of block reward
-        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), treasury);
-        // collators block reward
-        ToStakingPot::on_unbalanced(collators);
+        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), amount);
+    }
+}
+
+pub struct ToDappsStaking;
+impl OnUnbalanced<NegativeImbalance> for ToDappsStaking {
+    fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+        Balances::resolve_creating(&DappsStakingPalletId::get().into_account(), amount);
     }
 }
 
 parameter_types! {
-    pub const RewardAmount: Balance = 266_400 * MILLIASTR;
+    /// Block reward used until the reward amount was moved to storage.
+    pub const InitialRewardAmount: Balance = 266_400 * MILLIASTR;
+    /// Number of blocks in an era for which the block reward records are kept.
+    pub const BlockPerEra: BlockNumber = 1 * DAYS;
+    /// Number of eras for which the block reward records are kept.
+    pub const HistoryDepth: u32 = 14;
+    /// Block reward split used until the distribution was moved to storage.
+    pub InitialRewardDistribution: RewardDistributionConfig = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(40),
+        dapps_percent: Perbill::from_percent(50),
+        collators_percent: Perbill::from_percent(10),
+        burn_percent: Perbill::zero(),
+    };
 }
 
 impl pallet_block_reward::Config for Runtime {
     type Currency = Balances;
-    type OnBlockReward = OnBlockReward;
-    type RewardAmount = RewardAmount;
+    type TreasuryPayout = ToTreasury;
+    type DappsStakingPayout = ToDappsStaking;
+    type CollatorsPayout = ToStakingPot;
+    type DappsStakingTvl = ();
+    type RewardCalculator = pallet_block_reward::FixedReward;
+    type CurrentEra = pallet_block_reward::EraOfBlock<Runtime, BlockPerEra>;
+    type HistoryDepth = HistoryDepth;
+    type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+    type Event = Event;
+    type WeightInfo = pallet_block_reward::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -607,7 +631,7 @@ construct_runtime!(
         TransactionPayment: pallet_transaction_payment::{Pallet, Storage} = 30,
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>} = 31,
         Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>} = 32,
-        BlockReward: pallet_block_reward::{Pallet} = 33,
+        BlockReward: pallet_block_reward::{Pallet, Call, Storage, Config<T>, Event<T>} = 33,
 
         Authorship: pallet_authorship::{Pallet, Call, Storage, Inherent} = 40,
         CollatorSelection: pallet_collator_selection::{Pallet, Call, Storage, Event<T>, Config<T>} = 41,
@@ -669,6 +693,13 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPallets,
+    (
+        pallet_block_reward::migrations::MigrateRewardAmountToStorage<Runtime, InitialRewardAmount>,
+        pallet_block_reward::migrations::MigrateRewardDistributionToStorage<
+            Runtime,
+            InitialRewardDistribution,
+        >,
+    ),
 >;
 
 impl_runtime_apis! {
@@ -754,6 +785,22 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_custom_signatures_rpc_runtime_api::CustomSignaturesApi<
+        Block,
+        Index,
+        Balance,
+    > for Runtime {
+        fn signing_payload(call: Vec<u8>) -> Result<
+            pallet_custom_signatures::SigningPayload<Index, Balance>,
+            pallet_custom_signatures::PayloadError,
+        > {
+            EthCall::signing_payload(call)
+        }
+        fn dry_run(call: Vec<u8>) -> Result<(), pallet_custom_signatures::PayloadError> {
+            EthCall::dry_run(call)
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             SessionKeys::generate(seed)
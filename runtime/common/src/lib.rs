@@ -0,0 +1,185 @@
+//! Functionality shared by the Astar runtimes.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Compact, CompactLen, Decode};
+use frame_support::{
+    traits::{Get, PalletInfo},
+    weights::DispatchClass,
+};
+use frame_system::EventRecord;
+use sp_std::{convert::TryInto, marker::PhantomData, prelude::*, vec};
+
+/// Position in the events deposited during the current block.
+///
+/// Runtime logic mustn't read `System::events()` since it decodes all the events of the block.
+/// The events deposited after the mark are read on their own instead.
+pub struct EventsMark<T> {
+    /// Number of events deposited before the mark.
+    count: u32,
+    /// Encoded length of the events deposited before the mark, including the length prefix.
+    len: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: frame_system::Config> EventsMark<T> {
+    /// Marks the end of the events deposited so far.
+    pub fn current() -> Self {
+        Self {
+            count: frame_system::Pallet::<T>::event_count(),
+            len: sp_io::storage::read(&events_key::<T>(), &mut [], 0).unwrap_or(0),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the events deposited after the mark.
+    ///
+    /// Weight of the storage read is registered as extra weight of the block.
+    pub fn events_since(&self) -> Vec<EventRecord<T::Event, T::Hash>> {
+        frame_system::Pallet::<T>::register_extra_weight_unchecked(
+            T::DbWeight::get().reads(1),
+            DispatchClass::Normal,
+        );
+
+        let count = frame_system::Pallet::<T>::event_count();
+        let new_events = count.saturating_sub(self.count);
+        if new_events == 0 {
+            return Vec::new();
+        }
+
+        // Events are appended to an encoded vector, so only its length prefix in front of the
+        // events deposited before the mark can change.
+        let offset = self
+            .len
+            .saturating_sub(Compact::<u32>::compact_len(&self.count) as u32)
+            .saturating_add(Compact::<u32>::compact_len(&count) as u32);
+        let key = events_key::<T>();
+        let len = sp_io::storage::read(&key, &mut [], offset).unwrap_or(0);
+        let mut encoded = vec![0; len as usize];
+        sp_io::storage::read(&key, &mut encoded, offset);
+
+        let mut input = &encoded[..];
+        let mut events = Vec::with_capacity(new_events as usize);
+        for _ in 0..new_events {
+            match EventRecord::decode(&mut input) {
+                Ok(event) => events.push(event),
+                Err(_) => break,
+            }
+        }
+        events
+    }
+}
+
+/// Storage key of the events deposited during the current block.
+fn events_key<T: frame_system::Config>() -> [u8; 32] {
+    let pallet_name = T::PalletInfo::name::<frame_system::Pallet<T>>().unwrap_or("System");
+    frame_support::storage::storage_prefix(pallet_name.as_bytes(), b"Events")
+}
+
+/// Returns whether the Ethereum transaction which deposited the given events exited successfully.
+///
+/// `pallet_ethereum::transact` succeeds even if the EVM execution is reverted,
+/// the exit reason is only reported by the `Executed` event.
+pub fn ethereum_transaction_succeeded<Event, Hash>(events: &[EventRecord<Event, Hash>]) -> bool
+where
+    Event: Clone + TryInto<pallet_ethereum::Event>,
+{
+    events
+        .iter()
+        .rev()
+        .find_map(|record| match record.event.clone().try_into() {
+            Ok(pallet_ethereum::Event::Executed(_, _, _, reason)) => Some(reason.is_succeed()),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_support::{construct_runtime, parameter_types};
+    use sp_core::H256;
+    use sp_runtime::{
+        testing::Header,
+        traits::{BlakeTwo256, IdentityLookup},
+    };
+
+    type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<TestRuntime>;
+    type Block = frame_system::mocking::MockBlock<TestRuntime>;
+
+    construct_runtime!(
+        pub enum TestRuntime where
+            Block = Block,
+            NodeBlock = Block,
+            UncheckedExtrinsic = UncheckedExtrinsic,
+        {
+            System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+        }
+    );
+
+    parameter_types! {
+        pub const BlockHashCount: u64 = 250;
+    }
+
+    impl frame_system::Config for TestRuntime {
+        type BaseCallFilter = frame_support::traits::Everything;
+        type BlockWeights = ();
+        type BlockLength = ();
+        type Origin = Origin;
+        type Index = u64;
+        type Call = Call;
+        type BlockNumber = u64;
+        type Hash = H256;
+        type Hashing = BlakeTwo256;
+        type AccountId = u64;
+        type Lookup = IdentityLookup<Self::AccountId>;
+        type Header = Header;
+        type Event = Event;
+        type BlockHashCount = BlockHashCount;
+        type DbWeight = ();
+        type Version = ();
+        type PalletInfo = PalletInfo;
+        type AccountData = ();
+        type OnNewAccount = ();
+        type OnKilledAccount = ();
+        type SystemWeightInfo = ();
+        type SS58Prefix = ();
+        type OnSetCode = ();
+    }
+
+    #[test]
+    fn events_since_returns_new_events() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            // Events aren't deposited in the genesis block.
+            System::set_block_number(1);
+
+            let mark = EventsMark::<TestRuntime>::current();
+            assert!(mark.events_since().is_empty());
+
+            // Length prefix of the events grows by a byte once there are 64 of them.
+            for _ in 0..62 {
+                System::deposit_event(frame_system::Event::CodeUpdated);
+            }
+            let mark = EventsMark::<TestRuntime>::current();
+            assert!(mark.events_since().is_empty());
+
+            System::deposit_event(frame_system::Event::NewAccount(1));
+            System::deposit_event(frame_system::Event::KilledAccount(2));
+            System::deposit_event(frame_system::Event::Remarked(3, H256::zero()));
+
+            let events: Vec<_> = mark
+                .events_since()
+                .into_iter()
+                .map(|record| record.event)
+                .collect();
+            assert_eq!(
+                events,
+                vec![
+                    Event::System(frame_system::Event::NewAccount(1)),
+                    Event::System(frame_system::Event::KilledAccount(2)),
+                    Event::System(frame_system::Event::Remarked(3, H256::zero())),
+                ]
+            );
+        })
+    }
+}
@@ -6,18 +6,21 @@
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
+use astar_runtime_common::{ethereum_transaction_succeeded, EventsMark};
 use codec::{Decode, Encode};
 use frame_support::{
     construct_runtime, parameter_types,
-    traits::{Currency, FindAuthor, Imbalance, KeyOwnerProofSystem, Nothing, OnUnbalanced},
+    traits::{Currency, FindAuthor, Get, KeyOwnerProofSystem, Nothing, OnUnbalanced},
     weights::{
         constants::{RocksDbWeight, WEIGHT_PER_SECOND},
         IdentityFee, Weight,
     },
     ConsensusEngineId, PalletId,
 };
+use pallet_block_reward::RewardDistributionConfig;
 use pallet_contracts::weights::WeightInfo;
-use pallet_evm::{FeeCalculator, Runner};
+use pallet_custom_signatures::text;
+use pallet_evm::{AddressMapping, FeeCalculator, Runner};
 use pallet_grandpa::{fg_primitives, AuthorityList as GrandpaAuthorityList};
 use sp_api::impl_runtime_apis;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256};
@@ -27,9 +30,7 @@ use sp_runtime::{
         AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
         Dispatchable, IdentifyAccount, NumberFor, PostDispatchInfoOf, Verify,
     },
-    transaction_validity::{
-        TransactionPriority, TransactionSource, TransactionValidity, TransactionValidityError,
-    },
+    transaction_validity::{TransactionSource, TransactionValidity, TransactionValidityError},
     ApplyExtrinsicResult, MultiSignature, RuntimeDebug,
 };
 use sp_std::prelude::*;
@@ -40,7 +41,7 @@ use sp_version::RuntimeVersion;
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_grandpa::AuthorityId as GrandpaId;
 pub use pallet_timestamp::Call as TimestampCall;
-use pallet_transaction_payment::CurrencyAdapter;
+use pallet_transaction_payment::{CurrencyAdapter, Multiplier};
 pub use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
@@ -265,29 +266,58 @@ parameter_types! {
 
 type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
 
-pub struct OnBlockReward;
-impl OnUnbalanced<NegativeImbalance> for OnBlockReward {
+pub struct ToTreasury;
+impl OnUnbalanced<NegativeImbalance> for ToTreasury {
     fn on_nonzero_unbalanced(amount: NegativeImbalance) {
-        let (dapps, maintain) = amount.ration(50, 50);
+        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), amount);
+    }
+}
 
-        // dapp staking block reward
-        DappsStaking::on_unbalanced(dapps);
+/// Provides the total amount staked in dapps staking to the block reward pallet.
+pub struct DappsStakingTvl;
+impl pallet_block_reward::StakedAmountProvider<Balance> for DappsStakingTvl {
+    fn total_staked() -> Balance {
+        <DappsStaking as pallet_dapps_staking::DappsStakingInfo<
+            AccountId,
+            SmartContract<AccountId>,
+            Balance,
+        >>::total_staked()
+    }
+}
 
-        // XXX: skip block author reward, it's local runtime didn't
-        let (treasury, _) = maintain.ration(40, 10);
-        // treasury slice of block reward
-        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), treasury);
+/// Provides the dapps staking era to the block reward pallet.
+pub struct DappsStakingEra;
+impl Get<pallet_block_reward::EraIndex> for DappsStakingEra {
+    fn get() -> pallet_block_reward::EraIndex {
+        DappsStaking::current_era()
     }
 }
 
 parameter_types! {
-    pub const RewardAmount: Balance = 2_664 * MILLIAST;
+    /// Block reward used until the reward amount was moved to storage.
+    pub const InitialRewardAmount: Balance = 2_664 * MILLIAST;
+    /// Block reward split used until the distribution was moved to storage.
+    pub InitialRewardDistribution: RewardDistributionConfig = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(40),
+        dapps_percent: Perbill::from_percent(50),
+        collators_percent: Perbill::from_percent(10),
+        burn_percent: Perbill::zero(),
+    };
 }
 
 impl pallet_block_reward::Config for Runtime {
     type Currency = Balances;
-    type OnBlockReward = OnBlockReward;
-    type RewardAmount = RewardAmount;
+    type TreasuryPayout = ToTreasury;
+    type DappsStakingPayout = DappsStaking;
+    // XXX: skip block author reward, collators share is burned in local runtime
+    type CollatorsPayout = ();
+    type DappsStakingTvl = DappsStakingTvl;
+    type RewardCalculator = pallet_block_reward::FixedReward;
+    type CurrentEra = DappsStakingEra;
+    type HistoryDepth = HistoryDepth;
+    type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+    type Event = Event;
+    type WeightInfo = pallet_block_reward::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -297,6 +327,10 @@ parameter_types! {
     pub const MaxNumberOfStakersPerContract: u32 = 512;
     pub const MinimumStakingAmount: Balance = 10 * AST;
     pub const MinimumRemainingAmount: Balance = 1 * AST;
+    pub const MaxStakingTargetsPerCall: u32 = 16;
+    pub const MaxStakedContractsPerStaker: u32 = 100;
+    pub const MaxTopContracts: u32 = 50;
+    pub const UsageRewardPercentage: Perbill = Perbill::from_percent(10);
     pub const HistoryDepth: u32 = 14;
     pub const BonusEraDuration: u32 = 100;
 }
@@ -313,10 +347,29 @@ impl pallet_dapps_staking::Config for Runtime {
     type MinimumStakingAmount = MinimumStakingAmount;
     type PalletId = DappsStakingPalletId;
     type MinimumRemainingAmount = MinimumRemainingAmount;
+    type MaxStakingTargetsPerCall = MaxStakingTargetsPerCall;
+    type MaxStakedContractsPerStaker = MaxStakedContractsPerStaker;
+    type MaxTopContracts = MaxTopContracts;
+    type Hooks = ();
+    type UsageRewardPercentage = UsageRewardPercentage;
     type HistoryDepth = HistoryDepth;
     type BonusEraDuration = BonusEraDuration;
 }
 
+parameter_types! {
+    pub const MaxPollOptions: u32 = 16;
+}
+
+impl pallet_dapps_signalling::Config for Runtime {
+    type Event = Event;
+    type SmartContract = SmartContract<AccountId>;
+    type Balance = Balance;
+    type StakingInfo = DappsStaking;
+    type PollOrigin = frame_system::EnsureRoot<AccountId>;
+    type MaxPollOptions = MaxPollOptions;
+    type WeightInfo = pallet_dapps_signalling::weights::SubstrateWeight<Runtime>;
+}
+
 /// Multi-VM pointer to smart contract instance.
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug, scale_info::TypeInfo)]
 pub enum SmartContract<AccountId> {
@@ -465,21 +518,77 @@ impl pallet_ethereum::Config for Runtime {
 }
 
 parameter_types! {
-    pub const EcdsaUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
-    pub const CallFee: Balance = AST / 10;
     pub const CallMagicNumber: u16 = 0xff51;
+    pub const MaxBatchCalls: u32 = 16;
+    /// Chain name shown by the wallets in the EIP-712 domain and the text of signed calls.
+    pub const SignedCallChainName: &'static str = "Astar Local";
+    pub CustomSignaturesFeeMultiplier: Multiplier = TransactionPayment::next_fee_multiplier();
 }
 
 impl pallet_custom_signatures::Config for Runtime {
     type Event = Event;
     type Call = Call;
-    type Signature = pallet_custom_signatures::ethereum::EthereumSignature;
+    // Foreign wallet signatures are enabled on test networks only, Astar and Shiden accept
+    // Ethereum signatures until these are proven here
+    type Signature = pallet_custom_signatures::foreign::ForeignSignature;
     type Signer = <Signature as Verify>::Signer;
+    type SignedMessage = (
+        pallet_custom_signatures::ScaleMessage,
+        pallet_custom_signatures::eip712::Eip712Message<SignedCallChainName, ChainId>,
+        pallet_custom_signatures::text::TextMessage<SignedCallChainName, SignedCallArgs>,
+    );
     type CallMagicNumber = CallMagicNumber;
+    type MaxBatchCalls = MaxBatchCalls;
     type Currency = Balances;
-    type CallFee = CallFee;
+    type WeightToFee = IdentityFee<Balance>;
+    type TransactionByteFee = TransactionByteFee;
+    type FeeMultiplier = CustomSignaturesFeeMultiplier;
     type OnChargeTransaction = ();
-    type UnsignedPriority = EcdsaUnsignedPriority;
+}
+
+/// Renders the arguments of common calls by name in the text of signed calls, other calls
+/// are rendered as hex.
+pub struct SignedCallArgs;
+
+impl SignedCallArgs {
+    fn address(address: &Address) -> Vec<u8> {
+        match address {
+            sp_runtime::MultiAddress::Id(account) => text::hex_string(account.as_ref()),
+            _ => text::hex_string(&address.encode()),
+        }
+    }
+
+    fn balance(balance: Balance) -> Vec<u8> {
+        pallet_custom_signatures::eip712::decimal_string(balance)
+    }
+}
+
+impl text::RenderArgs<Call> for SignedCallArgs {
+    fn render_args(call: &Call) -> Vec<u8> {
+        match call {
+            Call::Balances(pallet_balances::Call::transfer { dest, value })
+            | Call::Balances(pallet_balances::Call::transfer_keep_alive { dest, value }) => {
+                text::render_fields(&[
+                    (&b"dest"[..], Self::address(dest)),
+                    (&b"value"[..], Self::balance(*value)),
+                ])
+            }
+            Call::Balances(pallet_balances::Call::transfer_all { dest, keep_alive }) => {
+                text::render_fields(&[
+                    (&b"dest"[..], Self::address(dest)),
+                    (
+                        &b"keep_alive"[..],
+                        if *keep_alive {
+                            b"yes".to_vec()
+                        } else {
+                            b"no".to_vec()
+                        },
+                    ),
+                ])
+            }
+            _ => <text::HexArgs as text::RenderArgs<Call>>::render_args(call),
+        }
+    }
 }
 
 parameter_types! {
@@ -557,7 +666,8 @@ construct_runtime!(
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
         Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>},
         DappsStaking: pallet_dapps_staking::{Pallet, Call, Storage, Event<T>},
-        BlockReward: pallet_block_reward::{Pallet},
+        DappsSignalling: pallet_dapps_signalling::{Pallet, Call, Storage, Event<T>},
+        BlockReward: pallet_block_reward::{Pallet, Call, Storage, Config<T>, Event<T>},
         TransactionPayment: pallet_transaction_payment::{Pallet, Storage},
         EVM: pallet_evm::{Pallet, Config, Call, Storage, Event<T>},
         Ethereum: pallet_ethereum::{Pallet, Call, Storage, Event, Origin, Config},
@@ -614,8 +724,28 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPallets,
+    (
+        pallet_block_reward::migrations::MigrateRewardAmountToStorage<Runtime, InitialRewardAmount>,
+        pallet_block_reward::migrations::MigrateRewardDistributionToStorage<
+            Runtime,
+            InitialRewardDistribution,
+        >,
+    ),
 >;
 
+/// Returns the contract address called by the Ethereum transaction, if any.
+fn evm_call_target(call: &Call) -> Option<H160> {
+    match call {
+        Call::Ethereum(pallet_ethereum::Call::transact { transaction }) => {
+            match transaction.action {
+                pallet_ethereum::TransactionAction::Call(to) => Some(to),
+                pallet_ethereum::TransactionAction::Create => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 impl fp_self_contained::SelfContainedCall for Call {
     type SignedInfo = H160;
 
@@ -655,9 +785,24 @@ impl fp_self_contained::SelfContainedCall for Call {
         info: Self::SignedInfo,
     ) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
         match self {
-            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => Some(call.dispatch(
-                Origin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info)),
-            )),
+            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => {
+                let target = evm_call_target(&call);
+                let events = EventsMark::<Runtime>::current();
+                let result = call.dispatch(Origin::from(
+                    pallet_ethereum::RawOrigin::EthereumTransaction(info),
+                ));
+
+                // Calls to registered contracts which didn't revert are counted for dapps staking usage rewards.
+                if let (Ok(_), Some(contract)) = (&result, target) {
+                    if ethereum_transaction_succeeded(&events.events_since()) {
+                        let caller =
+                            <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(info);
+                        DappsStaking::note_contract_usage(&SmartContract::Evm(contract), &caller);
+                    }
+                }
+
+                Some(result)
+            }
             _ => None,
         }
     }
@@ -794,6 +939,22 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_custom_signatures_rpc_runtime_api::CustomSignaturesApi<
+        Block,
+        Index,
+        Balance,
+    > for Runtime {
+        fn signing_payload(call: Vec<u8>) -> Result<
+            pallet_custom_signatures::SigningPayload<Index, Balance>,
+            pallet_custom_signatures::PayloadError,
+        > {
+            EthCall::signing_payload(call)
+        }
+        fn dry_run(call: Vec<u8>) -> Result<(), pallet_custom_signatures::PayloadError> {
+            EthCall::dry_run(call)
+        }
+    }
+
     impl fp_rpc::EthereumRuntimeRPCApi<Block> for Runtime {
         fn chain_id() -> u64 {
             ChainId::get()
@@ -966,7 +1127,9 @@ impl_runtime_apis! {
 
             let mut list = Vec::<BenchmarkList>::new();
 
+            list_benchmark!(list, extra, pallet_block_reward, BlockReward);
             list_benchmark!(list, extra, pallet_dapps_staking, DappsStaking);
+            list_benchmark!(list, extra, pallet_dapps_signalling, DappsSignalling);
 
             let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -998,7 +1161,9 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
             add_benchmark!(params, batches, pallet_balances, Balances);
             add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+            add_benchmark!(params, batches, pallet_block_reward, BlockReward);
             add_benchmark!(params, batches, pallet_dapps_staking, DappsStaking);
+            add_benchmark!(params, batches, pallet_dapps_signalling, DappsSignalling);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
             Ok(batches)
@@ -1017,3 +1182,60 @@ impl_runtime_apis! {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_system::{EventRecord, Phase};
+    use pallet_evm::{ExitReason, ExitRevert, ExitSucceed};
+
+    fn executed(reason: ExitReason) -> EventRecord<Event, Hash> {
+        EventRecord {
+            phase: Phase::ApplyExtrinsic(0),
+            event: Event::Ethereum(pallet_ethereum::Event::Executed(
+                H160::repeat_byte(0x01),
+                H160::repeat_byte(0x02),
+                H256::zero(),
+                reason,
+            )),
+            topics: vec![],
+        }
+    }
+
+    #[test]
+    fn reverted_ethereum_transaction_is_not_successful() {
+        let succeeded = executed(ExitReason::Succeed(ExitSucceed::Returned));
+        let reverted = executed(ExitReason::Revert(ExitRevert::Reverted));
+
+        assert!(ethereum_transaction_succeeded(&[succeeded.clone()]));
+        assert!(!ethereum_transaction_succeeded(&[reverted.clone()]));
+
+        // Only the last executed transaction is considered.
+        assert!(!ethereum_transaction_succeeded(&[
+            succeeded.clone(),
+            reverted.clone()
+        ]));
+        assert!(ethereum_transaction_succeeded(&[reverted, succeeded]));
+
+        assert!(!ethereum_transaction_succeeded::<Event, Hash>(&[]));
+    }
+
+    #[test]
+    fn signed_call_args_are_named() {
+        use pallet_custom_signatures::text::RenderArgs;
+
+        let transfer = Call::Balances(pallet_balances::Call::transfer_keep_alive {
+            dest: Address::Id(AccountId::from([1u8; 32])),
+            value: 1_000,
+        });
+        assert_eq!(
+            SignedCallArgs::render_args(&transfer),
+            b"dest 0x0101010101010101010101010101010101010101010101010101010101010101, value 1000"
+                .to_vec()
+        );
+
+        // Other calls are rendered as hex
+        let remark = Call::System(frame_system::Call::remark { remark: vec![0xab] });
+        assert_eq!(SignedCallArgs::render_args(&remark), b"0x04ab".to_vec());
+    }
+}
@@ -4,10 +4,11 @@
 // `construct_runtime!` does a lot of recursion and requires us to increase the limit to 256.
 #![recursion_limit = "256"]
 
+use astar_runtime_common::{ethereum_transaction_succeeded, EventsMark};
 use codec::{Decode, Encode};
 use frame_support::{
     construct_runtime, parameter_types,
-    traits::{Contains, Currency, FindAuthor, Imbalance, OnUnbalanced},
+    traits::{Contains, Currency, FindAuthor, Get, Imbalance, OnUnbalanced},
     weights::{
         constants::{BlockExecutionWeight, ExtrinsicBaseWeight, WEIGHT_PER_SECOND},
         DispatchClass, Weight, WeightToFeeCoefficient, WeightToFeeCoefficients,
@@ -16,7 +17,9 @@ use frame_support::{
     ConsensusEngineId, PalletId,
 };
 use frame_system::limits::{BlockLength, BlockWeights};
-use pallet_evm::{FeeCalculator, Runner};
+use pallet_block_reward::RewardDistributionConfig;
+use pallet_custom_signatures::text;
+use pallet_evm::{AddressMapping, FeeCalculator, Runner};
 use pallet_transaction_payment::{
     FeeDetails, Multiplier, RuntimeDispatchInfo, TargetedFeeAdjustment,
 };
@@ -30,9 +33,7 @@ use sp_runtime::{
         AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
         Dispatchable, OpaqueKeys, PostDispatchInfoOf, Verify,
     },
-    transaction_validity::{
-        TransactionPriority, TransactionSource, TransactionValidity, TransactionValidityError,
-    },
+    transaction_validity::{TransactionSource, TransactionValidity, TransactionValidityError},
     ApplyExtrinsicResult, FixedPointNumber, Perbill, Perquintill, RuntimeDebug,
 };
 use sp_std::prelude::*;
@@ -249,21 +250,77 @@ impl pallet_multisig::Config for Runtime {
 }
 
 parameter_types! {
-    pub const EcdsaUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
-    pub const CallFee: Balance = SDN / 10;
     pub const CallMagicNumber: u16 = 0xff51;
+    pub const MaxBatchCalls: u32 = 16;
+    /// Chain name shown by the wallets in the EIP-712 domain and the text of signed calls.
+    pub const SignedCallChainName: &'static str = "Shibuya";
+    pub CustomSignaturesFeeMultiplier: Multiplier = TransactionPayment::next_fee_multiplier();
 }
 
 impl pallet_custom_signatures::Config for Runtime {
     type Event = Event;
     type Call = Call;
-    type Signature = pallet_custom_signatures::ethereum::EthereumSignature;
+    // Foreign wallet signatures are enabled on test networks only, Astar and Shiden accept
+    // Ethereum signatures until these are proven here
+    type Signature = pallet_custom_signatures::foreign::ForeignSignature;
     type Signer = <Signature as Verify>::Signer;
+    type SignedMessage = (
+        pallet_custom_signatures::ScaleMessage,
+        pallet_custom_signatures::eip712::Eip712Message<SignedCallChainName, ChainId>,
+        pallet_custom_signatures::text::TextMessage<SignedCallChainName, SignedCallArgs>,
+    );
     type CallMagicNumber = CallMagicNumber;
+    type MaxBatchCalls = MaxBatchCalls;
     type Currency = Balances;
-    type CallFee = CallFee;
+    type WeightToFee = WeightToFee;
+    type TransactionByteFee = TransactionByteFee;
+    type FeeMultiplier = CustomSignaturesFeeMultiplier;
     type OnChargeTransaction = ToStakingPot;
-    type UnsignedPriority = EcdsaUnsignedPriority;
+}
+
+/// Renders the arguments of common calls by name in the text of signed calls, other calls
+/// are rendered as hex.
+pub struct SignedCallArgs;
+
+impl SignedCallArgs {
+    fn address(address: &Address) -> Vec<u8> {
+        match address {
+            sp_runtime::MultiAddress::Id(account) => text::hex_string(account.as_ref()),
+            _ => text::hex_string(&address.encode()),
+        }
+    }
+
+    fn balance(balance: Balance) -> Vec<u8> {
+        pallet_custom_signatures::eip712::decimal_string(balance)
+    }
+}
+
+impl text::RenderArgs<Call> for SignedCallArgs {
+    fn render_args(call: &Call) -> Vec<u8> {
+        match call {
+            Call::Balances(pallet_balances::Call::transfer { dest, value })
+            | Call::Balances(pallet_balances::Call::transfer_keep_alive { dest, value }) => {
+                text::render_fields(&[
+                    (&b"dest"[..], Self::address(dest)),
+                    (&b"value"[..], Self::balance(*value)),
+                ])
+            }
+            Call::Balances(pallet_balances::Call::transfer_all { dest, keep_alive }) => {
+                text::render_fields(&[
+                    (&b"dest"[..], Self::address(dest)),
+                    (
+                        &b"keep_alive"[..],
+                        if *keep_alive {
+                            b"yes".to_vec()
+                        } else {
+                            b"no".to_vec()
+                        },
+                    ),
+                ])
+            }
+            _ => <text::HexArgs as text::RenderArgs<Call>>::render_args(call),
+        }
+    }
 }
 
 parameter_types! {
@@ -273,6 +330,10 @@ parameter_types! {
     pub const MaxNumberOfStakersPerContract: u32 = 2048;
     pub const MinimumStakingAmount: Balance = 5 * SDN;
     pub const MinimumRemainingAmount: Balance = 1 * SDN;
+    pub const MaxStakingTargetsPerCall: u32 = 16;
+    pub const MaxStakedContractsPerStaker: u32 = 100;
+    pub const MaxTopContracts: u32 = 50;
+    pub const UsageRewardPercentage: Perbill = Perbill::from_percent(10);
     pub const HistoryDepth: u32 = 60;
     pub const BonusEraDuration: u32 = 600;
 }
@@ -289,10 +350,29 @@ impl pallet_dapps_staking::Config for Runtime {
     type MinimumStakingAmount = MinimumStakingAmount;
     type PalletId = DappsStakingPalletId;
     type MinimumRemainingAmount = MinimumRemainingAmount;
+    type MaxStakingTargetsPerCall = MaxStakingTargetsPerCall;
+    type MaxStakedContractsPerStaker = MaxStakedContractsPerStaker;
+    type MaxTopContracts = MaxTopContracts;
+    type Hooks = ();
+    type UsageRewardPercentage = UsageRewardPercentage;
     type HistoryDepth = HistoryDepth;
     type BonusEraDuration = BonusEraDuration;
 }
 
+parameter_types! {
+    pub const MaxPollOptions: u32 = 16;
+}
+
+impl pallet_dapps_signalling::Config for Runtime {
+    type Event = Event;
+    type SmartContract = SmartContract<AccountId>;
+    type Balance = Balance;
+    type StakingInfo = DappsStaking;
+    type PollOrigin = frame_system::EnsureRoot<AccountId>;
+    type MaxPollOptions = MaxPollOptions;
+    type WeightInfo = pallet_dapps_signalling::weights::SubstrateWeight<Runtime>;
+}
+
 /// Multi-VM pointer to smart contract instance.
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, RuntimeDebug, scale_info::TypeInfo)]
 pub enum SmartContract<AccountId> {
@@ -372,7 +452,7 @@ impl pallet_authorship::Config for Runtime {
     type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
     type UncleGenerations = UncleGenerations;
     type FilterUncle = ();
-    type EventHandler = (CollatorSelection,);
+    type EventHandler = (CollatorSelection, BlockReward);
 }
 
 parameter_types! {
@@ -430,30 +510,57 @@ impl OnUnbalanced<NegativeImbalance> for ToStakingPot {
     }
 }
 
-pub struct OnBlockReward;
-impl OnUnbalanced<NegativeImbalance> for OnBlockReward {
+pub struct ToTreasury;
+impl OnUnbalanced<NegativeImbalance> for ToTreasury {
     fn on_nonzero_unbalanced(amount: NegativeImbalance) {
-        let (dapps, maintain) = amount.ration(50, 50);
+        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), amount);
+    }
+}
 
-        // dapp staking block reward
-        DappsStaking::on_unbalanced(dapps);
+/// Provides the total amount staked in dapps staking to the block reward pallet.
+pub struct DappsStakingTvl;
+impl pallet_block_reward::StakedAmountProvider<Balance> for DappsStakingTvl {
+    fn total_staked() -> Balance {
+        <DappsStaking as pallet_dapps_staking::DappsStakingInfo<
+            AccountId,
+            SmartContract<AccountId>,
+            Balance,
+        >>::total_staked()
+    }
+}
 
-        let (treasury, collators) = maintain.ration(40, 10);
-        // treasury slice of block reward
-        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), treasury);
-        // collators block reward
-        ToStakingPot::on_unbalanced(collators);
+/// Provides the dapps staking era to the block reward pallet.
+pub struct DappsStakingEra;
+impl Get<pallet_block_reward::EraIndex> for DappsStakingEra {
+    fn get() -> pallet_block_reward::EraIndex {
+        DappsStaking::current_era()
     }
 }
 
 parameter_types! {
-    pub const RewardAmount: Balance = 2_664 * MILLISDN;
+    /// Block reward used until the reward amount was moved to storage.
+    pub const InitialRewardAmount: Balance = 2_664 * MILLISDN;
+    /// Block reward split used until the distribution was moved to storage.
+    pub InitialRewardDistribution: RewardDistributionConfig = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(40),
+        dapps_percent: Perbill::from_percent(50),
+        collators_percent: Perbill::from_percent(10),
+        burn_percent: Perbill::zero(),
+    };
 }
 
 impl pallet_block_reward::Config for Runtime {
     type Currency = Balances;
-    type OnBlockReward = OnBlockReward;
-    type RewardAmount = RewardAmount;
+    type TreasuryPayout = ToTreasury;
+    type DappsStakingPayout = DappsStaking;
+    type CollatorsPayout = ToStakingPot;
+    type DappsStakingTvl = DappsStakingTvl;
+    type RewardCalculator = pallet_block_reward::FixedReward;
+    type CurrentEra = DappsStakingEra;
+    type HistoryDepth = HistoryDepth;
+    type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+    type Event = Event;
+    type WeightInfo = pallet_block_reward::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -679,8 +786,9 @@ construct_runtime!(
         TransactionPayment: pallet_transaction_payment::{Pallet, Storage} = 30,
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>} = 31,
         Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>} = 32,
-        BlockReward: pallet_block_reward::{Pallet} = 33,
+        BlockReward: pallet_block_reward::{Pallet, Call, Storage, Config<T>, Event<T>} = 33,
         DappsStaking: pallet_dapps_staking::{Pallet, Call, Storage, Event<T>} = 34,
+        DappsSignalling: pallet_dapps_signalling::{Pallet, Call, Storage, Event<T>} = 35,
 
         Authorship: pallet_authorship::{Pallet, Call, Storage, Inherent} = 40,
         CollatorSelection: pallet_collator_selection::{Pallet, Call, Storage, Event<T>, Config<T>} = 41,
@@ -742,8 +850,28 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPallets,
+    (
+        pallet_block_reward::migrations::MigrateRewardAmountToStorage<Runtime, InitialRewardAmount>,
+        pallet_block_reward::migrations::MigrateRewardDistributionToStorage<
+            Runtime,
+            InitialRewardDistribution,
+        >,
+    ),
 >;
 
+/// Returns the contract address called by the Ethereum transaction, if any.
+fn evm_call_target(call: &Call) -> Option<H160> {
+    match call {
+        Call::Ethereum(pallet_ethereum::Call::transact { transaction }) => {
+            match transaction.action {
+                pallet_ethereum::TransactionAction::Call(to) => Some(to),
+                pallet_ethereum::TransactionAction::Create => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 impl fp_self_contained::SelfContainedCall for Call {
     type SignedInfo = H160;
 
@@ -783,9 +911,24 @@ impl fp_self_contained::SelfContainedCall for Call {
         info: Self::SignedInfo,
     ) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
         match self {
-            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => Some(call.dispatch(
-                Origin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info)),
-            )),
+            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => {
+                let target = evm_call_target(&call);
+                let events = EventsMark::<Runtime>::current();
+                let result = call.dispatch(Origin::from(
+                    pallet_ethereum::RawOrigin::EthereumTransaction(info),
+                ));
+
+                // Calls to registered contracts which didn't revert are counted for dapps staking usage rewards.
+                if let (Ok(_), Some(contract)) = (&result, target) {
+                    if ethereum_transaction_succeeded(&events.events_since()) {
+                        let caller =
+                            <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(info);
+                        DappsStaking::note_contract_usage(&SmartContract::Evm(contract), &caller);
+                    }
+                }
+
+                Some(result)
+            }
             _ => None,
         }
     }
@@ -874,6 +1017,22 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_custom_signatures_rpc_runtime_api::CustomSignaturesApi<
+        Block,
+        Index,
+        Balance,
+    > for Runtime {
+        fn signing_payload(call: Vec<u8>) -> Result<
+            pallet_custom_signatures::SigningPayload<Index, Balance>,
+            pallet_custom_signatures::PayloadError,
+        > {
+            EthCall::signing_payload(call)
+        }
+        fn dry_run(call: Vec<u8>) -> Result<(), pallet_custom_signatures::PayloadError> {
+            EthCall::dry_run(call)
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             SessionKeys::generate(seed)
@@ -1029,7 +1188,9 @@ impl_runtime_apis! {
 
             let mut list = Vec::<BenchmarkList>::new();
 
+            list_benchmark!(list, extra, pallet_block_reward, BlockReward);
             list_benchmark!(list, extra, pallet_dapps_staking, DappsStaking);
+            list_benchmark!(list, extra, pallet_dapps_signalling, DappsSignalling);
 
             let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -1061,7 +1222,9 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
             add_benchmark!(params, batches, pallet_balances, Balances);
             add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+            add_benchmark!(params, batches, pallet_block_reward, BlockReward);
             add_benchmark!(params, batches, pallet_dapps_staking, DappsStaking);
+            add_benchmark!(params, batches, pallet_dapps_signalling, DappsSignalling);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
             Ok(batches)
@@ -1072,11 +1235,14 @@ impl_runtime_apis! {
     impl frame_try_runtime::TryRuntime<Block> for Runtime {
         fn on_runtime_upgrade() -> (Weight, Weight) {
             let weight = Executive::try_runtime_upgrade().unwrap();
+            DappsStaking::ensure_storage_invariants().unwrap();
             (weight, RuntimeBlockWeights::get().max_block)
         }
 
         fn execute_block_no_check(block: Block) -> Weight {
-            Executive::execute_block_no_check(block)
+            let weight = Executive::execute_block_no_check(block);
+            DappsStaking::ensure_storage_invariants().unwrap();
+            weight
         }
     }
 }
@@ -46,19 +46,22 @@ impl<T: frame_system::Config> pallet_dapps_staking::WeightInfo for WeightInfo<T>
 	}
 	// Storage: DappsStaking RegisteredDevelopers (r:1 w:1)
 	// Storage: DappsStaking CurrentEra (r:1 w:0)
-	// Storage: DappsStaking ContractEraStake (r:3 w:0)
+	// Storage: DappsStaking ContractEraStake (r:3 w:2)
 	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:1)
 	// Storage: System Account (r:1 w:1)
 	// Storage: DappsStaking Ledger (r:25 w:25)
 	// Storage: Balances Locks (r:25 w:25)
+	// Storage: DappsStaking StakerPositions (r:25 w:25)
+	// Storage: DappsStaking StakingReceipts (r:0 w:25)
+	// Storage: DappsStaking ApproxTopContracts (r:1 w:1)
 	fn unregister(n: u32, ) -> Weight {
 		(0 as Weight)
 			// Standard Error: 81_000
 			.saturating_add((43_853_000 as Weight).saturating_mul(n as Weight))
-			.saturating_add(T::DbWeight::get().reads(5 as Weight))
-			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
-			.saturating_add(T::DbWeight::get().writes(3 as Weight))
-			.saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().reads((4 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes((5 as Weight).saturating_mul(n as Weight)))
 	}
 	// Storage: DappsStaking PreApprovalIsEnabled (r:0 w:1)
 	fn enable_developer_pre_approval() -> Weight {
@@ -76,39 +79,127 @@ impl<T: frame_system::Config> pallet_dapps_staking::WeightInfo for WeightInfo<T>
 	// Storage: DappsStaking Ledger (r:1 w:1)
 	// Storage: DappsStaking CurrentEra (r:1 w:0)
 	// Storage: DappsStaking ContractEraStake (r:1 w:1)
+	// Storage: DappsStaking StakerPositions (r:1 w:1)
 	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:1)
 	// Storage: Balances Locks (r:1 w:1)
+	// Storage: DappsStaking ApproxTopContracts (r:1 w:1)
 	fn bond_and_stake() -> Weight {
 		(337_755_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(7 as Weight))
-			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+			.saturating_add(T::DbWeight::get().reads(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
 	}
 	// Storage: DappsStaking RegisteredDapps (r:1 w:0)
+	// Storage: DappsStaking RegisteredDevelopers (r:1 w:0)
 	// Storage: DappsStaking CurrentEra (r:1 w:0)
-	// Storage: DappsStaking ContractEraStake (r:3 w:1)
+	// Storage: DappsStaking ContractEraStake (r:3 w:2)
+	// Storage: DappsStaking StakerPositions (r:1 w:1)
 	// Storage: DappsStaking Ledger (r:1 w:1)
 	// Storage: Balances Locks (r:1 w:1)
-	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:1)
+	// Storage: DappsStaking EraRewardsAndStakes (r:2 w:2)
+	// Storage: DappsStaking ApproxTopContracts (r:1 w:1)
+	// Storage: DappsStaking StakingReceipts (r:0 w:1)
 	fn unbond_unstake_and_withdraw() -> Weight {
 		(403_138_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(8 as Weight))
-			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+			.saturating_add(T::DbWeight::get().reads(12 as Weight))
+			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+	}
+	// Storage: DappsStaking Ledger (r:1 w:1)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking RegisteredDapps (r:16 w:0)
+	// Storage: DappsStaking RegisteredDevelopers (r:16 w:0)
+	// Storage: DappsStaking ContractEraStake (r:16 w:16)
+	// Storage: DappsStaking StakerPositions (r:1 w:1)
+	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:1)
+	// Storage: Balances Locks (r:1 w:1)
+	// Storage: DappsStaking ApproxTopContracts (r:1 w:1)
+	fn bond_and_stake_multi(n: u32, ) -> Weight {
+		(68_115_000 as Weight)
+			.saturating_add((312_482_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+			.saturating_add(T::DbWeight::get().writes((1 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking StakerPositions (r:1 w:1)
+	// Storage: DappsStaking ContractEraStake (r:32 w:32)
+	// Storage: DappsStaking ApproxTopContracts (r:1 w:1)
+	// Storage: DappsStaking StakingReceipts (r:0 w:16)
+	// Storage: DappsStaking Ledger (r:1 w:1)
+	// Storage: Balances Locks (r:1 w:1)
+	// Storage: DappsStaking EraRewardsAndStakes (r:2 w:2)
+	fn unbond_all(n: u32, ) -> Weight {
+		(95_402_000 as Weight)
+			.saturating_add((341_937_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
 	}
 	// Storage: DappsStaking RegisteredDapps (r:1 w:0)
 	// Storage: DappsStaking CurrentEra (r:1 w:0)
 	// Storage: DappsStaking ContractEraStake (r:1 w:1)
+	// Storage: DappsStaking ContractEraUsage (r:1 w:0)
 	// Storage: DappsStaking EraRewardsAndStakes (r:1 w:0)
+	// Storage: DappsStaking EraUsage (r:1 w:0)
 	// Storage: Balances TotalIssuance (r:1 w:1)
 	fn claim(n: u32, ) -> Weight {
 		(84_644_000 as Weight)
 			// Standard Error: 30_000
 			.saturating_add((9_213_000 as Weight).saturating_mul(n as Weight))
-			.saturating_add(T::DbWeight::get().reads(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
 			.saturating_add(T::DbWeight::get().writes(2 as Weight))
 	}
+	// Storage: DappsStaking StakingReceiptsAreEnabled (r:0 w:1)
+	fn enable_staking_receipts() -> Weight {
+		(3_104_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: DappsStaking StakingReceiptsAreEnabled (r:1 w:0)
+	// Storage: DappsStaking RegisteredDapps (r:1 w:0)
+	// Storage: DappsStaking RegisteredDevelopers (r:1 w:0)
+	// Storage: DappsStaking StakingReceipts (r:1 w:1)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking ContractEraStake (r:1 w:0)
+	fn mint_staking_receipt() -> Weight {
+		(29_563_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: DappsStaking StakingReceipts (r:1 w:2)
+	// Storage: DappsStaking RegisteredDapps (r:1 w:0)
+	// Storage: DappsStaking RegisteredDevelopers (r:1 w:0)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking ContractEraStake (r:2 w:2)
+	// Storage: DappsStaking Ledger (r:2 w:2)
+	// Storage: Balances Locks (r:2 w:2)
+	// Storage: System Account (r:2 w:2)
+	// Storage: DappsStaking StakerPositions (r:2 w:2)
+	fn transfer_staking_receipt() -> Weight {
+		(142_871_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(14 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: DappsStaking StakingReceipts (r:1 w:1)
+	fn burn_staking_receipt() -> Weight {
+		(10_228_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
 	// Storage: DappsStaking ForceEra (r:0 w:1)
 	fn force_new_era() -> Weight {
 		(3_132_000 as Weight)
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
+	// Storage: DappsStaking RegisteredDapps (r:1 w:0)
+	// Storage: DappsStaking RegisteredDevelopers (r:1 w:0)
+	// Storage: DappsStaking CurrentEra (r:1 w:0)
+	// Storage: DappsStaking ContractUsageCallers (r:1 w:1)
+	// Storage: DappsStaking ContractEraUsage (r:1 w:1)
+	// Storage: DappsStaking EraUsage (r:1 w:1)
+	fn note_contract_usage() -> Weight {
+		(21_405_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(6 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
 }
@@ -4,10 +4,11 @@
 // `construct_runtime!` does a lot of recursion and requires us to increase the limit to 256.
 #![recursion_limit = "256"]
 
+use astar_runtime_common::{ethereum_transaction_succeeded, EventsMark};
 use codec::{Decode, Encode};
 use frame_support::{
     construct_runtime, parameter_types,
-    traits::{Contains, Currency, FindAuthor, Imbalance, OnUnbalanced},
+    traits::{Contains, Currency, FindAuthor, Get, Imbalance, OnUnbalanced},
     weights::{
         constants::{BlockExecutionWeight, ExtrinsicBaseWeight, WEIGHT_PER_SECOND},
         DispatchClass, Weight, WeightToFeeCoefficient, WeightToFeeCoefficients,
@@ -16,7 +17,8 @@ use frame_support::{
     ConsensusEngineId, PalletId,
 };
 use frame_system::limits::{BlockLength, BlockWeights};
-use pallet_evm::{FeeCalculator, Runner};
+use pallet_block_reward::RewardDistributionConfig;
+use pallet_evm::{AddressMapping, FeeCalculator, Runner};
 use pallet_transaction_payment::{
     FeeDetails, Multiplier, RuntimeDispatchInfo, TargetedFeeAdjustment,
 };
@@ -30,9 +32,7 @@ use sp_runtime::{
         AccountIdConversion, AccountIdLookup, BlakeTwo256, Block as BlockT, ConvertInto,
         Dispatchable, OpaqueKeys, PostDispatchInfoOf, Verify,
     },
-    transaction_validity::{
-        TransactionPriority, TransactionSource, TransactionValidity, TransactionValidityError,
-    },
+    transaction_validity::{TransactionSource, TransactionValidity, TransactionValidityError},
     ApplyExtrinsicResult, FixedPointNumber, Perbill, Perquintill, RuntimeDebug,
 };
 use sp_std::prelude::*;
@@ -248,21 +248,25 @@ impl pallet_multisig::Config for Runtime {
 }
 
 parameter_types! {
-    pub const EcdsaUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
-    pub const CallFee: Balance = SDN / 10;
     pub const CallMagicNumber: u16 = 0x0150;
+    pub const MaxBatchCalls: u32 = 16;
+    pub CustomSignaturesFeeMultiplier: Multiplier = TransactionPayment::next_fee_multiplier();
 }
 
 impl pallet_custom_signatures::Config for Runtime {
     type Event = Event;
     type Call = Call;
+    // Foreign wallet signatures are enabled on Shibuya first
     type Signature = pallet_custom_signatures::ethereum::EthereumSignature;
     type Signer = <Signature as Verify>::Signer;
+    type SignedMessage = pallet_custom_signatures::ScaleMessage;
     type CallMagicNumber = CallMagicNumber;
+    type MaxBatchCalls = MaxBatchCalls;
     type Currency = Balances;
-    type CallFee = CallFee;
+    type WeightToFee = WeightToFee;
+    type TransactionByteFee = TransactionByteFee;
+    type FeeMultiplier = CustomSignaturesFeeMultiplier;
     type OnChargeTransaction = ToStakingPot;
-    type UnsignedPriority = EcdsaUnsignedPriority;
 }
 
 parameter_types! {
@@ -272,6 +276,10 @@ parameter_types! {
     pub const MaxNumberOfStakersPerContract: u32 = 512;
     pub const MinimumStakingAmount: Balance = 50 * SDN;
     pub const MinimumRemainingAmount: Balance = 1 * SDN;
+    pub const MaxStakingTargetsPerCall: u32 = 16;
+    pub const MaxStakedContractsPerStaker: u32 = 100;
+    pub const MaxTopContracts: u32 = 50;
+    pub const UsageRewardPercentage: Perbill = Perbill::from_percent(0);
     pub const HistoryDepth: u32 = 14;
     pub const BonusEraDuration: u32 = 10;
 }
@@ -288,6 +296,11 @@ impl pallet_dapps_staking::Config for Runtime {
     type MinimumStakingAmount = MinimumStakingAmount;
     type PalletId = DappsStakingPalletId;
     type MinimumRemainingAmount = MinimumRemainingAmount;
+    type MaxStakingTargetsPerCall = MaxStakingTargetsPerCall;
+    type MaxStakedContractsPerStaker = MaxStakedContractsPerStaker;
+    type MaxTopContracts = MaxTopContracts;
+    type Hooks = ();
+    type UsageRewardPercentage = UsageRewardPercentage;
     type HistoryDepth = HistoryDepth;
     type BonusEraDuration = BonusEraDuration;
 }
@@ -371,7 +384,7 @@ impl pallet_authorship::Config for Runtime {
     type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
     type UncleGenerations = UncleGenerations;
     type FilterUncle = ();
-    type EventHandler = (CollatorSelection,);
+    type EventHandler = (CollatorSelection, BlockReward);
 }
 
 parameter_types! {
@@ -429,29 +442,57 @@ impl OnUnbalanced<NegativeImbalance> for ToStakingPot {
     }
 }
 
-pub struct OnBlockReward;
-impl OnUnbalanced<NegativeImbalance> for OnBlockReward {
+pub struct ToTreasury;
+impl OnUnbalanced<NegativeImbalance> for ToTreasury {
     fn on_nonzero_unbalanced(amount: NegativeImbalance) {
-        let (dapps, maintain) = amount.ration(50, 50);
-        // dapp staking block reward
-        DappsStaking::on_unbalanced(dapps);
-
-        let (treasury, collators) = maintain.ration(40, 10);
-        // treasury slice of block reward
-        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), treasury);
-        // collators block reward
-        ToStakingPot::on_unbalanced(collators);
+        Balances::resolve_creating(&TreasuryPalletId::get().into_account(), amount);
+    }
+}
+
+/// Provides the total amount staked in dapps staking to the block reward pallet.
+pub struct DappsStakingTvl;
+impl pallet_block_reward::StakedAmountProvider<Balance> for DappsStakingTvl {
+    fn total_staked() -> Balance {
+        <DappsStaking as pallet_dapps_staking::DappsStakingInfo<
+            AccountId,
+            SmartContract<AccountId>,
+            Balance,
+        >>::total_staked()
+    }
+}
+
+/// Provides the dapps staking era to the block reward pallet.
+pub struct DappsStakingEra;
+impl Get<pallet_block_reward::EraIndex> for DappsStakingEra {
+    fn get() -> pallet_block_reward::EraIndex {
+        DappsStaking::current_era()
     }
 }
 
 parameter_types! {
-    pub const RewardAmount: Balance = 2_664 * MILLISDN;
+    /// Block reward used until the reward amount was moved to storage.
+    pub const InitialRewardAmount: Balance = 2_664 * MILLISDN;
+    /// Block reward split used until the distribution was moved to storage.
+    pub InitialRewardDistribution: RewardDistributionConfig = RewardDistributionConfig {
+        treasury_percent: Perbill::from_percent(40),
+        dapps_percent: Perbill::from_percent(50),
+        collators_percent: Perbill::from_percent(10),
+        burn_percent: Perbill::zero(),
+    };
 }
 
 impl pallet_block_reward::Config for Runtime {
     type Currency = Balances;
-    type OnBlockReward = OnBlockReward;
-    type RewardAmount = RewardAmount;
+    type TreasuryPayout = ToTreasury;
+    type DappsStakingPayout = DappsStaking;
+    type CollatorsPayout = ToStakingPot;
+    type DappsStakingTvl = DappsStakingTvl;
+    type RewardCalculator = pallet_block_reward::FixedReward;
+    type CurrentEra = DappsStakingEra;
+    type HistoryDepth = HistoryDepth;
+    type UpdateOrigin = frame_system::EnsureRoot<AccountId>;
+    type Event = Event;
+    type WeightInfo = pallet_block_reward::weights::SubstrateWeight<Runtime>;
 }
 
 parameter_types! {
@@ -677,7 +718,7 @@ construct_runtime!(
         TransactionPayment: pallet_transaction_payment::{Pallet, Storage} = 30,
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>} = 31,
         Vesting: pallet_vesting::{Pallet, Call, Storage, Config<T>, Event<T>} = 32,
-        BlockReward: pallet_block_reward::{Pallet} = 33,
+        BlockReward: pallet_block_reward::{Pallet, Call, Storage, Config<T>, Event<T>} = 33,
         DappsStaking: pallet_dapps_staking::{Pallet, Call, Storage, Event<T>} = 34,
 
         Authorship: pallet_authorship::{Pallet, Call, Storage, Inherent} = 40,
@@ -741,8 +782,28 @@ pub type Executive = frame_executive::Executive<
     frame_system::ChainContext<Runtime>,
     Runtime,
     AllPallets,
+    (
+        pallet_block_reward::migrations::MigrateRewardAmountToStorage<Runtime, InitialRewardAmount>,
+        pallet_block_reward::migrations::MigrateRewardDistributionToStorage<
+            Runtime,
+            InitialRewardDistribution,
+        >,
+    ),
 >;
 
+/// Returns the contract address called by the Ethereum transaction, if any.
+fn evm_call_target(call: &Call) -> Option<H160> {
+    match call {
+        Call::Ethereum(pallet_ethereum::Call::transact { transaction }) => {
+            match transaction.action {
+                pallet_ethereum::TransactionAction::Call(to) => Some(to),
+                pallet_ethereum::TransactionAction::Create => None,
+            }
+        }
+        _ => None,
+    }
+}
+
 impl fp_self_contained::SelfContainedCall for Call {
     type SignedInfo = H160;
 
@@ -782,9 +843,24 @@ impl fp_self_contained::SelfContainedCall for Call {
         info: Self::SignedInfo,
     ) -> Option<sp_runtime::DispatchResultWithInfo<PostDispatchInfoOf<Self>>> {
         match self {
-            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => Some(call.dispatch(
-                Origin::from(pallet_ethereum::RawOrigin::EthereumTransaction(info)),
-            )),
+            call @ Call::Ethereum(pallet_ethereum::Call::transact { .. }) => {
+                let target = evm_call_target(&call);
+                let events = EventsMark::<Runtime>::current();
+                let result = call.dispatch(Origin::from(
+                    pallet_ethereum::RawOrigin::EthereumTransaction(info),
+                ));
+
+                // Calls to registered contracts which didn't revert are counted for dapps staking usage rewards.
+                if let (Ok(_), Some(contract)) = (&result, target) {
+                    if ethereum_transaction_succeeded(&events.events_since()) {
+                        let caller =
+                            <Runtime as pallet_evm::Config>::AddressMapping::into_account_id(info);
+                        DappsStaking::note_contract_usage(&SmartContract::Evm(contract), &caller);
+                    }
+                }
+
+                Some(result)
+            }
             _ => None,
         }
     }
@@ -873,6 +949,22 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_custom_signatures_rpc_runtime_api::CustomSignaturesApi<
+        Block,
+        Index,
+        Balance,
+    > for Runtime {
+        fn signing_payload(call: Vec<u8>) -> Result<
+            pallet_custom_signatures::SigningPayload<Index, Balance>,
+            pallet_custom_signatures::PayloadError,
+        > {
+            EthCall::signing_payload(call)
+        }
+        fn dry_run(call: Vec<u8>) -> Result<(), pallet_custom_signatures::PayloadError> {
+            EthCall::dry_run(call)
+        }
+    }
+
     impl sp_session::SessionKeys<Block> for Runtime {
         fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
             SessionKeys::generate(seed)
@@ -1028,6 +1120,7 @@ impl_runtime_apis! {
 
             let mut list = Vec::<BenchmarkList>::new();
 
+            list_benchmark!(list, extra, pallet_block_reward, BlockReward);
             list_benchmark!(list, extra, pallet_dapps_staking, DappsStaking);
 
             let storage_info = AllPalletsWithSystem::storage_info();
@@ -1060,6 +1153,7 @@ impl_runtime_apis! {
             add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
             add_benchmark!(params, batches, pallet_balances, Balances);
             add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+            add_benchmark!(params, batches, pallet_block_reward, BlockReward);
             add_benchmark!(params, batches, pallet_dapps_staking, DappsStaking);
 
             if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
@@ -1071,11 +1165,14 @@ impl_runtime_apis! {
     impl frame_try_runtime::TryRuntime<Block> for Runtime {
         fn on_runtime_upgrade() -> (Weight, Weight) {
             let weight = Executive::try_runtime_upgrade().unwrap();
+            DappsStaking::ensure_storage_invariants().unwrap();
             (weight, RuntimeBlockWeights::get().max_block)
         }
 
         fn execute_block_no_check(block: Block) -> Weight {
-            Executive::execute_block_no_check(block)
+            let weight = Executive::execute_block_no_check(block);
+            DappsStaking::ensure_storage_invariants().unwrap();
+            weight
         }
     }
 }